@@ -9,15 +9,32 @@ use core::{
 use crossbeam_utils::Backoff;
 use either::Either;
 
-use crate::{common::*, error::*, ArenaOptions, Freelist};
+use crate::{
+  common::*, error::*, ArenaEvent, ArenaOptions, EventHook, Freelist, HighWaterHook, OomAction,
+  OomHook, UnknownFreelist,
+};
 
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 use crate::{MmapOptions, OpenOptions};
 
+#[cfg(all(feature = "memmap", target_family = "wasm"))]
+use crate::MmapOptions;
+
 #[allow(unused_imports)]
 use std::boxed::Box;
 
-const OVERHEAD: usize = mem::size_of::<Header>();
+/// The sanity bytes are stored before the header, and the header is aligned to its alignment.
+pub(crate) const OVERHEAD: usize = mem::align_of::<Header>() + mem::size_of::<Header>();
+/// The byte order marker, the header fields are native-endian, so a file can only be opened
+/// on a machine with the same byte order.
+const BYTE_ORDER_OFFSET: usize = 0;
+const LITTLE_ENDIAN: u8 = 1;
+const BIG_ENDIAN: u8 = 2;
+const NATIVE_ENDIAN: u8 = if cfg!(target_endian = "little") {
+  LITTLE_ENDIAN
+} else {
+  BIG_ENDIAN
+};
 const FREELIST_OFFSET: usize = 1;
 const FREELIST_SIZE: usize = mem::size_of::<Freelist>();
 const MAGIC_TEXT: [u8; 2] = *b"al";
@@ -27,24 +44,57 @@ const MAGIC_VERISON_OFFSET: usize = MAGIC_TEXT_OFFSET + MAGIC_TEXT_SIZE;
 const MAGIC_VERISON_SIZE: usize = mem::size_of::<u16>();
 const VERSION_OFFSET: usize = MAGIC_VERISON_OFFSET + MAGIC_VERISON_SIZE;
 const VERSION_SIZE: usize = mem::size_of::<u16>();
-const CURRENT_VERSION: u16 = 0;
+/// The version of the on-disk layout. All the header changes since `0.1.2` (the data offset, the timestamp, the
+/// generation, the root, the seqlock word, etc.) are covered by this single bump, the files written with
+/// version `0` cannot be upgraded in place because the data offset moved, so they are rejected.
+const CURRENT_VERSION: u16 = 1;
 
 const SEGMENT_NODE_SIZE: usize = mem::size_of::<SegmentNode>();
+/// The minimum alignment of the data offset, so that a segment node at the start of the data section
+/// never needs padding.
+pub(crate) const SEGMENT_NODE_ALIGNMENT: usize = mem::align_of::<SegmentNode>();
+/// The floor of the minimum segment size, a segment must be able to hold at least a node and a `u32`,
+/// otherwise the freelist is bloated by the tiny segments which can never be used.
+const MIN_SEGMENT_SIZE_FLOOR: u32 = (SEGMENT_NODE_SIZE + mem::size_of::<u32>()) as u32;
 const SENTINEL_SEGMENT_NODE_OFFSET: u32 = u32::MAX;
 const SENTINEL_SEGMENT_NODE_SIZE: u32 = u32::MAX;
 const REMOVED_SEGMENT_NODE: u32 = 0;
+/// The order of the smallest buddy block, which can hold a segment node when it is freed.
+const BUDDY_MIN_ORDER: u8 = SEGMENT_NODE_SIZE.trailing_zeros() as u8;
+const BUDDY_MAX_ORDER: u8 = 31;
 
 #[derive(Debug)]
 struct AlignedVec {
   ptr: ptr::NonNull<u8>,
   cap: usize,
   align: usize,
+  /// The allocator which allocates the buffer, `None` means the global allocator.
+  #[cfg(feature = "allocator-api")]
+  alloc: Option<VecAllocator>,
+}
+
+#[cfg(feature = "allocator-api")]
+struct VecAllocator(Box<dyn std::alloc::Allocator + Send + Sync>);
+
+#[cfg(feature = "allocator-api")]
+impl fmt::Debug for VecAllocator {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("VecAllocator")
+  }
 }
 
 impl Drop for AlignedVec {
   #[inline]
   fn drop(&mut self) {
     if self.cap != 0 {
+      #[cfg(feature = "allocator-api")]
+      if let Some(alloc) = &self.alloc {
+        unsafe {
+          alloc.0.deallocate(self.ptr, self.layout());
+        }
+        return;
+      }
+
       unsafe {
         dealloc(self.ptr.as_ptr(), self.layout());
       }
@@ -75,6 +125,35 @@ impl AlignedVec {
       ptr,
       cap: capacity,
       align,
+      #[cfg(feature = "allocator-api")]
+      alloc: None,
+    }
+  }
+
+  #[cfg(feature = "allocator-api")]
+  #[inline]
+  fn new_in<A>(capacity: usize, align: usize, alloc: A) -> Self
+  where
+    A: std::alloc::Allocator + Send + Sync + 'static,
+  {
+    let align = align.max(mem::align_of::<Header>());
+    assert!(
+      capacity <= Self::max_capacity(align),
+      "`capacity` cannot exceed isize::MAX - {}",
+      align - 1
+    );
+
+    let layout = unsafe { Layout::from_size_align_unchecked(capacity, align) };
+    let ptr = match alloc.allocate_zeroed(layout) {
+      Ok(ptr) => ptr.cast::<u8>(),
+      Err(_) => std::alloc::handle_alloc_error(layout),
+    };
+
+    Self {
+      ptr,
+      cap: capacity,
+      align,
+      alloc: Some(VecAllocator(Box::new(alloc))),
     }
   }
 
@@ -118,6 +197,24 @@ enum MemoryBackend {
     #[allow(dead_code)]
     buf: memmap2::MmapMut,
   },
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  MmapCow {
+    path: std::path::PathBuf,
+    #[allow(dead_code)]
+    buf: memmap2::MmapMut,
+    file: std::fs::File,
+  },
+}
+
+/// The reason of discarding bytes, used to maintain the breakdown of the discarded bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Discard {
+  /// The padding and the node of the freelist segments.
+  Padding,
+  /// The deallocated memory is too small to be a freelist segment.
+  SmallSegment,
+  /// Discarded by the user.
+  Manual,
 }
 
 #[derive(Debug)]
@@ -125,9 +222,71 @@ enum MemoryBackend {
 struct Header {
   /// The sentinel node for the ordered free list.
   sentinel: SegmentNode,
+  /// The last time (unix epoch in nanoseconds) the ARENA was flushed or unmounted, `0` if never.
+  last_modified: AtomicU64,
   allocated: AtomicU32,
   min_segment_size: AtomicU32,
   discarded: AtomicU32,
+  /// The offset of the data region, which is fixed once the ARENA is created.
+  data_offset: u32,
+  /// Non-zero if the high water hook has been fired.
+  high_water: AtomicU32,
+  /// The breakdown of `discarded`, only maintained when the `metrics` feature is enabled.
+  discarded_padding: AtomicU32,
+  discarded_small_segments: AtomicU32,
+  discarded_manual: AtomicU32,
+  /// Bumped by every [`Arena::clear`], so that the stale handles are detectable, see [`BytesRefMut::is_valid`].
+  generation: AtomicU32,
+  /// The user root offset, see [`Arena::set_root`], `0` if not set.
+  root: AtomicU64,
+  /// The seqlock of the header, see [`Arena::read_consistent`], the high 32 bits count the finished writes,
+  /// the low 32 bits count the writes in progress. Only bumped if [`ArenaOptions::with_seqlock`] is set.
+  seq: AtomicU64,
+}
+
+/// Added to [`Header::seq`] when a write finishes, i.e. one more finished write and one less write in progress.
+const SEQ_WRITE_DONE: u64 = (1 << 32) - 1;
+
+/// The statistics of the freelist walks and the slow path allocations, which are not persisted.
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct FreeListWalks {
+  visited: core::sync::atomic::AtomicU64,
+  max_depth: core::sync::atomic::AtomicU64,
+  slow_path_attempts: core::sync::atomic::AtomicU64,
+}
+
+/// Counts the nodes visited by a freelist walk, which is recorded when dropped.
+#[cfg(feature = "metrics")]
+struct WalkCounter<'a> {
+  walks: &'a FreeListWalks,
+  depth: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for WalkCounter<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    use core::sync::atomic::Ordering;
+
+    self.walks.visited.fetch_add(self.depth, Ordering::Relaxed);
+    self
+      .walks
+      .max_depth
+      .fetch_max(self.depth, Ordering::Relaxed);
+  }
+}
+
+/// A write of the header in progress, see [`Header::begin_write`].
+struct SeqWrite<'a> {
+  header: &'a Header,
+}
+
+impl Drop for SeqWrite<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    self.header.seq.fetch_add(SEQ_WRITE_DONE, Ordering::Release);
+  }
 }
 
 impl Header {
@@ -136,14 +295,103 @@ impl Header {
     Self {
       allocated: AtomicU32::new(size),
       sentinel: SegmentNode::sentinel(),
+      last_modified: AtomicU64::new(0),
       min_segment_size: AtomicU32::new(min_segment_size),
       discarded: AtomicU32::new(0),
+      data_offset: size,
+      high_water: AtomicU32::new(0),
+      discarded_padding: AtomicU32::new(0),
+      discarded_small_segments: AtomicU32::new(0),
+      discarded_manual: AtomicU32::new(0),
+      generation: AtomicU32::new(0),
+      root: AtomicU64::new(0),
+      seq: AtomicU64::new(0),
+    }
+  }
+
+  /// Returns a fresh header, which keeps the seqlock of this header, and bumps the generation.
+  #[inline]
+  fn cleared(&self, data_offset: u32) -> Self {
+    let header = Self::new(data_offset, self.min_segment_size.load(Ordering::Acquire));
+    header.generation.store(
+      self.generation.load(Ordering::Acquire).wrapping_add(1),
+      Ordering::Relaxed,
+    );
+    header
+      .seq
+      .store(self.seq.load(Ordering::Acquire), Ordering::Relaxed);
+    header
+  }
+
+  /// Returns a copy of this header, the writes in progress are not copied.
+  #[inline]
+  fn duplicate(&self) -> Self {
+    let header = Self::new(
+      self.data_offset,
+      self.min_segment_size.load(Ordering::Acquire),
+    );
+    header
+      .sentinel
+      .store(self.sentinel.load(Ordering::Acquire), Ordering::Relaxed);
+    header.last_modified.store(
+      self.last_modified.load(Ordering::Acquire),
+      Ordering::Relaxed,
+    );
+    header
+      .root
+      .store(self.root.load(Ordering::Acquire), Ordering::Relaxed);
+    for (dst, src) in [
+      (&header.allocated, &self.allocated),
+      (&header.discarded, &self.discarded),
+      (&header.high_water, &self.high_water),
+      (&header.discarded_padding, &self.discarded_padding),
+      (
+        &header.discarded_small_segments,
+        &self.discarded_small_segments,
+      ),
+      (&header.discarded_manual, &self.discarded_manual),
+      (&header.generation, &self.generation),
+    ] {
+      dst.store(src.load(Ordering::Acquire), Ordering::Relaxed);
+    }
+    header.seq.store(
+      self.seq.load(Ordering::Acquire) & !(u32::MAX as u64),
+      Ordering::Relaxed,
+    );
+    header
+  }
+
+  /// Marks a write of `allocated` or the freelist in progress, until the returned guard is dropped.
+  ///
+  /// Multiple writers may be in progress at the same time, as the allocations are lock-free.
+  #[inline]
+  fn begin_write(&self) -> SeqWrite<'_> {
+    self.seq.fetch_add(1, Ordering::AcqRel);
+    SeqWrite { header: self }
+  }
+
+  /// Resets the writes in progress, which are left by a process crashed in the middle of a write.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn reset_writes_in_progress(&self) {
+    self.seq.fetch_and(!(u32::MAX as u64), Ordering::AcqRel);
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn touch(&self) {
+    if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+      self
+        .last_modified
+        .store(now.as_nanos() as u64, Ordering::Release);
     }
   }
 }
 
 struct Memory {
   refs: AtomicUsize,
+  #[cfg(feature = "metrics")]
+  walks: FreeListWalks,
   cap: u32,
   data_offset: usize,
   header_ptr: Either<*mut u8, Header>,
@@ -156,13 +404,45 @@ struct Memory {
   freelist: Freelist,
 }
 
+/// The first mismatch found in the sanity bytes, see [`Memory::check_sanity_bytes`].
+#[cfg_attr(
+  not(all(feature = "memmap", not(target_family = "wasm"))),
+  allow(dead_code)
+)]
+enum SanityError {
+  ByteOrder,
+  Freelist(UnknownFreelist),
+  FreelistMismatch,
+  /// The stored magic version.
+  MagicVersion(u16),
+  /// The stored version.
+  Version(u16),
+  Magic,
+}
+
 impl Memory {
+  #[inline]
+  const fn backend_kind(&self) -> BackendKind {
+    match &self.backend {
+      MemoryBackend::Vec(_) => BackendKind::Vec,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      MemoryBackend::MmapMut { .. } => BackendKind::MmapMut,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      MemoryBackend::Mmap { .. } => BackendKind::Mmap,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      MemoryBackend::AnonymousMmap { .. } => BackendKind::AnonymousMmap,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      MemoryBackend::MmapCow { .. } => BackendKind::MmapCow,
+    }
+  }
+
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   #[inline]
   const fn path(&self) -> Option<&std::path::PathBuf> {
     match &self.backend {
       MemoryBackend::MmapMut { path, .. } => Some(path),
       MemoryBackend::Mmap { path, .. } => Some(path),
+      MemoryBackend::MmapCow { path, .. } => Some(path),
       _ => None,
     }
   }
@@ -196,39 +476,129 @@ impl Memory {
   }
 
   unsafe fn clear(&mut self) {
-    let header_ptr_offset = self.ptr.align_offset(mem::align_of::<Header>());
-    let data_offset = header_ptr_offset + mem::size_of::<Header>();
-
-    let min_segment_size = self.header().min_segment_size.load(Ordering::Acquire);
-    let (header, data_offset) = if self.unify {
-      let header_ptr = self.ptr.add(header_ptr_offset);
-      let header = header_ptr.cast::<Header>();
-      header.write(Header::new(data_offset as u32, min_segment_size));
-      (Either::Left(header_ptr), data_offset)
-    } else {
-      (Either::Right(Header::new(1, min_segment_size)), 1)
+    // keep the header and the data offset, which may be padded for the data alignment, in place.
+    let header = self.header().cleared(self.data_offset as u32);
+    match &mut self.header_ptr {
+      Either::Left(header_ptr) => header_ptr.cast::<Header>().write(header),
+      Either::Right(h) => *h = header,
+    }
+  }
+
+  /// Binds the pages in `[ptr, ptr + len)` to the NUMA node, on failure, the memory
+  /// falls back to the default policy of the system.
+  #[cfg(feature = "numa")]
+  unsafe fn bind_numa_node(ptr: *mut u8, len: usize, node: Option<u32>) {
+    let node = match node {
+      Some(node) => node,
+      None => return,
     };
 
-    self.header_ptr = header;
-    self.data_offset = data_offset;
+    #[cfg(target_os = "linux")]
+    {
+      // `c_long` and `c_ulong` have the same size as `isize` and `usize` on linux.
+      const MPOL_BIND: isize = 2;
+      const MPOL_MF_MOVE: usize = 1 << 1;
+      const BITS: usize = usize::BITS as usize;
+
+      // `mbind` requires a page aligned address, only bind the pages fully covered by the memory.
+      let page_size = match libc::sysconf(libc::_SC_PAGESIZE) {
+        size if size > 0 => size as usize,
+        _ => 4096,
+      };
+      let start = (ptr as usize + page_size - 1) & !(page_size - 1);
+      let end = (ptr as usize + len) & !(page_size - 1);
+      if start >= end {
+        return;
+      }
+
+      let node = node as usize;
+      let mut mask = std::vec![0usize; node / BITS + 1];
+      mask[node / BITS] |= 1 << (node % BITS);
+      let rst = libc::syscall(
+        libc::SYS_mbind,
+        start,
+        end - start,
+        MPOL_BIND,
+        mask.as_ptr(),
+        mask.len() * BITS + 1,
+        MPOL_MF_MOVE,
+      );
+      if rst != 0 {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+          "failed to bind the ARENA to NUMA node {node}, fall back to the default policy: {}",
+          std::io::Error::last_os_error()
+        );
+      }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+      let _ = (ptr, len);
+      #[cfg(feature = "tracing")]
+      tracing::warn!(
+        "binding the ARENA to NUMA node {node} is not supported on this platform, fall back to the default policy"
+      );
+    }
+  }
+
+  /// Pads the `data_offset` up, so that `ptr + data_offset` is aligned to `data_alignment`,
+  /// and at least to the alignment of the segment node.
+  #[inline]
+  unsafe fn align_data_offset(ptr: *const u8, data_offset: usize, data_alignment: usize) -> usize {
+    data_offset
+      + ptr
+        .add(data_offset)
+        .align_offset(data_alignment.max(SEGMENT_NODE_ALIGNMENT))
+  }
+
+  fn new_vec(opts: &ArenaOptions) -> Self {
+    let (cap, align) = Self::vec_layout(opts);
+    Self::from_aligned_vec(AlignedVec::new(cap, align), opts)
+  }
+
+  #[cfg(feature = "allocator-api")]
+  fn new_vec_in<A>(opts: &ArenaOptions, alloc: A) -> Self
+  where
+    A: std::alloc::Allocator + Send + Sync + 'static,
+  {
+    let (cap, align) = Self::vec_layout(opts);
+    Self::from_aligned_vec(AlignedVec::new_in(cap, align, alloc), opts)
   }
 
-  fn new_vec(opts: ArenaOptions) -> Self {
+  /// Returns the capacity and the alignment of the [`AlignedVec`] for the given options.
+  #[inline]
+  fn vec_layout(opts: &ArenaOptions) -> (usize, usize) {
     let cap = opts.capacity();
     let alignment = opts.maximum_alignment();
-    let min_segment_size = opts.minimum_segment_size();
-    let unify = opts.unify();
-
-    let cap = if unify {
-      cap.saturating_add(OVERHEAD as u32)
+    let data_alignment = opts.data_alignment().max(SEGMENT_NODE_ALIGNMENT);
+
+    // reserve the bytes for the header (or the null offset), and the padding for the maximum alignment,
+    // so that `capacity` bytes can always be allocated from a new ARENA.
+    let cap = if opts.unify() {
+      cap
+        .saturating_add(OVERHEAD as u32)
+        .saturating_add(alignment as u32 - 1)
     } else {
       cap.saturating_add(alignment as u32)
-    } as usize;
+    }
+    .saturating_add(data_alignment as u32 - 1) as usize;
+
+    (cap, alignment.max(data_alignment))
+  }
+
+  fn from_aligned_vec(mut vec: AlignedVec, opts: &ArenaOptions) -> Self {
+    let cap = vec.cap;
+    let min_segment_size = opts.minimum_segment_size().max(MIN_SEGMENT_SIZE_FLOOR);
+    let unify = opts.unify();
+    let data_alignment = opts.data_alignment();
 
-    let mut vec = AlignedVec::new(cap, alignment);
     // Safety: we have add the overhead for the header
     unsafe {
       let ptr = vec.as_mut_ptr();
+      // bind before the memory is touched, so that the pages are faulted in on the NUMA node.
+      #[cfg(feature = "numa")]
+      Self::bind_numa_node(ptr, vec.cap, opts.numa_node());
       ptr::write_bytes(ptr, 0, vec.cap);
 
       let header_ptr_offset = ptr.add(1).align_offset(mem::align_of::<Header>()) + 1;
@@ -241,16 +611,22 @@ impl Memory {
           opts.magic_version(),
           slice::from_raw_parts_mut(ptr, 8),
         );
+        data_offset = Self::align_data_offset(ptr, data_offset, data_alignment);
         header_ptr.write(Header::new(data_offset as u32, min_segment_size));
         (Either::Left(header_ptr as _), data_offset)
       } else {
-        data_offset = 1;
-        (Either::Right(Header::new(1, min_segment_size)), data_offset)
+        data_offset = Self::align_data_offset(ptr, 1, data_alignment);
+        (
+          Either::Right(Header::new(data_offset as u32, min_segment_size)),
+          data_offset,
+        )
       };
 
       Self {
         cap: cap as u32,
         refs: AtomicUsize::new(1),
+        #[cfg(feature = "metrics")]
+        walks: FreeListWalks::default(),
         ptr,
         header_ptr: header,
         backend: MemoryBackend::Vec(vec),
@@ -263,17 +639,199 @@ impl Memory {
     }
   }
 
+  /// Relayouts a non-unify [`Vec`](MemoryBackend::Vec) memory into a new unify one.
+  ///
+  /// The shift is a multiple of the alignment of the buffer, so the alignments of the allocations are preserved.
+  fn to_unified(&self) -> Result<Self, Error> {
+    let align = match &self.backend {
+      MemoryBackend::Vec(vec) if !self.unify => vec.align,
+      _ => return Err(Error::IncompatibleArena),
+    };
+
+    let header = self.header();
+    let old_data_offset = self.data_offset as u32;
+    let allocated = header.allocated.load(Ordering::Acquire);
+
+    // the buffer is aligned to `align`, which is at least the alignment of the header.
+    let header_ptr_offset = align_offset::<Header>(1) as usize;
+    let header_end = (header_ptr_offset + mem::size_of::<Header>()) as u32;
+    let align_mask = align as u32 - 1;
+    let shift = header_end
+      .saturating_sub(old_data_offset)
+      .checked_add(align_mask)
+      .map(|shift| shift & !align_mask);
+    let (shift, cap) =
+      match shift.and_then(|shift| self.cap.checked_add(shift).map(|cap| (shift, cap))) {
+        Some(val) => val,
+        None => {
+          return Err(Error::InsufficientSpace {
+            requested: header_end,
+            available: 0,
+          })
+        }
+      };
+    let data_offset = old_data_offset + shift;
+
+    let mut vec = AlignedVec::new(cap as usize, align);
+    // Safety: `[0, cap)` is in bounds of the new buffer, `[old_data_offset, allocated)` is in bounds
+    // of the old buffer, and the header fits in the new buffer before `data_offset`.
+    unsafe {
+      let ptr = vec.as_mut_ptr();
+      ptr::write_bytes(ptr, 0, cap as usize);
+      Self::write_sanity(
+        self.freelist as u8,
+        self.magic_version,
+        slice::from_raw_parts_mut(ptr, 8),
+      );
+      ptr::copy_nonoverlapping(
+        self.ptr.add(old_data_offset as usize),
+        ptr.add(data_offset as usize),
+        (allocated - old_data_offset) as usize,
+      );
+
+      let new_header = Header::new(data_offset, header.min_segment_size.load(Ordering::Acquire));
+      new_header
+        .allocated
+        .store(allocated + shift, Ordering::Relaxed);
+      for (dst, src) in [
+        (&new_header.discarded, &header.discarded),
+        (&new_header.high_water, &header.high_water),
+        (&new_header.discarded_padding, &header.discarded_padding),
+        (
+          &new_header.discarded_small_segments,
+          &header.discarded_small_segments,
+        ),
+        (&new_header.discarded_manual, &header.discarded_manual),
+        (&new_header.generation, &header.generation),
+      ] {
+        dst.store(src.load(Ordering::Acquire), Ordering::Relaxed);
+      }
+      new_header.last_modified.store(
+        header.last_modified.load(Ordering::Acquire),
+        Ordering::Relaxed,
+      );
+      // the root is an offset into the ARENA, which moves with the data.
+      let root = header.root.load(Ordering::Acquire);
+      if root != 0 {
+        new_header
+          .root
+          .store(root + shift as u64, Ordering::Relaxed);
+      }
+
+      // move the links of the freelist, the segment nodes themselves have been copied with the data.
+      let shift_link = |val: u64| {
+        let (size, next) = decode_segment_node(val);
+        if next == SENTINEL_SEGMENT_NODE_OFFSET {
+          val
+        } else {
+          encode_segment_node(size, next + shift)
+        }
+      };
+      new_header.sentinel.store(
+        shift_link(header.sentinel.load(Ordering::Acquire)),
+        Ordering::Relaxed,
+      );
+      let (_, mut next) = decode_segment_node(new_header.sentinel.load(Ordering::Relaxed));
+      // bounded by the number of the segment nodes that fit in the memory, in case of a cycle.
+      let mut remaining = cap as usize / SEGMENT_NODE_SIZE;
+      while next != SENTINEL_SEGMENT_NODE_OFFSET && remaining > 0 {
+        if next < data_offset
+          || next as usize % SEGMENT_NODE_ALIGNMENT != 0
+          || next as usize + SEGMENT_NODE_SIZE > cap as usize
+        {
+          break;
+        }
+
+        let node = &*ptr.add(next as usize).cast::<AtomicU64>();
+        let val = shift_link(node.load(Ordering::Relaxed));
+        node.store(val, Ordering::Relaxed);
+        next = decode_segment_node(val).1;
+        remaining -= 1;
+      }
+
+      let header_ptr = ptr.add(header_ptr_offset).cast::<Header>();
+      header_ptr.write(new_header);
+
+      Ok(Self {
+        cap,
+        refs: AtomicUsize::new(1),
+        #[cfg(feature = "metrics")]
+        walks: FreeListWalks::default(),
+        ptr,
+        header_ptr: Either::Left(header_ptr as _),
+        backend: MemoryBackend::Vec(vec),
+        data_offset: data_offset as usize,
+        unify: true,
+        magic_version: self.magic_version,
+        version: self.version,
+        freelist: self.freelist,
+      })
+    }
+  }
+
+  /// Copies this memory into a new [`Vec`](MemoryBackend::Vec) memory of the same capacity and layout.
+  fn deep_clone(&self) -> Self {
+    let align = match &self.backend {
+      MemoryBackend::Vec(vec) => vec.align,
+      // the memory maps are page aligned.
+      #[allow(unreachable_patterns)]
+      _ => 4096,
+    };
+
+    let header = self.header();
+    let allocated = header.allocated.load(Ordering::Acquire);
+    let mut vec = AlignedVec::new(self.cap as usize, align);
+    // Safety: the two buffers have the same capacity and alignment, so `[0, allocated)` is in bounds
+    // of both, and the header of a unify memory is at the same offset in the new buffer.
+    unsafe {
+      let ptr = vec.as_mut_ptr();
+      ptr::copy_nonoverlapping(self.ptr, ptr, allocated as usize);
+
+      let header_ptr = match self.header_ptr {
+        Either::Left(header_ptr) => {
+          let offset = header_ptr as usize - self.ptr as usize;
+          let new_header_ptr = ptr.add(offset);
+          new_header_ptr.cast::<Header>().write(header.duplicate());
+          Either::Left(new_header_ptr)
+        }
+        Either::Right(_) => Either::Right(header.duplicate()),
+      };
+
+      Self {
+        cap: self.cap,
+        refs: AtomicUsize::new(1),
+        #[cfg(feature = "metrics")]
+        walks: FreeListWalks::default(),
+        ptr,
+        header_ptr,
+        backend: MemoryBackend::Vec(vec),
+        data_offset: self.data_offset,
+        unify: self.unify,
+        magic_version: self.magic_version,
+        version: self.version,
+        freelist: self.freelist,
+      }
+    }
+  }
+
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   fn map_mut<P: AsRef<std::path::Path>>(
     path: P,
     open_options: OpenOptions,
     mmap_options: MmapOptions,
-    alignment: usize,
-    min_segment_size: u32,
-    magic_version: u16,
-    freelist: Freelist,
+    opts: &ArenaOptions,
   ) -> std::io::Result<Self> {
+    let alignment = opts.maximum_alignment();
+    let min_segment_size = opts.minimum_segment_size().max(MIN_SEGMENT_SIZE_FLOOR);
+    let magic_version = opts.magic_version();
+    let freelist = opts.freelist();
     let (create_new, file) = open_options.open(path.as_ref())?;
+    if create_new && open_options.reserves_overhead() {
+      // the length of the new file is the capacity hint, make room for the header and the padding of the data.
+      let hint = file.metadata()?.len();
+      let padding = opts.data_alignment().max(SEGMENT_NODE_ALIGNMENT) - 1;
+      open_options.set_len(&file, hint + (OVERHEAD + padding) as u64)?;
+    }
 
     unsafe {
       mmap_options.map_mut(&file).and_then(|mut mmap| {
@@ -288,10 +846,15 @@ impl Memory {
         let ptr = mmap.as_mut_ptr();
 
         let header_ptr_offset = ptr.add(1).align_offset(mem::align_of::<Header>()) + 1;
-        let data_offset = header_ptr_offset + mem::size_of::<Header>();
+        let mut data_offset = header_ptr_offset + mem::size_of::<Header>();
         let header_ptr = ptr.add(header_ptr_offset).cast::<Header>();
 
         let (version, magic_version) = if create_new {
+          data_offset = Self::align_data_offset(ptr, data_offset, opts.data_alignment());
+          if data_offset > cap {
+            return Err(invalid_data(TooSmall::new(cap, data_offset)));
+          }
+
           // initialize the memory with 0
           ptr::write_bytes(ptr, 0, cap);
 
@@ -306,13 +869,9 @@ impl Memory {
 
           (CURRENT_VERSION, magic_version)
         } else {
-          let allocated = (*header_ptr).allocated.load(Ordering::Acquire);
-          ptr::write_bytes(
-            ptr.add(allocated as usize),
-            0,
-            mmap.len() - allocated as usize,
-          );
-          Self::sanity_check(Some(freelist), magic_version, &mmap)?;
+          data_offset = Self::reopen(&mut mmap, &*header_ptr, data_offset, opts)?;
+          // otherwise the readers of the seqlock wait for the writes of a crashed process forever.
+          (*header_ptr).reset_writes_in_progress();
           (CURRENT_VERSION, magic_version)
         };
 
@@ -328,6 +887,8 @@ impl Memory {
           header_ptr: Either::Left(header_ptr as _),
           ptr,
           refs: AtomicUsize::new(1),
+          #[cfg(feature = "metrics")]
+          walks: FreeListWalks::default(),
           data_offset,
           unify: true,
           magic_version,
@@ -340,6 +901,101 @@ impl Memory {
     }
   }
 
+  /// Checks the persisted memory map of an existing ARENA, and zeroes the memory after
+  /// the allocated bytes, returns the data offset.
+  ///
+  /// ## Safety
+  /// - `header` must point to the header in the `mmap`.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  unsafe fn reopen(
+    mmap: &mut memmap2::MmapMut,
+    header: &Header,
+    min_data_offset: usize,
+    opts: &ArenaOptions,
+  ) -> std::io::Result<usize> {
+    let min_segment_size = opts.minimum_segment_size().max(MIN_SEGMENT_SIZE_FLOOR);
+    let cap = mmap.len();
+    Self::sanity_check(Some(opts.freelist()), opts.magic_version(), mmap)?;
+    let allocated = header.allocated.load(Ordering::Acquire) as usize;
+    if allocated > cap {
+      return Err(bad_data_offset());
+    }
+    ptr::write_bytes(mmap.as_mut_ptr().add(allocated), 0, cap - allocated);
+    let data_offset = Self::stored_data_offset(header, min_data_offset, cap)?;
+
+    // the header is persisted, never re-initialize it on reopen,
+    // the stored minimum segment size wins unless strict reopen is required.
+    let found = header.min_segment_size.load(Ordering::Acquire);
+    if found != min_segment_size && opts.strict_reopen() {
+      return Err(invalid_data(MinimumSegmentSizeMismatch::new(
+        min_segment_size,
+        found,
+      )));
+    }
+
+    #[cfg(feature = "tracing")]
+    if found != min_segment_size {
+      tracing::warn!(
+        "minimum segment size mismatch on reopen: expected {}, but found {}, use the persisted value",
+        min_segment_size,
+        found
+      );
+    }
+    Ok(data_offset)
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  fn map_cow<P: AsRef<std::path::Path>>(
+    path: P,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+    opts: &ArenaOptions,
+  ) -> std::io::Result<Self> {
+    if !path.as_ref().exists() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "file not found",
+      ));
+    }
+
+    let (_, file) = open_options.open(path.as_ref())?;
+
+    unsafe {
+      mmap_options.map_copy(&file).and_then(|mut mmap| {
+        let cap = mmap.len();
+        if cap < OVERHEAD {
+          return Err(invalid_data(TooSmall::new(cap, OVERHEAD)));
+        }
+
+        let ptr = mmap.as_mut_ptr();
+        let header_ptr_offset = ptr.add(1).align_offset(mem::align_of::<Header>()) + 1;
+        let data_offset = header_ptr_offset + mem::size_of::<Header>();
+        let header_ptr = ptr.add(header_ptr_offset);
+        let data_offset =
+          Self::reopen(&mut mmap, &*header_ptr.cast::<Header>(), data_offset, opts)?;
+
+        Ok(Self {
+          cap: cap as u32,
+          backend: MemoryBackend::MmapCow {
+            path: path.as_ref().to_path_buf(),
+            buf: mmap,
+            file,
+          },
+          header_ptr: Either::Left(header_ptr),
+          ptr,
+          refs: AtomicUsize::new(1),
+          #[cfg(feature = "metrics")]
+          walks: FreeListWalks::default(),
+          data_offset,
+          unify: true,
+          magic_version: opts.magic_version(),
+          version: CURRENT_VERSION,
+          freelist: opts.freelist(),
+        })
+      })
+    }
+  }
+
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   fn map<P: AsRef<std::path::Path>>(
     path: P,
@@ -370,7 +1026,9 @@ impl Memory {
         let ptr = mmap.as_ptr();
         let header_ptr_offset = ptr.add(1).align_offset(mem::align_of::<Header>()) + 1;
         let data_offset = header_ptr_offset + mem::size_of::<Header>();
-        let header_ptr = ptr.add(header_ptr_offset) as _;
+        let header_ptr = ptr.add(header_ptr_offset) as *mut u8;
+        let data_offset =
+          Self::stored_data_offset(&*header_ptr.cast::<Header>(), data_offset, len)?;
         let this = Self {
           cap: len as u32,
           backend: MemoryBackend::Mmap {
@@ -383,6 +1041,8 @@ impl Memory {
           header_ptr: Either::Left(header_ptr),
           ptr: ptr as _,
           refs: AtomicUsize::new(1),
+          #[cfg(feature = "metrics")]
+          walks: FreeListWalks::default(),
           data_offset,
           unify: true,
           magic_version,
@@ -396,15 +1056,30 @@ impl Memory {
   }
 
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  fn map_anon(
-    mmap_options: MmapOptions,
-    alignment: usize,
-    min_segment_size: u32,
-    unify: bool,
-    magic_version: u16,
-    freelist: Freelist,
-  ) -> std::io::Result<Self> {
+  fn map_anon(mmap_options: MmapOptions, opts: &ArenaOptions) -> std::io::Result<Self> {
+    let alignment = opts.maximum_alignment();
+    let min_segment_size = opts.minimum_segment_size().max(MIN_SEGMENT_SIZE_FLOOR);
+    let unify = opts.unify();
+    let magic_version = opts.magic_version();
+    let freelist = opts.freelist();
+    let data_alignment = opts.data_alignment();
+
+    // the length of an anonymous memory map is the capacity of the ARENA, so it must be explicit.
+    if let Some(0) = mmap_options.get_len() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "memory map must have a non-zero length",
+      ));
+    }
+
     mmap_options.map_anon().and_then(|mut mmap| {
+      if mmap.is_empty() {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          "memory map must have a non-zero length",
+        ));
+      }
+
       if unify {
         if mmap.len() < OVERHEAD {
           return Err(invalid_data(TooSmall::new(mmap.len(), OVERHEAD)));
@@ -419,6 +1094,8 @@ impl Memory {
 
       // Safety: we have add the overhead for the header
       unsafe {
+        #[cfg(feature = "numa")]
+        Self::bind_numa_node(ptr, mmap.len(), opts.numa_node());
         ptr::write_bytes(ptr, 0, mmap.len());
 
         let header_ptr_offset = ptr.add(1).align_offset(mem::align_of::<Header>()) + 1;
@@ -431,19 +1108,29 @@ impl Memory {
             magic_version,
             slice::from_raw_parts_mut(ptr, header_ptr_offset),
           );
+          data_offset = Self::align_data_offset(ptr, data_offset, data_alignment);
           header_ptr
             .cast::<Header>()
             .write(Header::new(data_offset as u32, min_segment_size));
           (Either::Left(header_ptr as _), data_offset)
         } else {
-          data_offset = 1;
-          (Either::Right(Header::new(1, min_segment_size)), data_offset)
+          data_offset = Self::align_data_offset(ptr, 1, data_alignment);
+          (
+            Either::Right(Header::new(data_offset as u32, min_segment_size)),
+            data_offset,
+          )
         };
 
+        if data_offset > mmap.len() {
+          return Err(invalid_data(TooSmall::new(mmap.len(), data_offset)));
+        }
+
         let this = Self {
           cap: mmap.len() as u32,
           backend: MemoryBackend::AnonymousMmap { buf: mmap },
           refs: AtomicUsize::new(1),
+          #[cfg(feature = "metrics")]
+          walks: FreeListWalks::default(),
           data_offset,
           header_ptr: header,
           ptr,
@@ -458,12 +1145,54 @@ impl Memory {
     })
   }
 
+  /// Grows the anonymous memory map to `new_cap` bytes, the new tail is zeroed.
+  ///
+  /// On Linux, the mapping is enlarged by `mremap` without copying, on other platforms,
+  /// a new mapping is created and the old memory is copied into it.
+  ///
+  /// ## Safety
+  /// - There must be no other references to the memory, the memory may be moved.
+  /// - `new_cap` must be greater than the current capacity.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  unsafe fn grow(&mut self, new_cap: usize) -> std::io::Result<()> {
+    let MemoryBackend::AnonymousMmap { buf } = &mut self.backend else {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "only the ARENA backed by an anonymous memory map can grow",
+      ));
+    };
+
+    let old_cap = self.cap as usize;
+    let old_ptr = self.ptr as usize;
+
+    #[cfg(target_os = "linux")]
+    buf.remap(new_cap, memmap2::RemapOptions::new().may_move(true))?;
+
+    #[cfg(not(target_os = "linux"))]
+    {
+      let mut new = memmap2::MmapMut::map_anon(new_cap)?;
+      new[..old_cap].copy_from_slice(&buf[..old_cap]);
+      *buf = new;
+    }
+
+    let ptr = buf.as_mut_ptr();
+    ptr::write_bytes(ptr.add(old_cap), 0, new_cap - old_cap);
+
+    if let Either::Left(header_ptr) = &mut self.header_ptr {
+      *header_ptr = ptr.add(*header_ptr as usize - old_ptr);
+    }
+    self.ptr = ptr;
+    self.cap = new_cap as u32;
+    Ok(())
+  }
+
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   fn lock_exclusive(&self) -> std::io::Result<()> {
     use fs4::FileExt;
     match &self.backend {
       MemoryBackend::MmapMut { file, .. } => file.lock_exclusive(),
       MemoryBackend::Mmap { file, .. } => file.lock_exclusive(),
+      MemoryBackend::MmapCow { file, .. } => file.lock_exclusive(),
       _ => Ok(()),
     }
   }
@@ -474,6 +1203,7 @@ impl Memory {
     match &self.backend {
       MemoryBackend::MmapMut { file, .. } => file.lock_shared(),
       MemoryBackend::Mmap { file, .. } => file.lock_shared(),
+      MemoryBackend::MmapCow { file, .. } => file.lock_shared(),
       _ => Ok(()),
     }
   }
@@ -484,6 +1214,7 @@ impl Memory {
     match &self.backend {
       MemoryBackend::MmapMut { file, .. } => file.try_lock_exclusive(),
       MemoryBackend::Mmap { file, .. } => file.try_lock_exclusive(),
+      MemoryBackend::MmapCow { file, .. } => file.try_lock_exclusive(),
       _ => Ok(()),
     }
   }
@@ -494,6 +1225,7 @@ impl Memory {
     match &self.backend {
       MemoryBackend::MmapMut { file, .. } => file.try_lock_shared(),
       MemoryBackend::Mmap { file, .. } => file.try_lock_shared(),
+      MemoryBackend::MmapCow { file, .. } => file.try_lock_shared(),
       _ => Ok(()),
     }
   }
@@ -504,12 +1236,14 @@ impl Memory {
     match &self.backend {
       MemoryBackend::MmapMut { file, .. } => file.unlock(),
       MemoryBackend::Mmap { file, .. } => file.unlock(),
+      MemoryBackend::MmapCow { file, .. } => file.unlock(),
       _ => Ok(()),
     }
   }
 
   #[inline]
   fn write_sanity(freelist: u8, magic_version: u16, data: &mut [u8]) {
+    data[BYTE_ORDER_OFFSET] = NATIVE_ENDIAN;
     data[FREELIST_OFFSET] = freelist;
     data[MAGIC_TEXT_OFFSET..MAGIC_TEXT_OFFSET + MAGIC_TEXT_SIZE]
       .copy_from_slice(MAGIC_TEXT.as_ref());
@@ -519,6 +1253,21 @@ impl Memory {
       .copy_from_slice(CURRENT_VERSION.to_le_bytes().as_ref());
   }
 
+  /// Returns the data offset persisted in the header, which must be in `[min_data_offset, cap]`.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn stored_data_offset(
+    header: &Header,
+    min_data_offset: usize,
+    cap: usize,
+  ) -> std::io::Result<usize> {
+    let data_offset = header.data_offset as usize;
+    if data_offset < min_data_offset || data_offset > cap {
+      return Err(bad_data_offset());
+    }
+    Ok(data_offset)
+  }
+
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   #[inline]
   fn sanity_check(
@@ -526,12 +1275,36 @@ impl Memory {
     magic_version: u16,
     data: &[u8],
   ) -> std::io::Result<Freelist> {
+    Self::check_sanity_bytes(freelist, magic_version, data).map_err(|e| match e {
+      SanityError::ByteOrder => bad_byte_order(),
+      SanityError::Freelist(e) => invalid_data(e),
+      SanityError::FreelistMismatch => bad_freelist(),
+      SanityError::MagicVersion(found) => {
+        invalid_data(MagicVersionMismatch::new(magic_version, found))
+      }
+      SanityError::Version(found) => invalid_data(VersionMismatch::new(CURRENT_VERSION, found)),
+      SanityError::Magic => bad_magic(),
+    })
+  }
+
+  /// Checks the sanity bytes in front of the header, shared by the memory maps and [`ArenaView`],
+  /// so that the parsers never drift apart.
+  #[inline]
+  fn check_sanity_bytes(
+    freelist: Option<Freelist>,
+    magic_version: u16,
+    data: &[u8],
+  ) -> Result<Freelist, SanityError> {
+    if data[BYTE_ORDER_OFFSET] != NATIVE_ENDIAN {
+      return Err(SanityError::ByteOrder);
+    }
+
     let stored_freelist = data[FREELIST_OFFSET];
-    let stored_freelist = Freelist::try_from(stored_freelist).map_err(invalid_data)?;
+    let stored_freelist = Freelist::try_from(stored_freelist).map_err(SanityError::Freelist)?;
 
     if let Some(freelist) = freelist {
       if stored_freelist != freelist {
-        return Err(bad_freelist());
+        return Err(SanityError::FreelistMismatch);
       }
     }
 
@@ -547,18 +1320,15 @@ impl Memory {
     );
 
     if stored_magic_version != magic_version {
-      return Err(invalid_data(MagicVersionMismatch::new(
-        magic_version,
-        stored_magic_version,
-      )));
+      return Err(SanityError::MagicVersion(stored_magic_version));
     }
 
     if version != CURRENT_VERSION {
-      return Err(invalid_data(VersionMismatch::new(CURRENT_VERSION, version)));
+      return Err(SanityError::Version(version));
     }
 
     if data[MAGIC_TEXT_OFFSET..MAGIC_TEXT_OFFSET + MAGIC_TEXT_SIZE] != MAGIC_TEXT {
-      return Err(bad_magic());
+      return Err(SanityError::Magic);
     }
     Ok(stored_freelist)
   }
@@ -567,7 +1337,11 @@ impl Memory {
   fn flush(&self) -> std::io::Result<()> {
     match &self.backend {
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-      MemoryBackend::MmapMut { buf: mmap, .. } => unsafe { (**mmap).flush() },
+      MemoryBackend::MmapMut { buf: mmap, .. } => {
+        // only the writable file mapping records the flush, the header of the others may be read-only.
+        self.header().touch();
+        unsafe { (**mmap).flush() }
+      }
       _ => Ok(()),
     }
   }
@@ -576,11 +1350,83 @@ impl Memory {
   fn flush_async(&self) -> std::io::Result<()> {
     match &self.backend {
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-      MemoryBackend::MmapMut { buf: mmap, .. } => unsafe { (**mmap).flush_async() },
+      MemoryBackend::MmapMut { buf: mmap, .. } => {
+        // only the writable file mapping records the flush, the header of the others may be read-only.
+        self.header().touch();
+        unsafe { (**mmap).flush_async() }
+      }
       _ => Ok(()),
     }
   }
 
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  fn prefault(&self) -> std::io::Result<()> {
+    const PAGE_SIZE: usize = 4096;
+
+    #[cfg(unix)]
+    match &self.backend {
+      MemoryBackend::MmapMut { buf: mmap, .. } => unsafe {
+        (**mmap).advise(memmap2::Advice::WillNeed)?
+      },
+      MemoryBackend::Mmap { buf: mmap, .. } => unsafe {
+        (**mmap).advise(memmap2::Advice::WillNeed)?
+      },
+      MemoryBackend::AnonymousMmap { buf } | MemoryBackend::MmapCow { buf, .. } => {
+        buf.advise(memmap2::Advice::WillNeed)?
+      }
+      MemoryBackend::Vec(_) => {}
+    }
+
+    // a read is enough to fault the page in, so this also works for the read-only mapping.
+    let mut offset = 0;
+    while offset < self.cap as usize {
+      unsafe {
+        ptr::read_volatile(self.ptr.add(offset));
+      }
+      offset += PAGE_SIZE;
+    }
+    Ok(())
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  unsafe fn advise_dontneed(&self, offset: usize, len: usize) -> std::io::Result<()> {
+    if offset
+      .checked_add(len)
+      .map_or(true, |end| end > self.cap as usize)
+    {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "the range is out of the bounds of the ARENA",
+      ));
+    }
+
+    if let MemoryBackend::Vec(_) = self.backend {
+      return Ok(());
+    }
+
+    #[cfg(unix)]
+    {
+      // `madvise` requires a page aligned address, only advise the pages fully covered by the range,
+      // so that the bytes out of the range are never dropped.
+      let page_size = match libc::sysconf(libc::_SC_PAGESIZE) {
+        size if size > 0 => size as usize,
+        _ => 4096,
+      };
+      let ptr = self.ptr.add(offset) as usize;
+      let start = (ptr + page_size - 1) & !(page_size - 1);
+      let end = (ptr + len) & !(page_size - 1);
+      if start >= end {
+        return Ok(());
+      }
+
+      if libc::madvise(start as *mut libc::c_void, end - start, libc::MADV_DONTNEED) != 0 {
+        return Err(std::io::Error::last_os_error());
+      }
+    }
+
+    Ok(())
+  }
+
   #[allow(dead_code)]
   #[inline]
   const fn as_ptr(&self) -> *const u8 {
@@ -633,12 +1479,14 @@ impl Memory {
           return;
         }
 
+        let header = match &self.header_ptr {
+          Either::Left(header_ptr) => &*(*header_ptr).cast::<Header>(),
+          Either::Right(header) => header,
+        };
+        header.touch();
+
         // we must trigger the drop of the mmap
         let used = if shrink_on_drop.load(Ordering::Acquire) {
-          let header = match &self.header_ptr {
-            Either::Left(header_ptr) => &*(*header_ptr).cast::<Header>(),
-            Either::Right(header) => header,
-          };
           Some(header.allocated.load(Ordering::Acquire))
         } else {
           None
@@ -693,6 +1541,225 @@ impl Memory {
   }
 }
 
+/// The kind of the backend memory of an ARENA, returned by [`Arena::backend_kind`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BackendKind {
+  /// Backed by a heap allocated buffer.
+  Vec,
+  /// Backed by a read only memory map of a file.
+  Mmap,
+  /// Backed by a mutable memory map of a file.
+  MmapMut,
+  /// Backed by an anonymous memory map.
+  AnonymousMmap,
+  /// Backed by a copy-on-write memory map of a file, the writes are never written back to the file.
+  MmapCow,
+}
+
+/// The counter of the successful allocations, see [`ArenaOptions::with_auto_flush_interval`].
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[derive(Debug)]
+struct AutoFlush {
+  interval: u32,
+  allocations: AtomicU32,
+}
+
+/// The backoff of the freelist operations, which gives up after the given number of
+/// backoff iterations in total, or never gives up if unbounded.
+///
+/// Created by [`Arena::slow_path_backoff`].
+struct SlowPathBackoff<'a> {
+  backoff: Backoff,
+  remaining: Option<u32>,
+  exhausted: bool,
+  /// Parks the thread instead of snoozing once the spins are exhausted, if set.
+  #[cfg(feature = "std")]
+  parker: Option<&'a Parker>,
+  #[cfg(not(feature = "std"))]
+  _marker: core::marker::PhantomData<&'a ()>,
+}
+
+impl SlowPathBackoff<'_> {
+  /// Returns `false` if the budget is exhausted, then the slow path should give up.
+  #[inline]
+  fn snooze(&mut self) -> bool {
+    self.consume() && {
+      #[cfg(feature = "std")]
+      if let Some(parker) = self.parker.filter(|_| self.backoff.is_completed()) {
+        parker.park();
+        return true;
+      }
+
+      self.backoff.snooze();
+      true
+    }
+  }
+
+  /// Returns `false` if the budget is exhausted, then the slow path should give up.
+  #[inline]
+  fn spin(&mut self) -> bool {
+    self.consume() && {
+      self.backoff.spin();
+      true
+    }
+  }
+
+  #[inline]
+  fn consume(&mut self) -> bool {
+    match &mut self.remaining {
+      None => true,
+      Some(0) => {
+        self.exhausted = true;
+        false
+      }
+      Some(remaining) => {
+        *remaining -= 1;
+        true
+      }
+    }
+  }
+
+  /// Restarts the backoff for a new slow path invocation, the budget is kept.
+  #[inline]
+  fn restart(&mut self) {
+    self.backoff.reset();
+  }
+
+  /// Returns `true` if the slow path gave up due to the contention.
+  #[inline]
+  const fn exhausted(&self) -> bool {
+    self.exhausted
+  }
+}
+
+/// How a single allocation was satisfied, returned by [`Arena::alloc_bytes_with_stats`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AllocStats {
+  fast_path: bool,
+  slow_path_retries: u8,
+  from_free_list: bool,
+}
+
+impl AllocStats {
+  /// Returns `true` if the allocation was satisfied by bumping the main memory.
+  #[inline]
+  pub const fn fast_path(&self) -> bool {
+    self.fast_path
+  }
+
+  /// Returns the number of the failed attempts of the slow path before the allocation was satisfied.
+  #[inline]
+  pub const fn slow_path_retries(&self) -> u8 {
+    self.slow_path_retries
+  }
+
+  /// Returns `true` if the allocation was satisfied by a segment of the freelist.
+  #[inline]
+  pub const fn from_free_list(&self) -> bool {
+    self.from_free_list
+  }
+}
+
+/// A snapshot of the statistics of an ARENA, returned by [`Arena::stats`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ArenaStats {
+  allocated: u32,
+  discarded: u32,
+  free_list_bytes: u32,
+  cap: u32,
+}
+
+impl ArenaStats {
+  /// Returns the number of bytes allocated by the ARENA, including the overhead.
+  #[inline]
+  pub const fn allocated(&self) -> usize {
+    self.allocated as usize
+  }
+
+  /// Returns the number of bytes discarded by the ARENA.
+  #[inline]
+  pub const fn discarded(&self) -> usize {
+    self.discarded as usize
+  }
+
+  /// Returns the number of bytes in the freelist, including the segment nodes.
+  #[inline]
+  pub const fn free_list_bytes(&self) -> usize {
+    self.free_list_bytes as usize
+  }
+
+  /// Returns the capacity of the ARENA.
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    self.cap as usize
+  }
+
+  /// Returns the number of bytes which can still be bumped from the main memory of the ARENA.
+  #[inline]
+  pub const fn remaining(&self) -> usize {
+    self.cap.saturating_sub(self.allocated) as usize
+  }
+}
+
+/// The report of a well-formed freelist, returned by [`Arena::validate_free_list`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FreeListReport {
+  segments: usize,
+  free_bytes: usize,
+}
+
+impl FreeListReport {
+  /// Returns the number of the segments in the freelist.
+  #[inline]
+  pub const fn segments(&self) -> usize {
+    self.segments
+  }
+
+  /// Returns the number of bytes in the freelist, including the segment nodes.
+  #[inline]
+  pub const fn free_bytes(&self) -> usize {
+    self.free_bytes
+  }
+}
+
+/// The persisted header of a file backed ARENA, returned by [`Arena::peek_header`].
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HeaderInfo {
+  allocated: u32,
+  discarded: u32,
+  min_segment_size: u32,
+  cap: u64,
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl HeaderInfo {
+  /// Returns the number of bytes allocated by the ARENA, including the overhead.
+  #[inline]
+  pub const fn allocated(&self) -> u32 {
+    self.allocated
+  }
+
+  /// Returns the number of bytes discarded by the ARENA.
+  #[inline]
+  pub const fn discarded(&self) -> u32 {
+    self.discarded
+  }
+
+  /// Returns the minimum segment size of the ARENA.
+  #[inline]
+  pub const fn minimum_segment_size(&self) -> u32 {
+    self.min_segment_size
+  }
+
+  /// Returns the capacity of the ARENA, which is the length of the file.
+  #[inline]
+  pub const fn capacity(&self) -> u64 {
+    self.cap
+  }
+}
+
 /// The metadata of the structs allocated from ARENA.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Meta {
@@ -744,6 +1811,13 @@ impl Meta {
     self.ptr_size = mem::size_of::<T>() as u32;
   }
 
+  /// Aligns the address of the value to `align` instead of the natural alignment of `T`.
+  #[inline]
+  fn align_to_with<T>(&mut self, align: u32) {
+    self.ptr_offset = align_address(self.parent_ptr, self.memory_offset, align) as u32;
+    self.ptr_size = mem::size_of::<T>() as u32;
+  }
+
   #[inline]
   fn align_bytes_to<T>(&mut self) {
     let align_offset = align_offset::<T>(self.memory_offset);
@@ -838,8 +1912,37 @@ pub struct Arena {
   magic_version: u16,
   version: u16,
   ro: bool,
+  zero_on_free: bool,
+  /// Bumps the seqlock in the header around the writes, see [`ArenaOptions::with_seqlock`].
+  seqlock: bool,
+  compaction_threshold: Option<f64>,
   cap: u32,
   freelist: Freelist,
+  on_oom: Option<OomHook>,
+  on_high_water: Option<HighWaterHook>,
+  on_event: Option<EventHook>,
+  #[cfg(feature = "trace")]
+  tracer: Option<std::sync::Arc<Tracer>>,
+  /// Parks the contending threads, see [`ArenaOptions::with_park_on_contention`], shared by the clones.
+  #[cfg(feature = "std")]
+  parker: Option<std::sync::Arc<Parker>>,
+  /// Serializes the allocations and the deallocations, see [`ArenaOptions::with_deterministic`], shared by the clones.
+  serial: Option<std::sync::Arc<SerialLock>>,
+  /// Flushes the memory map periodically, see [`ArenaOptions::with_auto_flush_interval`], shared by the clones.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  auto_flush: Option<std::sync::Arc<AutoFlush>>,
+  /// The header of a partition, see [`Arena::partition`], `None` if the header of the memory is used.
+  partition_header: Option<NonNull<Header>>,
+  /// The process-wide unique id of the offset space, shared by the clones.
+  id: u64,
+}
+
+/// Returns a new process-wide unique id for an ARENA.
+#[inline]
+fn next_arena_id() -> u64 {
+  // the ids are not persisted, so the counter is never a loom atomic.
+  static NEXT_ID: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(1);
+  NEXT_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed)
 }
 
 impl fmt::Debug for Arena {
@@ -879,48 +1982,70 @@ impl Clone for Arena {
         ptr: self.ptr,
         data_offset: self.data_offset,
         ro: self.ro,
+        zero_on_free: self.zero_on_free,
+        seqlock: self.seqlock,
+        compaction_threshold: self.compaction_threshold,
         inner: self.inner,
         unify: self.unify,
         cap: self.cap,
         freelist: self.freelist,
+        on_oom: self.on_oom.clone(),
+        on_high_water: self.on_high_water.clone(),
+        on_event: self.on_event.clone(),
+        #[cfg(feature = "trace")]
+        tracer: self.tracer.clone(),
+        #[cfg(feature = "std")]
+        parker: self.parker.clone(),
+        serial: self.serial.clone(),
+        #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+        auto_flush: self.auto_flush.clone(),
+        partition_header: self.partition_header,
+        id: self.id,
       }
     }
   }
 }
 
 impl Arena {
-  /// Returns the version of the ARENA.
+  /// Returns the process-wide unique id of the ARENA, which is used to detect the handles
+  /// passed to the wrong ARENA.
+  ///
+  /// The clones of an ARENA share the same id since they share the same offset space,
+  /// while every [`partition`](Self::partition) has its own id. The id is not persisted.
   ///
   /// # Example
   ///
   /// ```rust
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// let arena = Arena::new(ArenaOptions::new());
-  /// let version = arena.version();
+  /// let a = Arena::new(ArenaOptions::new());
+  /// let b = Arena::new(ArenaOptions::new());
+  /// assert_ne!(a.id(), b.id());
+  /// assert_eq!(a.id(), a.clone().id());
   /// ```
   #[inline]
-  pub const fn version(&self) -> u16 {
-    self.version
+  pub const fn id(&self) -> u64 {
+    self.id
   }
 
-  /// Returns the magic version of the ARENA. This value can be used to check the compatibility for application using
-  /// [`Arena`].
+  /// Links a spill ARENA to this ARENA, the returned [`SpillArena`] allocates from this ARENA,
+  /// and transparently retries in the `spill` ARENA when this ARENA does not have enough space.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions};
+  /// use rarena_allocator::{Arena, ArenaOptions, Tier};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let magic_version = arena.magic_version();
+  /// let tiered = arena.with_spill(Arena::new(ArenaOptions::new()));
+  /// assert_eq!(tiered.alloc_bytes(8).unwrap().tier(), Tier::Primary);
   /// ```
   #[inline]
-  pub const fn magic_version(&self) -> u16 {
-    self.magic_version
+  pub fn with_spill(&self, spill: Arena) -> SpillArena {
+    SpillArena::new(self.clone(), spill)
   }
 
-  /// Returns the number of bytes allocated by the ARENA.
+  /// Returns the version of the ARENA.
   ///
   /// # Example
   ///
@@ -928,14 +2053,17 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let allocated = arena.allocated();
+  /// let version = arena.version();
   /// ```
   #[inline]
-  pub fn allocated(&self) -> usize {
-    self.header().allocated.load(Ordering::Acquire) as usize
+  pub const fn version(&self) -> u16 {
+    self.version
   }
 
-  /// Returns the capacity of the ARENA.
+  /// Returns the last time the ARENA was flushed or unmounted, `None` if it never was.
+  ///
+  /// The timestamp is persisted in the header, so for a file backed ARENA, it tells when
+  /// the file was last written by any process.
   ///
   /// # Example
   ///
@@ -943,14 +2071,25 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let capacity = arena.capacity();
+  /// assert!(arena.last_modified().is_none());
   /// ```
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   #[inline]
-  pub const fn capacity(&self) -> usize {
-    self.cap as usize
+  pub fn last_modified(&self) -> Option<std::time::SystemTime> {
+    match self.header().last_modified.load(Ordering::Acquire) {
+      0 => None,
+      nanos => Some(std::time::UNIX_EPOCH + core::time::Duration::from_nanos(nanos)),
+    }
   }
 
-  /// Returns the number of bytes remaining bytes can be allocated by the ARENA.
+  /// Sets the root offset of the ARENA, which is stored in the header, so that the entry point of the data
+  /// structure built in a file backed ARENA can be found after reopening the file. Setting `0` clears the root.
+  ///
+  /// The ARENA does not interpret the offset, it is the caller's responsibility to keep the root allocated.
+  ///
+  /// # Panics
+  /// - If the ARENA is read-only.
   ///
   /// # Example
   ///
@@ -958,14 +2097,22 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let remaining = arena.remaining();
+  /// let mut bytes = arena.alloc_bytes(8).unwrap();
+  /// bytes.detach();
+  ///
+  /// arena.set_root(bytes.offset() as u32);
+  /// assert_eq!(arena.root(), Some(bytes.offset() as u32));
   /// ```
   #[inline]
-  pub fn remaining(&self) -> usize {
-    (self.cap as usize).saturating_sub(self.allocated())
+  pub fn set_root(&self, offset: u32) {
+    assert!(!self.ro, "ARENA is read-only");
+
+    let header = self.header();
+    let _write = self.begin_write();
+    header.root.store(offset as u64, Ordering::Release);
   }
 
-  /// Returns the number of references to the ARENA.
+  /// Returns the root offset of the ARENA set by [`set_root`](Self::set_root), `None` if not set.
   ///
   /// # Example
   ///
@@ -973,14 +2120,18 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let refs = arena.refs();
+  /// assert_eq!(arena.root(), None);
   /// ```
   #[inline]
-  pub fn refs(&self) -> usize {
-    unsafe { self.inner.as_ref().refs.load(Ordering::Acquire) }
+  pub fn root(&self) -> Option<u32> {
+    match self.header().root.load(Ordering::Acquire) {
+      0 => None,
+      offset => Some(offset as u32),
+    }
   }
 
-  /// Returns the number of bytes discarded by the ARENA.
+  /// Returns the generation of the ARENA, which is bumped by every [`clear`](Self::clear),
+  /// see [`BytesRefMut::is_valid`].
   ///
   /// # Example
   ///
@@ -988,14 +2139,18 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let discarded = arena.discarded();
+  /// assert_eq!(arena.generation(), 0);
+  ///
+  /// unsafe { arena.clear().unwrap() };
+  /// assert_eq!(arena.generation(), 1);
   /// ```
   #[inline]
-  pub fn discarded(&self) -> u32 {
-    self.header().discarded.load(Ordering::Acquire)
+  pub fn generation(&self) -> u32 {
+    self.header().generation.load(Ordering::Acquire)
   }
 
-  /// Forcelly increases the discarded bytes.
+  /// Returns the magic version of the ARENA. This value can be used to check the compatibility for application using
+  /// [`Arena`].
   ///
   /// # Example
   ///
@@ -1003,19 +2158,18 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// arena.increase_discarded(100);
+  /// let magic_version = arena.magic_version();
   /// ```
   #[inline]
-  pub fn increase_discarded(&self, size: u32) {
-    #[cfg(feature = "tracing")]
-    tracing::debug!("discard {size} bytes");
-
-    self.header().discarded.fetch_add(size, Ordering::Release);
+  pub const fn magic_version(&self) -> u16 {
+    self.magic_version
   }
 
-  /// Discards all freelist nodes in the ARENA.
+  /// Returns the number of bytes allocated by the ARENA, including the reserved bytes before the
+  /// [`data_offset`](Self::data_offset).
   ///
-  /// Returns the number of bytes discarded.
+  /// This is the high-water mark of the main memory, which never decreases when the buffers are freed
+  /// to the freelist, see [`live_bytes`](Self::live_bytes) for the bytes currently in use.
   ///
   /// # Example
   ///
@@ -1023,21 +2177,14 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// arena.discard_freelist();
+  /// let allocated = arena.allocated();
   /// ```
   #[inline]
-  pub fn discard_freelist(&self) -> Result<u32, Error> {
-    if self.ro {
-      return Err(Error::ReadOnly);
-    }
-
-    Ok(match self.freelist {
-      Freelist::None => 0,
-      _ => self.discard_freelist_in(),
-    })
+  pub fn allocated(&self) -> usize {
+    self.header().allocated.load(Ordering::Acquire) as usize
   }
 
-  /// Returns the minimum segment size of the ARENA.
+  /// Returns the capacity of the ARENA.
   ///
   /// # Example
   ///
@@ -1045,32 +2192,37 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let min_segment_size = arena.minimum_segment_size();
+  /// let capacity = arena.capacity();
   /// ```
   #[inline]
-  pub fn minimum_segment_size(&self) -> u32 {
-    self.header().min_segment_size.load(Ordering::Acquire)
+  pub const fn capacity(&self) -> usize {
+    self.cap as usize
   }
 
-  /// Sets the minimum segment size of the ARENA.
+  /// Returns the number of bytes which can be allocated from an empty ARENA, the capacity without the
+  /// bytes reserved for the header and the data alignment.
+  ///
+  /// For an ARENA created by [`Arena::new`], this is never less than [`ArenaOptions::capacity`],
+  /// whatever the [`ArenaOptions::maximum_alignment`] is.
   ///
   /// # Example
   ///
   /// ```rust
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// let arena = Arena::new(ArenaOptions::new());
-  /// arena.set_minimum_segment_size(100);
+  /// let arena = Arena::new(ArenaOptions::new().with_capacity(100).with_maximum_alignment(64));
+  /// assert!(arena.usable_capacity() >= 100);
+  /// let b = arena.alloc_bytes(arena.usable_capacity() as u32).unwrap();
   /// ```
   #[inline]
-  pub fn set_minimum_segment_size(&self, size: u32) {
-    self
-      .header()
-      .min_segment_size
-      .store(size, Ordering::Release);
+  pub const fn usable_capacity(&self) -> usize {
+    (self.cap - self.data_offset) as usize
   }
 
-  /// Returns the data offset of the ARENA. The offset is the end of the reserved bytes of the ARENA.
+  /// Returns the number of bytes remaining bytes can be allocated by the ARENA.
+  ///
+  /// Only the main memory after the high-water mark ([`allocated`](Self::allocated)) is counted,
+  /// the bytes in the freelist are not included.
   ///
   /// # Example
   ///
@@ -1078,14 +2230,19 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let data_offset = arena.data_offset();
+  /// let remaining = arena.remaining();
   /// ```
   #[inline]
-  pub const fn data_offset(&self) -> usize {
-    self.data_offset as usize
+  pub fn remaining(&self) -> usize {
+    (self.cap as usize).saturating_sub(self.allocated())
   }
 
-  /// Returns the data section of the ARENA as a byte slice, header is not included.
+  /// Returns the approximate number of bytes currently in use, i.e. the bytes between the
+  /// [`data_offset`](Self::data_offset) and the high-water mark ([`allocated`](Self::allocated)),
+  /// minus the bytes in the freelist.
+  ///
+  /// The padding of the allocations and the discarded bytes are counted as in use. The freelist
+  /// is walked, so this is not as cheap as [`allocated`](Self::allocated).
   ///
   /// # Example
   ///
@@ -1093,34 +2250,78 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let data = arena.data();
+  /// let freed = arena.alloc_bytes(40).unwrap();
+  /// let _b = arena.alloc_bytes(60).unwrap();
+  /// let allocated = arena.allocated();
+  ///
+  /// drop(freed);
+  /// assert_eq!(arena.allocated(), allocated);
+  /// assert_eq!(arena.live_bytes(), 60);
   /// ```
   #[inline]
-  pub fn data(&self) -> &[u8] {
-    unsafe {
-      let ptr = self.ptr.add(self.data_offset as usize);
-      let allocated = self.header().allocated.load(Ordering::Acquire);
-      slice::from_raw_parts(ptr, (allocated - self.data_offset) as usize)
-    }
+  pub fn live_bytes(&self) -> usize {
+    self
+      .allocated()
+      .saturating_sub(self.data_offset as usize + self.free_list_bytes() as usize)
   }
 
-  /// Returns the whole main memory of the ARENA as a byte slice.
+  /// Calls `f` until it observes a consistent view of the ARENA, i.e. no allocation, deallocation
+  /// or other writes of the watermark and the freelist are in progress or finished while `f` runs.
+  ///
+  /// The writers bump the seqlock in the header around their writes if [`ArenaOptions::with_seqlock`] is set.
+  /// The seqlock lives in the memory of the ARENA, so this also works across the processes sharing a file backed ARENA,
+  /// e.g. one maps it read-only and reads [`allocated`](Self::allocated) or [`free_segments`](Self::free_segments),
+  /// while another one writes. The view is only consistent if all the writers, in all the processes, set the option.
+  ///
+  /// `f` may run multiple times and observe torn views during the retries, so it should not have side effects,
+  /// and must not panic on inconsistent data. It must not be called by the hooks of the ARENA, e.g. [`with_on_event`](Self::with_on_event),
+  /// while allocating, otherwise it never observes a consistent view.
+  ///
+  /// Returns `None` if no consistent view is observed in [`ArenaOptions::with_maximum_retries`] retries.
   ///
   /// # Example
   ///
   /// ```rust
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// let arena = Arena::new(ArenaOptions::new());
-  /// let memory = arena.memory();
+  /// let arena = Arena::new(ArenaOptions::new().with_seqlock(true));
+  /// let (allocated, free) = arena
+  ///   .read_consistent(|| (arena.allocated(), arena.free_segments()))
+  ///   .unwrap();
+  /// assert_eq!(allocated, arena.data_offset());
+  /// assert!(free.is_empty());
   /// ```
-  #[inline]
-  pub fn allocated_memory(&self) -> &[u8] {
-    let allocated = self.header().allocated.load(Ordering::Acquire);
-    unsafe { slice::from_raw_parts(self.ptr, allocated as usize) }
+  pub fn read_consistent<F, R>(&self, mut f: F) -> Option<R>
+  where
+    F: FnMut() -> R,
+  {
+    let seq = &self.header().seq;
+    let backoff = Backoff::new();
+    for _ in 0..=self.max_retries {
+      let begin = seq.load(Ordering::Acquire);
+      // the low 32 bits count the writes in progress.
+      if begin as u32 != 0 {
+        backoff.snooze();
+        continue;
+      }
+
+      let res = f();
+      // the reads in `f` must not be reordered after the re-check.
+      fence(Ordering::Acquire);
+      if seq.load(Ordering::Acquire) == begin {
+        return Some(res);
+      }
+      backoff.snooze();
+    }
+    None
   }
 
-  /// Returns the whole main memory of the ARENA as a byte slice.
+  /// Returns a snapshot of the statistics of the ARENA.
+  ///
+  /// Reading the statistics one by one may tear when the ARENA is modified concurrently,
+  /// so `allocated` is re-read after the other fields, and the snapshot is retried
+  /// (at most [`ArenaOptions::with_maximum_retries`] times) if it changed in the meantime.
+  /// The snapshot is not linearizable, but the inconsistency is minimized.
   ///
   /// # Example
   ///
@@ -1128,14 +2329,49 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let memory = arena.memory();
+  /// let stats = arena.stats();
+  /// assert_eq!(stats.allocated() + stats.remaining(), arena.capacity());
   /// ```
-  #[inline]
-  pub const fn memory(&self) -> &[u8] {
-    unsafe { slice::from_raw_parts(self.ptr, self.cap as usize) }
+  pub fn stats(&self) -> ArenaStats {
+    let header = self.header();
+    let mut i = 0;
+    loop {
+      let allocated = header.allocated.load(Ordering::Acquire);
+      let discarded = header.discarded.load(Ordering::Acquire);
+      let free_list_bytes = self.free_list_bytes();
+      let stats = ArenaStats {
+        allocated,
+        discarded,
+        free_list_bytes,
+        cap: self.cap,
+      };
+
+      if header.allocated.load(Ordering::Acquire) == allocated || i + 1 >= self.max_retries {
+        return stats;
+      }
+      i += 1;
+    }
   }
 
-  /// Returns `true` if the arena is read-only.
+  /// Returns the number of bytes in the freelist, including the segment nodes.
+  fn free_list_bytes(&self) -> u32 {
+    let mut bytes = 0u32;
+    let (_, mut next_node_offset) =
+      decode_segment_node(self.header().sentinel.load(Ordering::Acquire));
+    while next_node_offset != SENTINEL_SEGMENT_NODE_OFFSET {
+      let Some(node) = self.get_segment_node(next_node_offset) else {
+        break;
+      };
+      let (node_size, next) = decode_segment_node(node.load(Ordering::Acquire));
+      if node_size != REMOVED_SEGMENT_NODE {
+        bytes = bytes.saturating_add(node_size + SEGMENT_NODE_SIZE as u32);
+      }
+      next_node_offset = next;
+    }
+    bytes
+  }
+
+  /// Returns the ratio of the allocated bytes to the capacity of the ARENA.
   ///
   /// # Example
   ///
@@ -1143,564 +2379,723 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  /// let read_only = arena.read_only();
+  /// let ratio = arena.used_ratio();
+  /// assert!(ratio > 0.0 && ratio < 1.0);
   /// ```
   #[inline]
-  pub const fn read_only(&self) -> bool {
-    self.ro
+  pub fn used_ratio(&self) -> f64 {
+    self.allocated() as f64 / self.cap as f64
   }
 
-  /// Sets remove on drop, only works on mmap with a file backend.
-  ///
-  /// Default is `false`.
-  ///
-  /// > **WARNING:** Once set to `true`, the backed file will be removed when the ARENA is dropped, even though the file is opened in
-  /// > read-only mode.
+  /// Returns the threshold of the discarded ratio set by [`ArenaOptions::with_compaction_threshold`].
   ///
   /// # Example
   ///
   /// ```rust
-  /// # use rarena_allocator::{Arena, ArenaOptions};
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # let arena = Arena::new(ArenaOptions::new());
-  /// arena.remove_on_drop(true);
+  /// let arena = Arena::new(ArenaOptions::new().with_compaction_threshold(0.25));
+  /// assert_eq!(arena.compaction_threshold(), Some(0.25));
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
-  pub fn remove_on_drop(&self, remove_on_drop: bool) {
-    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
-    unsafe { self.inner.as_ref().set_remove_on_drop(remove_on_drop) }
+  pub const fn compaction_threshold(&self) -> Option<f64> {
+    self.compaction_threshold
   }
 
-  /// Sets the option to make the file shrink to the used size when dropped.
-  ///
-  /// This option, when true, will indicate that the file should be shrunk to
-  /// the size of the data written to it when the file is dropped.
-  ///
-  /// Default is `false`.
+  /// Returns `true` if the ratio of the [`discarded`](Self::discarded) bytes to the capacity of the ARENA
+  /// reaches the threshold set by [`ArenaOptions::with_compaction_threshold`], e.g. the live data should be
+  /// compacted by [`compact_into`](Self::compact_into).
   ///
-  /// > **WARNING:** Once set to `true`, the backed file will be shrunk when the ARENA is dropped, even though the file is opened in
-  /// > read-only mode.
+  /// Always returns `false` if the threshold is not set.
   ///
-  /// # Examples
+  /// # Example
   ///
   /// ```rust
-  /// # use rarena_allocator::{Arena, ArenaOptions};
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # let arena = Arena::new(ArenaOptions::new());
-  /// arena.shrink_on_drop(true);
+  /// let arena = Arena::new(ArenaOptions::new().with_capacity(1000).with_compaction_threshold(0.1));
+  /// assert!(!arena.should_compact());
+  ///
+  /// arena.increase_discarded(200);
+  /// assert!(arena.should_compact());
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
-  pub fn shrink_on_drop(&self, shrink_on_drop: bool) {
-    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
-    unsafe { self.inner.as_ref().set_shrink_on_drop(shrink_on_drop) }
+  pub fn should_compact(&self) -> bool {
+    match self.compaction_threshold {
+      Some(threshold) => self.discarded() as f64 / self.cap as f64 >= threshold,
+      None => false,
+    }
   }
 
-  /// Returns the path of the mmap file, only returns `Some` when the ARENA is backed by a mmap file.
+  /// Returns the number of references to the ARENA.
   ///
   /// # Example
   ///
   /// ```rust
-  /// # use rarena_allocator::{Arena, ArenaOptions};
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # let arena = Arena::new(ArenaOptions::new());
-  /// let path = arena.path();
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let refs = arena.refs();
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
-  #[inline]
-  pub const fn path(&self) -> Option<&std::path::PathBuf> {
-    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
-    unsafe { self.inner.as_ref().path() }
-  }
-
   #[inline]
-  fn header(&self) -> &Header {
-    // Safety:
-    // The inner is always non-null, we only deallocate it when the memory refs is 1.
-    unsafe { (*self.inner.as_ptr()).header() }
+  pub fn refs(&self) -> usize {
+    unsafe { self.inner.as_ref().refs.load(Ordering::Acquire) }
   }
-}
 
-unsafe impl Send for Arena {}
-unsafe impl Sync for Arena {}
-
-impl Arena {
-  /// Creates a new ARENA with the given options.
+  /// Tries to lock the ARENA for the operations which require the exclusive access, e.g. [`clear`](Self::clear).
+  ///
+  /// Returns `None` if the ARENA is shared, i.e. [`refs`](Self::refs) is not `1`, e.g. it is cloned,
+  /// or an owned handle (e.g. a [`BytesMut`]) is alive. The handles borrowing the ARENA are ruled out
+  /// by the mutable borrow, so the allocations do not pay for tracking them.
+  ///
+  /// The detached or leaked (e.g. by [`mem::forget`](core::mem::forget)) handles which borrowed the ARENA
+  /// do not block the guard, the memory they point to is reclaimed by [`ExclusiveGuard::clear`].
   ///
   /// # Example
   ///
   /// ```rust
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(8).unwrap();
+  /// bytes.detach();
+  /// drop(bytes);
+  ///
+  /// let mut guard = arena.try_exclusive().unwrap();
+  /// guard.clear().unwrap();
+  /// drop(guard);
+  ///
+  /// assert_eq!(arena.allocated(), arena.data_offset());
   /// ```
   #[inline]
-  pub fn new(opts: ArenaOptions) -> Self {
-    let memory = Memory::new_vec(opts);
-    Self::new_in(memory, opts.maximum_retries(), opts.unify(), false)
+  pub fn try_exclusive(&mut self) -> Option<ExclusiveGuard<'_>> {
+    ExclusiveGuard::new(self)
   }
 
-  /// Creates a new ARENA backed by a mmap with the given options.
+  /// Sets the hook invoked when the ARENA does not have enough space for an allocation.
   ///
-  /// # Example
+  /// The hook receives the requested size. If it returns [`OomAction::Retry`], the allocation
+  /// is retried once more, e.g. the hook has freed some space out-of-band, otherwise the allocation
+  /// fails with [`Error::InsufficientSpace`].
   ///
-  /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// The hook is shared by the clones made afterwards, the existing clones are not affected.
   ///
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
+  /// # Example
   ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OomAction};
+  /// use std::sync::Arc;
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new()).with_on_oom(Arc::new(|_size| OomAction::Fail));
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
-  pub fn map_mut<P: AsRef<std::path::Path>>(
-    path: P,
-    opts: ArenaOptions,
-    open_options: OpenOptions,
-    mmap_options: MmapOptions,
-  ) -> std::io::Result<Self> {
-    Memory::map_mut(
-      path,
-      open_options,
-      mmap_options,
-      opts.maximum_alignment(),
-      opts.minimum_segment_size(),
-      opts.magic_version(),
-      opts.freelist(),
-    )
-    .map(|memory| Self::new_in(memory, opts.maximum_retries(), true, false))
+  pub fn with_on_oom(
+    mut self,
+    on_oom: std::sync::Arc<dyn Fn(u32) -> OomAction + Send + Sync>,
+  ) -> Self {
+    self.on_oom = Some(OomHook(on_oom));
+    self
   }
 
-  /// Opens a read only ARENA backed by a mmap with the given capacity.
+  /// Sets the hook invoked once when the used ratio of the ARENA first crosses `ratio`,
+  /// see [`used_ratio`](Self::used_ratio).
   ///
-  /// # Example
+  /// This can be used to proactively grow or flush before the ARENA runs out of memory.
+  /// The hook fires at most once, until the ARENA is cleared by [`clear`](Self::clear).
   ///
-  /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// The ratio must be in `(0, 1]`.
   ///
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
+  /// The hook is shared by the clones made afterwards, the existing clones are not affected.
   ///
-  /// # {
-  ///   # let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  ///   # let mmap_options = MmapOptions::new();
-  ///   # let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// # }
+  /// # Example
   ///
-  /// let open_options = OpenOptions::default().read(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let arena = Arena::map(&path, open_options, mmap_options, 0).unwrap();
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  /// use std::sync::Arc;
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new()).with_on_high_water(0.8, Arc::new(|| println!("80% used")));
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
-  pub fn map<P: AsRef<std::path::Path>>(
-    path: P,
-    open_options: OpenOptions,
-    mmap_options: MmapOptions,
-    magic_version: u16,
-  ) -> std::io::Result<Self> {
-    Memory::map(path, open_options, mmap_options, magic_version)
-      .map(|memory| Self::new_in(memory, 0, true, true))
+  pub fn with_on_high_water(
+    mut self,
+    ratio: f64,
+    on_high_water: std::sync::Arc<dyn Fn() + Send + Sync>,
+  ) -> Self {
+    assert!(
+      ratio > 0.0 && ratio <= 1.0,
+      "high water ratio must be in (0, 1]"
+    );
+    self.on_high_water = Some(HighWaterHook {
+      ratio,
+      f: on_high_water,
+    });
+    self
   }
 
-  /// Creates a new ARENA backed by an anonymous mmap with the given capacity.
+  /// Sets the hook invoked on every allocation, deallocation and discard of the ARENA,
+  /// which is the integration point for tracking the leaks or plugging into the tracing or metrics systems.
+  ///
+  /// Unlike the bounded trace (see `ArenaOptions::with_trace_capacity`), every event is observed.
+  /// The hook is called on the allocating or deallocating thread, so it should be cheap.
+  ///
+  /// The hook is shared by the clones made afterwards, the existing clones are not affected.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, MmapOptions};
+  /// use rarena_allocator::{Arena, ArenaEvent, ArenaOptions};
+  /// use std::sync::Arc;
   ///
-  /// let mmap_options = MmapOptions::new().len(100);
-  /// let arena = Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap();
+  /// let arena = Arena::new(ArenaOptions::new())
+  ///   .with_on_event(Arc::new(|event: ArenaEvent| println!("{event:?}")));
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
-  pub fn map_anon(opts: ArenaOptions, mmap_options: MmapOptions) -> std::io::Result<Self> {
-    Memory::map_anon(
-      mmap_options,
-      opts.maximum_alignment(),
-      opts.minimum_segment_size(),
-      opts.unify(),
-      opts.magic_version(),
-      opts.freelist(),
-    )
-    .map(|memory| Self::new_in(memory, opts.maximum_retries(), opts.unify(), false))
+  pub fn with_on_event(
+    mut self,
+    on_event: std::sync::Arc<dyn Fn(ArenaEvent) + Send + Sync>,
+  ) -> Self {
+    self.on_event = Some(EventHook(on_event));
+    self
   }
 
-  /// Locks the underlying file for exclusive access, only works on mmap with a file backend.
+  /// Returns the number of bytes discarded by the ARENA.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
-  ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// arena.lock_exclusive().unwrap();
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let discarded = arena.discarded();
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
-  pub fn lock_exclusive(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().lock_exclusive() }
+  #[inline]
+  pub fn discarded(&self) -> u32 {
+    self.header().discarded.load(Ordering::Acquire)
   }
 
-  /// Locks the underlying file for shared access, only works on mmap with a file backend.
+  /// Returns the number of bytes discarded for the padding and the node of the freelist segments.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
-  ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// arena.lock_shared().unwrap();
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let discarded = arena.discarded_padding();
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
-  pub fn lock_shared(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().lock_shared() }
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+  #[inline]
+  pub fn discarded_padding(&self) -> u32 {
+    self.header().discarded_padding.load(Ordering::Acquire)
   }
 
-  /// Try to lock the underlying file for exclusive access, only works on mmap with a file backend.
+  /// Returns the number of bytes discarded because the deallocated memory is too small to become
+  /// a freelist segment, or the freelist is disabled.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// arena.try_lock_exclusive().unwrap();
-  ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let discarded = arena.discarded_small_segments();
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
-  pub fn try_lock_exclusive(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().try_lock_exclusive() }
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+  #[inline]
+  pub fn discarded_small_segments(&self) -> u32 {
+    self
+      .header()
+      .discarded_small_segments
+      .load(Ordering::Acquire)
   }
 
-  /// Try to lock the underlying file for shared access, only works on mmap with a file backend.
+  /// Returns the number of bytes discarded by [`increase_discarded`](Self::increase_discarded),
+  /// [`discard_freelist`](Self::discard_freelist) and [`prune_free_list`](Self::prune_free_list).
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
-  ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// arena.try_lock_shared().unwrap();
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// arena.increase_discarded(100);
+  /// assert_eq!(arena.discarded_manual(), 100);
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
-  pub fn try_lock_shared(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().try_lock_shared() }
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+  #[inline]
+  pub fn discarded_manual(&self) -> u32 {
+    self.header().discarded_manual.load(Ordering::Acquire)
   }
 
-  /// Unlocks the underlying file, only works on mmap with a file backend.
+  /// Returns the total number of the freelist nodes visited by the walks of the freelist, i.e. finding the position
+  /// to insert a deallocated segment, or finding the best fit segment for an allocation with [`Freelist::Pessimistic`].
+  ///
+  /// The counter is shared by the clones of the ARENA, and is not persisted.
+  /// A large number relative to the number of the allocations implies that
+  /// [`prune_free_list`](Self::prune_free_list) may help.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
-  ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// arena.lock_exclusive().unwrap();
-  ///
-  /// // do some thing
-  /// arena.unlock().unwrap();
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// assert_eq!(arena.free_list_traversals(), 0);
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
   #[inline]
-  pub fn unlock(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().unlock() }
+  pub fn free_list_traversals(&self) -> u64 {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref() }
+      .walks
+      .visited
+      .load(core::sync::atomic::Ordering::Relaxed)
   }
 
-  /// Flushes the memory-mapped file to disk.
+  /// Returns the maximum number of the freelist nodes visited by a single walk of the freelist,
+  /// see [`free_list_traversals`](Self::free_list_traversals).
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
-  ///
-  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  /// arena.flush().unwrap();
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// assert_eq!(arena.max_free_list_depth(), 0);
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  pub fn flush(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().flush() }
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+  #[inline]
+  pub fn max_free_list_depth(&self) -> u64 {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref() }
+      .walks
+      .max_depth
+      .load(core::sync::atomic::Ordering::Relaxed)
   }
 
-  /// Flushes the memory-mapped file to disk asynchronously.
+  /// Returns the number of the attempts to allocate from the freelist, i.e. the slow path, which is taken
+  /// when the main memory cannot satisfy an allocation, see [`ArenaOptions::with_maximum_retries`].
+  ///
+  /// The counter is shared by the clones of the ARENA, and is not persisted.
   ///
   /// # Example
   ///
   /// ```rust
-  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
-  ///
-  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
-  /// # std::fs::remove_file(&path);
-  /// let open_options = OpenOptions::default().create(Some(100)).read(true).write(true);
-  /// let mmap_options = MmapOptions::new();
-  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
-  ///
-  /// arena.flush_async().unwrap();
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # std::fs::remove_file(path);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// assert_eq!(arena.slow_path_attempts(), 0);
   /// ```
-  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  pub fn flush_async(&self) -> std::io::Result<()> {
-    unsafe { self.inner.as_ref().flush_async() }
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+  #[inline]
+  pub fn slow_path_attempts(&self) -> u64 {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref() }
+      .walks
+      .slow_path_attempts
+      .load(core::sync::atomic::Ordering::Relaxed)
   }
 
-  /// Allocates an owned slice of memory in the ARENA.
-  ///
-  /// The cost of this method is an extra atomic operation, compared to [`alloc_bytes`](Self::alloc_bytes).
+  #[cfg(feature = "metrics")]
   #[inline]
-  pub fn alloc_bytes_owned(&self, size: u32) -> Result<BytesMut, Error> {
-    self.alloc_bytes(size).map(|mut b| b.to_owned())
+  fn count_slow_path_attempt(&self) {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref() }
+      .walks
+      .slow_path_attempts
+      .fetch_add(1, core::sync::atomic::Ordering::Relaxed);
   }
 
-  /// Allocates a slice of memory in the ARENA.
-  ///
-  /// The [`BytesRefMut`] is zeroed out.
-  ///
-  /// If you want a [`BytesMut`], see [`alloc_bytes_owned`](Self::alloc_bytes_owned).
+  #[cfg(feature = "metrics")]
   #[inline]
-  pub fn alloc_bytes(&self, size: u32) -> Result<BytesRefMut, Error> {
-    self.alloc_bytes_in(size).map(|a| match a {
-      None => BytesRefMut::null(self),
-      Some(allocated) => unsafe { BytesRefMut::new(self, allocated) },
-    })
+  fn walk_counter(&self) -> WalkCounter<'_> {
+    WalkCounter {
+      // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+      walks: unsafe { &self.inner.as_ref().walks },
+      depth: 0,
+    }
   }
 
-  /// Allocates an owned byte slice that can hold a well-aligned `T` and extra `size` bytes.
+  /// Drains the allocation trace, returns the recorded events from the oldest to the newest.
   ///
-  /// The layout of the allocated memory is:
-  ///
-  /// ```text
-  /// | T | [u8; size] |
-  /// ```
+  /// The trace is shared by all the clones of the ARENA, and it is always empty
+  /// if [`ArenaOptions::with_trace_capacity`] is not set.
   ///
   /// # Example
   ///
-  /// ```ignore
-  /// let mut bytes = arena.alloc_aligned_bytes_owned::<T>(extra).unwrap();
-  /// bytes.put(val).unwrap(); // write `T` to the byte slice.
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, TraceOp};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new().with_trace_capacity(16));
+  /// let b = arena.alloc_bytes(8).unwrap();
+  /// drop(b);
+  ///
+  /// let trace = arena.drain_trace();
+  /// assert_eq!(trace[0].op(), TraceOp::Alloc);
+  /// assert_eq!(trace[1].op(), TraceOp::Dealloc);
   /// ```
+  #[cfg(feature = "trace")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
   #[inline]
-  pub fn alloc_aligned_bytes_owned<T>(&self, size: u32) -> Result<BytesMut, Error> {
+  pub fn drain_trace(&self) -> std::vec::Vec<TraceEvent> {
     self
-      .alloc_aligned_bytes::<T>(size)
-      .map(|mut b| b.to_owned())
+      .tracer
+      .as_ref()
+      .map(|tracer| tracer.drain())
+      .unwrap_or_default()
   }
 
-  /// Allocates a byte slice that can hold a well-aligned `T` and extra `size` bytes.
-  ///
-  /// The layout of the allocated memory is:
-  ///
-  /// ```text
-  /// | T | [u8; size] |
-  /// ```
+  /// Forcelly increases the discarded bytes.
   ///
   /// # Example
   ///
-  /// ```ignore
-  /// let mut bytes = arena.alloc_aligned_bytes::<T>(extra).unwrap();
-  /// bytes.put(val).unwrap(); // write `T` to the byte slice.
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// arena.increase_discarded(100);
   /// ```
   #[inline]
-  pub fn alloc_aligned_bytes<T>(&self, size: u32) -> Result<BytesRefMut, Error> {
-    self.alloc_aligned_bytes_in::<T>(size).map(|a| match a {
-      None => BytesRefMut::null(self),
-      Some(allocated) => unsafe { BytesRefMut::new(self, allocated) },
-    })
+  pub fn increase_discarded(&self, size: u32) {
+    self.discard(0, size, Discard::Manual);
   }
 
-  /// Allocates a `T` in the ARENA.
+  /// Resets the discarded bytes (and the breakdown of them if the `metrics` feature is enabled) to zero,
+  /// e.g. after a tuning phase.
   ///
-  /// # Safety
+  /// Unlike [`clear`](Self::clear), the allocations and the freelist are untouched, so this method is safe
+  /// to be called concurrently. This method does nothing if the ARENA is read-only.
   ///
-  /// - If `T` needs to be dropped and callers invoke [`RefMut::detach`],
-  ///   then the caller must ensure that the `T` is dropped before the ARENA is dropped.
-  ///   Otherwise, it will lead to memory leaks.
+  /// # Example
   ///
-  /// - If this is file backed ARENA, then `T` must be recoverable from bytes.
-  ///   1. Types require allocation are not recoverable.
-  ///   2. Pointers are not recoverable, like `*const T`, `*mut T`, `NonNull` and any structs contains pointers,
-  ///      although those types are on stack, but they cannot be recovered, when reopens the file.
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// # Examples
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// arena.increase_discarded(100);
+  /// arena.reset_discarded();
+  /// assert_eq!(arena.discarded(), 0);
+  /// ```
+  #[inline]
+  pub fn reset_discarded(&self) {
+    if self.ro {
+      return;
+    }
+
+    let header = self.header();
+    header.discarded.store(0, Ordering::Release);
+    #[cfg(feature = "metrics")]
+    {
+      header.discarded_padding.store(0, Ordering::Release);
+      header.discarded_small_segments.store(0, Ordering::Release);
+      header.discarded_manual.store(0, Ordering::Release);
+    }
+  }
+
+  #[inline]
+  fn discard(&self, _offset: u32, size: u32, _kind: Discard) {
+    #[cfg(feature = "trace")]
+    self.trace(TraceOp::Discard, _offset, size);
+    self.emit(ArenaEvent::Discard { size });
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!("discard {size} bytes");
+
+    let header = self.header();
+    header.discarded.fetch_add(size, Ordering::Release);
+
+    #[cfg(feature = "metrics")]
+    match _kind {
+      Discard::Padding => &header.discarded_padding,
+      Discard::SmallSegment => &header.discarded_small_segments,
+      Discard::Manual => &header.discarded_manual,
+    }
+    .fetch_add(size, Ordering::Release);
+  }
+
+  /// Discards all freelist nodes in the ARENA.
   ///
-  /// ## Memory leak
+  /// Returns the number of bytes discarded.
   ///
-  /// The following example demonstrates the memory leak when the `T` is a heap allocated type and detached.
+  /// # Example
   ///
-  /// ```ignore
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  ///
-  /// {
-  ///   let mut data = arena.alloc::<Vec<u8>>().unwrap();
-  ///   data.detach();
-  ///   data.write(vec![1, 2, 3]);
-  /// }
-  ///
-  /// drop(arena); // memory leak, the `Vec<u8>` is not dropped.
+  /// arena.discard_freelist();
   /// ```
+  #[inline]
+  pub fn discard_freelist(&self) -> Result<u32, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    Ok(match self.freelist {
+      Freelist::None => 0,
+      _ => self.discard_freelist_in(),
+    })
+  }
+
+  /// Walks the freelist once and checks that it is well-formed:
   ///
-  /// ## Undefined behavior
-  ///
-  /// The following example demonstrates the undefined behavior when the `T` is not recoverable.
+  /// - the walk terminates, i.e. there is no cycle in the freelist.
+  /// - the segments are ordered by their sizes as the [`Freelist`] kind requires.
+  /// - every segment node is in `[data_offset, cap)`.
+  /// - every segment is in the allocated memory `[data_offset, allocated)`, the bytes after
+  ///   `allocated` still belong to the main memory.
+  /// - no two segments overlap.
   ///
-  /// ```ignore
+  /// Returns [`Error::CorruptedFreeList`] identifying the first violation.
   ///
-  /// struct TypeOnHeap {
-  ///   data: Vec<u8>,
-  /// }
+  /// The freelist must not be modified concurrently, otherwise the result is meaningless.
   ///
-  /// let arena = Arena::map_mut("path/to/file", ArenaOptions::new(), OpenOptions::create_new(Some(1000)).read(true).write(true), MmapOptions::default()).unwrap();
+  /// # Example
   ///
-  /// let mut data = arena.alloc::<TypeOnHeap>().unwrap();
-  /// data.detach();
-  /// data.write(TypeOnHeap { data: vec![1, 2, 3] });
-  /// let offset = data.offset();
-  /// drop(arena);
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// // reopen the file
-  /// let arena = Arena::map("path/to/file", OpenOptions::read(true), MmapOptions::default()).unwrap();
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let b = arena.alloc_bytes(100).unwrap();
+  /// let _guard = arena.alloc_bytes(8).unwrap();
+  /// drop(b);
   ///
-  /// let foo = &*arena.get_aligned_pointer::<TypeOnHeap>(offset as usize);
-  /// let b = foo.data[1]; // undefined behavior, the `data`'s pointer stored in the file is not valid anymore.
+  /// let report = arena.validate_free_list().unwrap();
+  /// assert_eq!(report.segments(), 1);
   /// ```
+  pub fn validate_free_list(&self) -> Result<FreeListReport, Error> {
+    let corrupted = |offset, violation| Err(Error::CorruptedFreeList { offset, violation });
+
+    let allocated = self.header().allocated.load(Ordering::Acquire) as u64;
+    // every segment holds at least a node, so a well-formed freelist never has more nodes than this.
+    let max_nodes = self.cap as usize / SEGMENT_NODE_SIZE;
+
+    let mut segments = std::vec::Vec::new();
+    let mut prev_size = None;
+    let mut free_bytes = 0usize;
+    let (_, mut next_node_offset) =
+      decode_segment_node(self.header().sentinel.load(Ordering::Acquire));
+    let mut nodes = 0;
+    while next_node_offset != SENTINEL_SEGMENT_NODE_OFFSET {
+      nodes += 1;
+      if nodes > max_nodes {
+        return corrupted(next_node_offset, FreeListViolation::Cycle);
+      }
+
+      let Some(node) = self.get_segment_node(next_node_offset) else {
+        return corrupted(next_node_offset, FreeListViolation::OutOfBounds);
+      };
+      let (node_size, next) = decode_segment_node(node.load(Ordering::Acquire));
+      if node_size != REMOVED_SEGMENT_NODE {
+        let end = next_node_offset as u64 + SEGMENT_NODE_SIZE as u64 + node_size as u64;
+        if end > allocated {
+          return corrupted(next_node_offset, FreeListViolation::Unallocated);
+        }
+
+        let ordered = match (self.freelist, prev_size) {
+          (Freelist::Optimistic, Some(prev)) => node_size <= prev,
+          (Freelist::Pessimistic, Some(prev)) => node_size >= prev,
+          _ => true,
+        };
+        if !ordered {
+          return corrupted(next_node_offset, FreeListViolation::Unordered);
+        }
+
+        prev_size = Some(node_size);
+        free_bytes += node_size as usize + SEGMENT_NODE_SIZE;
+        segments.push((next_node_offset as u64, end));
+      }
+      next_node_offset = next;
+    }
+
+    segments.sort_unstable();
+    if let Some(w) = segments.windows(2).find(|w| w[1].0 < w[0].1) {
+      return corrupted(w[1].0 as u32, FreeListViolation::Overlapping);
+    }
+
+    Ok(FreeListReport {
+      segments: segments.len(),
+      free_bytes,
+    })
+  }
+
+  /// Returns the segments in the freelist as `(offset, size)` pairs, in the order of the freelist.
   ///
-  /// ## Good practice
+  /// The `offset` is the offset of the segment node, and the `size` includes the segment node,
+  /// so the result can be passed to [`rebuild_free_list`](Self::rebuild_free_list) as is.
   ///
-  /// Some examples about how to use this method correctly.
+  /// # Example
   ///
-  /// ### Heap allocated type with carefull memory management
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// ```ignore
   /// let arena = Arena::new(ArenaOptions::new());
+  /// assert!(arena.free_segments().is_empty());
+  /// ```
+  pub fn free_segments(&self) -> std::vec::Vec<(u32, u32)> {
+    let mut segments = std::vec::Vec::new();
+    let (_, mut next_node_offset) =
+      decode_segment_node(self.header().sentinel.load(Ordering::Acquire));
+    while next_node_offset != SENTINEL_SEGMENT_NODE_OFFSET {
+      let Some(node) = self.get_segment_node(next_node_offset) else {
+        break;
+      };
+      let (node_size, next) = decode_segment_node(node.load(Ordering::Acquire));
+      if node_size != REMOVED_SEGMENT_NODE {
+        segments.push((next_node_offset, node_size + SEGMENT_NODE_SIZE as u32));
+      }
+      next_node_offset = next;
+    }
+    segments
+  }
+
+  /// Divides the allocated memory `[data_offset, allocated)` into `granularity` bytes buckets, the last one
+  /// may be shorter, and returns whether each bucket is occupied, i.e. not fully covered by the free segments,
+  /// see [`free_segments`](Self::free_segments). The adjacent free segments are merged, so a bucket spanning
+  /// several of them is still free.
   ///
-  /// // Do not invoke detach, so when the data is dropped, the drop logic will be handled by the ARENA.
-  /// // automatically.
-  /// {
-  ///   let mut data = arena.alloc::<Vec<u8>>().unwrap();
-  ///   data.write(vec![1, 2, 3]);
-  /// }
+  /// This is a diagnostic, e.g. for visualizing the fragmentation. Under concurrent allocations or
+  /// deallocations, the result is a best-effort snapshot.
   ///
+  /// # Panics
+  /// - If `granularity` is `0`.
   ///
-  /// let mut detached_data = arena.alloc::<Vec<u8>>().unwrap();
-  /// detached_data.detach();
-  /// detached_data.write(vec![4, 5, 6]);
+  /// # Example
   ///
-  /// // some other logic
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// core::ptr::drop_in_place(detached_data.as_mut()); // drop the `Vec` manually.
+  /// let arena = Arena::new(ArenaOptions::new().with_capacity(1024));
+  /// let a = arena.alloc_bytes(64).unwrap();
+  /// let _b = arena.alloc_bytes(64).unwrap();
+  /// drop(a);
   ///
-  /// drop(arena); // it is safe, the `Vec` is already dropped.
+  /// assert_eq!(arena.occupancy_bitmap(64), [false, true]);
   /// ```
+  pub fn occupancy_bitmap(&self, granularity: u32) -> std::vec::Vec<bool> {
+    assert!(granularity > 0, "granularity must be greater than 0");
+
+    let mut segments = self.free_segments();
+    segments.sort_unstable();
+    let mut free = std::vec::Vec::<(u32, u32)>::with_capacity(segments.len());
+    for (offset, size) in segments {
+      let end = offset + size;
+      match free.last_mut() {
+        Some((_, last_end)) if *last_end >= offset => *last_end = (*last_end).max(end),
+        _ => free.push((offset, end)),
+      }
+    }
+
+    let start = self.data_offset;
+    let end = self.allocated() as u32;
+    let mut free = free.into_iter().peekable();
+    let mut bitmap = std::vec::Vec::new();
+    let mut bucket_start = start;
+    while bucket_start < end {
+      let bucket_end = bucket_start.saturating_add(granularity).min(end);
+      while matches!(free.peek(), Some(&(_, free_end)) if free_end <= bucket_start) {
+        free.next();
+      }
+      let covered = matches!(
+        free.peek(),
+        Some(&(free_start, free_end)) if free_start <= bucket_start && bucket_end <= free_end
+      );
+      bitmap.push(!covered);
+      bucket_start = bucket_end;
+    }
+    bitmap
+  }
+
+  /// Resets the freelist and rebuilds it from the given `(offset, size)` segments,
+  /// e.g. the segments returned by [`free_segments`](Self::free_segments) of a snapshot.
   ///
-  /// ### Recoverable type with file backed ARENA
-  ///
-  /// ```ignore
-  ///
-  /// struct Recoverable {
-  ///   field1: u64,
-  ///   field2: AtomicU32,
-  /// }
+  /// The segments are inserted in the order of their sizes, as the freelist requires. Each segment
+  /// must be in the allocated memory of the ARENA, must not overlap with others, and must be large
+  /// enough to hold a segment node and [`ArenaOptions::minimum_segment_size`] bytes,
+  /// otherwise [`Error::InvalidSegment`] is returned and the freelist is left empty.
   ///
-  /// let arena = Arena::map_mut("path/to/file", ArenaOptions::new(), OpenOptions::create_new(Some(1000)).read(true).write(true), MmapOptions::default()).unwrap();
+  /// # Safety
+  /// - This method is not lock-free, there must be no concurrent allocations or deallocations
+  ///   on the ARENA, e.g. it is called on a freshly restored ARENA.
+  /// - The segments must not be used by any allocated buffers.
   ///
-  /// let mut data = arena.alloc::<Recoverable>().unwrap();
-  /// data.write(Recoverable { field1: 10, field2: AtomicU32::new(20) });
-  /// data.detach();
-  /// let offset = data.offset();
-  /// drop(arena);
+  /// # Example
   ///
-  /// // reopen the file
-  /// let arena = Arena::map("path/to/file", OpenOptions::read(true), MmapOptions::default()).unwrap();
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
   ///
-  /// let foo = &*arena.get_aligned_pointer::<Recoverable>(offset as usize);
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(200).unwrap();
+  /// b.detach();
   ///
-  /// assert_eq!(foo.field1, 10);
-  /// assert_eq!(foo.field2.load(Ordering::Acquire), 20);
+  /// let segment = (b.memory_offset() as u32, b.memory_capacity() as u32);
+  /// unsafe { arena.rebuild_free_list(&[segment]).unwrap() };
+  /// assert_eq!(arena.free_segments().len(), 1);
   /// ```
-  #[inline]
-  pub unsafe fn alloc<T>(&self) -> Result<RefMut<'_, T>, Error> {
-    if mem::size_of::<T>() == 0 {
-      return Ok(RefMut::new_zst(self));
+  pub unsafe fn rebuild_free_list(&self, segments: &[(u32, u32)]) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
     }
 
-    let allocated = self
-      .alloc_in::<T>()?
-      .expect("allocated size is not zero, but get None");
-    let ptr = unsafe { self.get_aligned_pointer_mut::<T>(allocated.memory_offset as usize) };
-    if mem::needs_drop::<T>() {
-      unsafe {
-        let ptr: *mut MaybeUninit<T> = ptr.as_ptr().cast();
-        ptr::write(ptr, MaybeUninit::uninit());
+    let header = self.header();
+    let _write = self.begin_write();
+    header.sentinel.store(
+      encode_segment_node(SENTINEL_SEGMENT_NODE_OFFSET, SENTINEL_SEGMENT_NODE_OFFSET),
+      Ordering::Release,
+    );
 
-        Ok(RefMut::new(ptr::read(ptr), allocated, self))
+    if segments.is_empty() {
+      return Ok(());
+    }
+
+    if let Freelist::None = self.freelist {
+      let (offset, size) = segments[0];
+      return Err(Error::InvalidSegment { offset, size });
+    }
+
+    let mut sorted = segments.to_vec();
+    sorted.sort_unstable();
+
+    let allocated = header.allocated.load(Ordering::Acquire) as u64;
+    let mut end = self.data_offset as u64;
+    for &(offset, size) in sorted.iter() {
+      if (offset as u64) < end
+        || offset as u64 + size as u64 > allocated
+        || !self.validate_segment(offset, size)
+      {
+        return Err(Error::InvalidSegment { offset, size });
       }
-    } else {
-      Ok(RefMut::new_inline(ptr, allocated, self))
+      end = offset as u64 + size as u64;
+    }
+
+    sorted.sort_unstable_by_key(|&(_, size)| size);
+    for (offset, size) in sorted {
+      match self.freelist {
+        Freelist::Optimistic => self.optimistic_dealloc(offset, size),
+        Freelist::Pessimistic => self.pessimistic_dealloc(offset, size),
+        Freelist::None => unreachable!(),
+      };
     }
+    Ok(())
   }
 
-  /// Allocates a `T` in the ARENA. Like [`alloc`](Self::alloc), but returns an `Owned`.
+  /// Removes all the freelist nodes whose size is less than `min_keep` from the freelist,
+  /// the memory of the removed nodes is discarded.
   ///
-  /// The cost is one more atomic operation than [`alloc`](Self::alloc).
+  /// Tiny segments which are too small to satisfy real requests still cost traversal time in
+  /// the slow path of allocation, this trades the reclaimable bytes for faster freelist operations.
   ///
-  /// # Safety
+  /// Returns the number of the removed nodes.
   ///
-  /// - See [`alloc`](Self::alloc) for safety.
+  /// # Safety
+  /// - This method is not lock-free, there must be no concurrent allocations or deallocations
+  ///   on the ARENA (e.g. [`refs`](Self::refs) returns `1` and no buffers are dropped concurrently).
   ///
   /// # Example
   ///
@@ -1708,162 +3103,2901 @@ impl Arena {
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  ///
-  /// unsafe {
-  ///   let mut data = arena.alloc_owned::<u64>().unwrap();
-  ///   data.write(10);
-  ///
-  ///   assert_eq!(*data.as_ref(), 10);
-  /// }
+  /// let removed = unsafe { arena.prune_free_list(64).unwrap() };
+  /// assert_eq!(removed, 0);
   /// ```
-  #[inline]
-  pub unsafe fn alloc_owned<T>(&self) -> Result<Owned<T>, Error> {
-    self.alloc::<T>().map(|mut r| r.to_owned())
+  pub unsafe fn prune_free_list(&self, min_keep: u32) -> Result<usize, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    if let Freelist::None = self.freelist {
+      return Ok(0);
+    }
+
+    let _write = self.begin_write();
+    let mut removed = 0;
+    let mut prev: &AtomicU64 = &self.header().sentinel;
+    loop {
+      let prev_node = prev.load(Ordering::Acquire);
+      let (prev_node_size, current_node_offset) = decode_segment_node(prev_node);
+      if current_node_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+        return Ok(removed);
+      }
+
+      let Some(current) = self.get_segment_node(current_node_offset) else {
+        return Ok(removed);
+      };
+      let (current_node_size, next_node_offset) =
+        decode_segment_node(current.load(Ordering::Acquire));
+      if current_node_size >= min_keep {
+        prev = current;
+        continue;
+      }
+
+      // unlink the current node, no other threads touch the freelist, so plain stores are enough.
+      current.store(
+        encode_segment_node(REMOVED_SEGMENT_NODE, next_node_offset),
+        Ordering::Release,
+      );
+      prev.store(
+        encode_segment_node(prev_node_size, next_node_offset),
+        Ordering::Release,
+      );
+      self.discard(current_node_offset, current_node_size, Discard::Manual);
+      removed += 1;
+    }
   }
 
-  /// Clear the ARENA.
-  ///
-  /// # Safety
-  /// - The current pointers get from the ARENA cannot be used anymore after calling this method.
-  /// - This method is not thread-safe.
-  ///
-  /// # Examples
+  /// Merges the physically adjacent segments in the freelist into maximal runs, which is the batch
+  /// compaction of the free space (no data is moved), e.g. called during the idle periods.
   ///
-  /// Undefine behavior:
-  ///
-  /// ```ignore
-  /// let mut data = arena.alloc::<Vec<u8>>().unwrap();
+  /// The segment nodes of the merged segments become the data of the runs, so their bytes are
+  /// no longer counted as [`discarded`](Self::discarded).
   ///
-  /// arena.clear();
+  /// Returns the number of merges performed.
   ///
-  /// data.write(vec![1, 2, 3]); // undefined behavior
-  /// ```
+  /// # Safety
+  /// - This method is not lock-free, there must be no concurrent allocations or deallocations
+  ///   on the ARENA (e.g. [`refs`](Self::refs) returns `1` and no buffers are dropped concurrently),
+  ///   see [`ExclusiveGuard::consolidate`] for the checked version.
   ///
-  /// Good practice:
+  /// # Example
   ///
   /// ```rust
   /// use rarena_allocator::{Arena, ArenaOptions};
   ///
   /// let arena = Arena::new(ArenaOptions::new());
-  ///
-  /// unsafe {
-  ///   let mut data = arena.alloc::<Vec<u8>>().unwrap();
-  ///   data.write(vec![1, 2, 3]);
-  ///
-  ///   arena.clear().unwrap();
-  /// }
-  ///
+  /// let a = arena.alloc_bytes(64).unwrap();
+  /// let b = arena.alloc_bytes(64).unwrap();
+  /// let mut tail = arena.alloc_bytes(8).unwrap();
+  /// tail.detach();
+  /// drop(a);
+  /// drop(b);
+  ///
+  /// let merged = unsafe { arena.consolidate().unwrap() };
+  /// assert_eq!(merged, 1);
+  /// assert_eq!(arena.free_segments().len(), 1);
   /// ```
-  pub unsafe fn clear(&self) -> Result<(), Error> {
+  pub unsafe fn consolidate(&self) -> Result<usize, Error> {
     if self.ro {
       return Err(Error::ReadOnly);
     }
 
-    let memory = &mut *self.inner.as_ptr();
-    memory.clear();
+    if let Freelist::None = self.freelist {
+      return Ok(0);
+    }
+
+    self.validate_free_list()?;
+    let _write = self.begin_write();
+    let mut segments = self.free_segments();
+    segments.sort_unstable();
+
+    let nodes = segments.len();
+    let mut merged: std::vec::Vec<(u32, u32)> = std::vec::Vec::with_capacity(nodes);
+    for (offset, size) in segments {
+      match merged.last_mut() {
+        Some(last) if last.0 as u64 + last.1 as u64 == offset as u64 => last.1 += size,
+        _ => merged.push((offset, size)),
+      }
+    }
 
-    Ok(())
+    let merges = nodes - merged.len();
+    if merges == 0 {
+      return Ok(0);
+    }
+
+    // the nodes are discarded again when the freelist is rebuilt.
+    let header = self.header();
+    let reclaimed = (nodes * SEGMENT_NODE_SIZE) as u32;
+    let _ = header
+      .discarded
+      .fetch_update(Ordering::AcqRel, Ordering::Acquire, |discarded| {
+        Some(discarded.saturating_sub(reclaimed))
+      });
+    #[cfg(feature = "metrics")]
+    let _ =
+      header
+        .discarded_padding
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |discarded| {
+          Some(discarded.saturating_sub(reclaimed))
+        });
+
+    self.rebuild_free_list(&merged)?;
+    Ok(merges)
   }
 
-  /// Deallocates the memory at the given offset and size, the `offset..offset + size` will be made to a segment,
-  /// returns `true` if the deallocation is successful.
+  /// Lowers the watermark [`allocated`](Self::allocated) over the free segments which end exactly at it,
+  /// so that the tail space stranded in the freelist can be bump allocated again, e.g. by a large
+  /// request which no single segment could satisfy.
+  ///
+  /// The segments are unlinked from the freelist one by one, until no segment ends at the watermark,
+  /// and the bytes of their segment nodes are no longer counted as [`discarded`](Self::discarded).
+  ///
+  /// Returns the number of bytes reclaimed.
   ///
   /// # Safety
-  /// - you must ensure the same `offset..offset + size` is not deallocated twice.
-  /// - `offset` must be larger than the [`Arena::data_offset`].
-  /// - `offset + size` must be less than the [`Arena::allocated`].
-  #[inline]
-  pub unsafe fn dealloc(&self, offset: u32, size: u32) -> bool {
-    // first try to deallocate the memory back to the main memory.
-    let header = self.header();
-    // if the offset + size is the current allocated size, then we can deallocate the memory back to the main memory.
-    if header
-      .allocated
-      .compare_exchange(offset + size, offset, Ordering::SeqCst, Ordering::Relaxed)
-      .is_ok()
-    {
-      return true;
+  /// - This method is not lock-free, there must be no concurrent allocations or deallocations
+  ///   on the ARENA (e.g. [`refs`](Self::refs) returns `1` and no buffers are dropped concurrently),
+  ///   see [`ExclusiveGuard::reclaim_tail`] for the checked version.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let a = arena.alloc_bytes(64).unwrap();
+  /// let b = arena.alloc_bytes(64).unwrap();
+  /// drop(a);
+  /// // the last allocation goes back to the main memory, the segment of `a` is stranded before it.
+  /// drop(b);
+  /// assert_eq!(arena.allocated(), arena.data_offset() + 64);
+  ///
+  /// let reclaimed = unsafe { arena.reclaim_tail().unwrap() };
+  /// assert_eq!(reclaimed, 64);
+  /// assert_eq!(arena.allocated(), arena.data_offset());
+  /// ```
+  pub unsafe fn reclaim_tail(&self) -> Result<usize, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
     }
 
-    match self.freelist {
-      Freelist::None => {
-        self.increase_discarded(size);
-        true
-      }
-      Freelist::Optimistic => self.optimistic_dealloc(offset, size),
-      Freelist::Pessimistic => self.pessimistic_dealloc(offset, size),
+    if let Freelist::None = self.freelist {
+      return Ok(0);
     }
-  }
 
-  /// Returns the free list position to insert the value.
-  /// - `None` means that we should insert to the head.
-  /// - `Some(offset)` means that we should insert after the offset. offset -> new -> next
-  fn find_position(&self, val: u32, check: impl Fn(u32, u32) -> bool) -> (u64, &AtomicU64) {
     let header = self.header();
-    let mut current: &AtomicU64 = &header.sentinel;
-    let mut current_node = current.load(Ordering::Acquire);
-    let (mut current_node_size, mut next_offset) = decode_segment_node(current_node);
-    let backoff = Backoff::new();
+    let _write = self.begin_write();
+    let mut reclaimed = 0;
     loop {
-      // the list is empty
-      if current_node_size == SENTINEL_SEGMENT_NODE_SIZE
-        && next_offset == SENTINEL_SEGMENT_NODE_OFFSET
-      {
-        return (current_node, current);
-      }
-
-      // the current is marked as remove and the next is the tail.
-      if current_node_size == REMOVED_SEGMENT_NODE && next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
-        return (current_node, current);
-      }
+      let allocated = header.allocated.load(Ordering::Acquire);
+      let Some((offset, size)) = self
+        .free_segments()
+        .into_iter()
+        .find(|&(offset, size)| offset as u64 + size as u64 == allocated as u64)
+      else {
+        break;
+      };
 
-      if current_node_size == REMOVED_SEGMENT_NODE {
-        current = if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
-          backoff.snooze();
-          &header.sentinel
-        } else {
-          self.get_segment_node(next_offset)
-        };
-        current_node = current.load(Ordering::Acquire);
-        (current_node_size, next_offset) = decode_segment_node(current_node);
-        continue;
+      if !self.take_segment(offset, size) {
+        break;
       }
 
-      // the next is the tail, then we should insert the value after the current node.
-      if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
-        return (current_node, current);
-      }
+      // no other threads touch the watermark, so a plain store is enough.
+      header.allocated.store(offset, Ordering::Release);
+      let _ = header
+        .discarded
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |discarded| {
+          Some(discarded.saturating_sub(SEGMENT_NODE_SIZE as u32))
+        });
+      #[cfg(feature = "metrics")]
+      let _ =
+        header
+          .discarded_padding
+          .fetch_update(Ordering::AcqRel, Ordering::Acquire, |discarded| {
+            Some(discarded.saturating_sub(SEGMENT_NODE_SIZE as u32))
+          });
+      reclaimed += size as usize;
+    }
 
-      let next = self.get_segment_node(next_offset);
-      let next_node = next.load(Ordering::Acquire);
-      let (next_node_size, next_next_offset) = decode_segment_node(next_node);
-      if next_node_size == REMOVED_SEGMENT_NODE {
-        backoff.snooze();
-        continue;
-      }
+    Ok(reclaimed)
+  }
 
-      if check(val, next_node_size) {
-        return (current_node, current);
-      }
+  /// Returns the minimum segment size of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let min_segment_size = arena.minimum_segment_size();
+  /// ```
+  #[inline]
+  pub fn minimum_segment_size(&self) -> u32 {
+    self.header().min_segment_size.load(Ordering::Acquire)
+  }
 
-      current = next;
-      current_node = next_node;
-      current_node_size = next_node_size;
-      next_offset = next_next_offset;
-    }
+  /// Returns the minimum size of a freed region which is always reclaimed into the freelist,
+  /// whatever its offset is, the smaller regions may be discarded.
+  ///
+  /// A segment needs a segment node aligned to `8` bytes, plus [`minimum_segment_size`](Self::minimum_segment_size)
+  /// bytes of data, so the size covers the segment node, the worst padding of the node, and the minimum segment size.
+  /// The data offset is always aligned to the segment node, so a region at an `8`-aligned offset
+  /// needs no padding.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new().with_minimum_segment_size(64));
+  /// assert_eq!(arena.min_reclaimable_segment_size(), 8 + 7 + 64);
+  /// ```
+  #[inline]
+  pub fn min_reclaimable_segment_size(&self) -> u32 {
+    (SEGMENT_NODE_SIZE + SEGMENT_NODE_ALIGNMENT - 1) as u32 + self.minimum_segment_size()
   }
 
-  #[allow(clippy::type_complexity)]
-  fn find_prev_and_next(
-    &self,
-    val: u32,
-    check: impl Fn(u32, u32) -> bool,
-  ) -> Option<((u64, &AtomicU64), (u64, &AtomicU64))> {
-    let header = self.header();
-    let mut current: &AtomicU64 = &header.sentinel;
-    let mut current_node = current.load(Ordering::Acquire);
-    let (mut current_node_size, mut next_offset) = decode_segment_node(current_node);
-    let backoff = Backoff::new();
+  /// Sets the minimum segment size of the ARENA.
+  ///
+  /// The size is clamped to at least the size of a segment node plus `4` bytes.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// arena.set_minimum_segment_size(100);
+  /// ```
+  #[inline]
+  pub fn set_minimum_segment_size(&self, size: u32) {
+    self
+      .header()
+      .min_segment_size
+      .store(size.max(MIN_SEGMENT_SIZE_FLOOR), Ordering::Release);
+  }
+
+  /// Returns the data offset of the ARENA. The offset is the end of the reserved bytes of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let data_offset = arena.data_offset();
+  /// ```
+  #[inline]
+  pub const fn data_offset(&self) -> usize {
+    self.data_offset as usize
+  }
+
+  /// Returns the raw bytes of the header of the ARENA, which is useful for the tools inspecting or repairing
+  /// the ARENA files.
+  ///
+  /// The header only lives in the buffer of the ARENAs which are unified, memory mapped, or partitioned by
+  /// [`partition`](Self::partition). For the ARENAs backed by a `Vec` which are not unified,
+  /// the header is kept out of the buffer, so an empty slice is returned.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// assert!(arena.header_bytes().is_empty());
+  ///
+  /// let arena = Arena::new(ArenaOptions::new().with_unify(true));
+  /// assert!(!arena.header_bytes().is_empty());
+  /// ```
+  pub fn header_bytes(&self) -> &[u8] {
+    let ptr = match self.partition_header {
+      Some(header) => header.as_ptr().cast::<u8>(),
+      // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+      None => match unsafe { &self.inner.as_ref().header_ptr } {
+        Either::Left(header_ptr) => *header_ptr,
+        Either::Right(_) => return &[],
+      },
+    };
+
+    // Safety: the header is in bounds of the memory, and lives as long as the memory.
+    unsafe { slice::from_raw_parts(ptr, mem::size_of::<Header>()) }
+  }
+
+  /// Returns the data section of the ARENA as a byte slice, header is not included.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let data = arena.data();
+  /// ```
+  #[inline]
+  pub fn data(&self) -> &[u8] {
+    unsafe {
+      let ptr = self.ptr.add(self.data_offset as usize);
+      let allocated = self.header().allocated.load(Ordering::Acquire);
+      slice::from_raw_parts(ptr, (allocated - self.data_offset) as usize)
+    }
+  }
+
+  /// Returns the data section of the ARENA as a mutable byte slice, header is not included,
+  /// which is useful for the bulk post-processing of the allocated memory.
+  ///
+  /// # Safety
+  /// - There must be no other live handles or slices (e.g. [`BytesRefMut`], [`RefMut`] or the slices returned by
+  ///   [`data`](Self::data) and [`get_bytes`](Self::get_bytes)) over the data section while the returned slice is alive,
+  ///   see [`try_exclusive`](Self::try_exclusive) for checking the handles.
+  /// - The ARENA must not be allocated from or deallocated to while the returned slice is alive.
+  ///
+  /// # Panic
+  /// - If the ARENA is read-only, then this method will panic.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(4).unwrap();
+  /// b.detach();
+  ///
+  /// unsafe { arena.data_mut() }.fill(1);
+  /// assert_eq!(arena.data(), &[1, 1, 1, 1]);
+  /// ```
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  pub unsafe fn data_mut(&self) -> &mut [u8] {
+    assert!(!self.ro, "ARENA is read-only");
+
+    let ptr = self.ptr.add(self.data_offset as usize);
+    let allocated = self.header().allocated.load(Ordering::Acquire);
+    slice::from_raw_parts_mut(ptr, (allocated - self.data_offset) as usize)
+  }
+
+  /// Returns the range of the pointers of the data section of the ARENA, header is not included.
+  ///
+  /// Unlike [`data`](Self::data), the range covers the whole capacity, not only the allocated bytes,
+  /// so it can be used to assert that the pointers derived from the ARENA stay in bounds.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let bytes = arena.alloc_bytes(8).unwrap();
+  /// assert!(arena.data_ptr_range().contains(&bytes.as_ptr()));
+  /// ```
+  #[inline]
+  pub fn data_ptr_range(&self) -> core::ops::Range<*const u8> {
+    // Safety: `data_offset..cap` is in bounds of the ARENA.
+    unsafe {
+      let start = self.ptr.add(self.data_offset as usize) as *const u8;
+      start..self.ptr.add(self.cap as usize) as *const u8
+    }
+  }
+
+  /// Returns the range of the pointers of the whole memory of the ARENA, including the reserved bytes
+  /// (and the header if the ARENA is unified).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let range = arena.full_ptr_range();
+  /// assert_eq!(range.end as usize - range.start as usize, arena.capacity());
+  /// ```
+  #[inline]
+  pub fn full_ptr_range(&self) -> core::ops::Range<*const u8> {
+    // Safety: `0..cap` is in bounds of the ARENA.
+    unsafe { self.ptr as *const u8..self.ptr.add(self.cap as usize) as *const u8 }
+  }
+
+  /// Returns an iterator over the [`data`](Self::data) section of the ARENA in chunks of `chunk` bytes,
+  /// the last chunk may be shorter.
+  ///
+  /// # Panics
+  /// - If `chunk` is `0`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(10).unwrap();
+  /// b.detach();
+  ///
+  /// let chunks = arena.data_chunks(4).map(|c| c.len()).collect::<Vec<_>>();
+  /// assert_eq!(chunks, [4, 4, 2]);
+  /// ```
+  #[inline]
+  pub fn data_chunks(&self, chunk: usize) -> slice::Chunks<'_, u8> {
+    self.data().chunks(chunk)
+  }
+
+  /// Returns the allocated memory of the ARENA as a byte slice, i.e. the first [`allocated`](Self::allocated) bytes
+  /// of [`memory`](Self::memory), including the header of a unify ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let memory = arena.allocated_memory();
+  /// assert_eq!(memory.len(), arena.allocated());
+  /// ```
+  #[inline]
+  pub fn allocated_memory(&self) -> &[u8] {
+    let allocated = self.header().allocated.load(Ordering::Acquire);
+    unsafe { slice::from_raw_parts(self.ptr, allocated as usize) }
+  }
+
+  /// Returns the whole buffer of the ARENA as a byte slice, which is [`capacity`](Self::capacity) bytes long,
+  /// i.e. the header (of a unify ARENA), the data section, and the unused tail after [`allocated`](Self::allocated),
+  /// unlike [`data`](Self::data) and [`allocated_memory`](Self::allocated_memory), which stop at `allocated`.
+  ///
+  /// The unused tail of a new ARENA is zeroed. The freed bytes, and the bytes reclaimed by [`clear`](Self::clear),
+  /// are not zeroed until they are allocated again, see [`ArenaOptions::with_zero_on_free`],
+  /// so they may still look like live data, e.g. for a whole-buffer checksum.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let memory = arena.memory();
+  /// assert_eq!(memory.len(), arena.capacity());
+  /// assert!(memory[arena.allocated()..].iter().all(|b| *b == 0));
+  /// ```
+  #[inline]
+  pub const fn memory(&self) -> &[u8] {
+    unsafe { slice::from_raw_parts(self.ptr, self.cap as usize) }
+  }
+
+  /// Copies the live data of this ARENA into `dst`, and makes the header of `dst` match this ARENA.
+  ///
+  /// The offsets are preserved, so offsets of the allocations from this ARENA are still valid in `dst`.
+  ///
+  /// `dst` must be empty (nothing allocated yet), have the same [`data_offset`](Self::data_offset) and the same
+  /// [`Freelist`] as this ARENA, and have enough capacity to hold the [`allocated`](Self::allocated) bytes of this ARENA.
+  ///
+  /// **Note:** this ARENA should not be modified concurrently, otherwise `dst` may get a torn copy.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(10).unwrap();
+  /// bytes.put_slice(b"hello").unwrap();
+  /// bytes.detach();
+  /// let offset = bytes.offset();
+  ///
+  /// let dst = Arena::new(ArenaOptions::new().with_capacity(2048));
+  /// arena.clone_into(&dst).unwrap();
+  /// assert_eq!(unsafe { dst.get_bytes(offset, 5) }, b"hello");
+  /// ```
+  pub fn clone_into(&self, dst: &Arena) -> Result<(), Error> {
+    if dst.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    if dst.data_offset != self.data_offset
+      || dst.freelist != self.freelist
+      || dst.allocated() != dst.data_offset as usize
+    {
+      return Err(Error::IncompatibleArena);
+    }
+
+    let header = self.header();
+    let allocated = header.allocated.load(Ordering::Acquire);
+    if allocated > dst.cap {
+      return Err(Error::InsufficientSpace {
+        requested: allocated - self.data_offset,
+        available: dst.remaining() as u32,
+      });
+    }
+
+    // Safety: both `self.data_offset..allocated` are in bounds of the two ARENAs,
+    // and the two ARENAs do not share the same memory.
+    unsafe {
+      ptr::copy_nonoverlapping(
+        self.ptr.add(self.data_offset as usize),
+        dst.ptr.add(dst.data_offset as usize),
+        (allocated - self.data_offset) as usize,
+      );
+    }
+
+    let dst_header = dst.header();
+    dst_header
+      .sentinel
+      .store(header.sentinel.load(Ordering::Acquire), Ordering::Release);
+    dst_header.min_segment_size.store(
+      header.min_segment_size.load(Ordering::Acquire),
+      Ordering::Release,
+    );
+    dst_header
+      .discarded
+      .store(header.discarded.load(Ordering::Acquire), Ordering::Release);
+    dst_header.discarded_padding.store(
+      header.discarded_padding.load(Ordering::Acquire),
+      Ordering::Release,
+    );
+    dst_header.discarded_small_segments.store(
+      header.discarded_small_segments.load(Ordering::Acquire),
+      Ordering::Release,
+    );
+    dst_header.discarded_manual.store(
+      header.discarded_manual.load(Ordering::Acquire),
+      Ordering::Release,
+    );
+    dst_header.last_modified.store(
+      header.last_modified.load(Ordering::Acquire),
+      Ordering::Release,
+    );
+    dst_header
+      .root
+      .store(header.root.load(Ordering::Acquire), Ordering::Release);
+    dst_header
+      .generation
+      .store(header.generation.load(Ordering::Acquire), Ordering::Release);
+    dst_header.allocated.store(allocated, Ordering::Release);
+    Ok(())
+  }
+
+  /// Copies `len` bytes at `src_offset` of this ARENA into a new allocation in `dst`, and returns the offset
+  /// of the new allocation in `dst`, e.g. to migrate a region from a hot in-memory ARENA to a cold file backed one.
+  ///
+  /// The new allocation is detached, and the source region is not freed, it is the caller's responsibility
+  /// to free them. Returns `0` if `len` is `0`.
+  ///
+  /// **Note:** the source region should not be modified concurrently, otherwise `dst` may get a torn copy.
+  ///
+  /// # Errors
+  /// - [`Error::ReadOnly`] if `dst` is read-only.
+  /// - [`Error::OutOfBounds`] if `src_offset..src_offset + len` is not in the live region of this ARENA.
+  /// - Any error of [`alloc_bytes`](Self::alloc_bytes) on `dst`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(5).unwrap();
+  /// bytes.put_slice(b"hello").unwrap();
+  /// bytes.detach();
+  ///
+  /// let dst = Arena::new(ArenaOptions::new());
+  /// let offset = arena.migrate_region(bytes.offset() as u32, 5, &dst).unwrap();
+  /// assert_eq!(unsafe { dst.get_bytes(offset as usize, 5) }, b"hello");
+  /// ```
+  pub fn migrate_region(&self, src_offset: u32, len: u32, dst: &Arena) -> Result<u32, Error> {
+    if dst.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    if len == 0 {
+      return Ok(0);
+    }
+
+    let src = self.try_bytes(src_offset as usize, len as usize)?;
+    let mut bytes = dst.alloc_bytes(len)?;
+    // Safety: the new allocation has `len` bytes, and the source region is live in this ARENA,
+    // if the two ARENAs share the same memory, the new allocation does not overlap the live source region.
+    unsafe {
+      ptr::copy(src.as_ptr(), bytes.as_mut_ptr(), len as usize);
+    }
+    bytes.detach();
+    Ok(bytes.offset() as u32)
+  }
+
+  /// Converts a non-unify ARENA into a new unify ARENA, whose header is written into the buffer,
+  /// so that the memory can be persisted coherently, e.g. written to a file and memory-mapped later.
+  ///
+  /// The data is shifted to make room for the header, the offsets of the allocations from this ARENA
+  /// must be adjusted by the difference of the [`data_offset`](Self::data_offset)s of the two ARENAs.
+  /// The shift is a multiple of the maximum alignment, so the alignments of the allocations are preserved.
+  ///
+  /// Returns [`Error::IncompatibleArena`] if this ARENA is not a non-unify `Vec` ARENA (e.g. a memory
+  /// mapped ARENA or a partition).
+  ///
+  /// **Note:** this ARENA should not be modified concurrently, otherwise the new ARENA may get a torn copy.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(5).unwrap();
+  /// bytes.put_slice(b"hello").unwrap();
+  /// bytes.detach();
+  ///
+  /// let unified = arena.to_unified().unwrap();
+  /// assert!(!unified.header_bytes().is_empty());
+  /// let offset = bytes.offset() - arena.data_offset() + unified.data_offset();
+  /// assert_eq!(unsafe { unified.get_bytes(offset, 5) }, b"hello");
+  /// ```
+  pub fn to_unified(&self) -> Result<Arena, Error> {
+    if self.partition_header.is_some() {
+      return Err(Error::IncompatibleArena);
+    }
+
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    let memory = unsafe { self.inner.as_ref() }.to_unified()?;
+    Ok(Self {
+      ptr: memory.ptr,
+      data_offset: memory.data_offset as u32,
+      max_retries: self.max_retries,
+      unify: true,
+      magic_version: self.magic_version,
+      version: self.version,
+      ro: self.ro,
+      zero_on_free: self.zero_on_free,
+      seqlock: self.seqlock,
+      compaction_threshold: self.compaction_threshold,
+      cap: memory.cap,
+      freelist: self.freelist,
+      on_oom: self.on_oom.clone(),
+      on_high_water: self.on_high_water.clone(),
+      on_event: self.on_event.clone(),
+      #[cfg(feature = "trace")]
+      tracer: self
+        .tracer
+        .as_ref()
+        .map(|tracer| std::sync::Arc::new(Tracer::new(tracer.capacity()))),
+      #[cfg(feature = "std")]
+      parker: self
+        .parker
+        .as_ref()
+        .map(|_| std::sync::Arc::new(Parker::default())),
+      serial: self
+        .serial
+        .as_ref()
+        .map(|_| std::sync::Arc::new(SerialLock::default())),
+      // the copy is not file backed.
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      auto_flush: None,
+      partition_header: None,
+      id: next_arena_id(),
+      inner: unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(memory)) as _) },
+    })
+  }
+
+  /// Creates a fully independent copy of this ARENA, backed by a new `Vec` of the same capacity.
+  ///
+  /// Unlike [`Clone`], which shares the memory, the copy owns its memory, so the modifications
+  /// of the copy are not visible to this ARENA, and vice versa. Unlike [`clone_into`](Self::clone_into),
+  /// the destination is allocated by this method. The offsets are preserved, so offsets of the allocations
+  /// from this ARENA are still valid in the copy.
+  ///
+  /// Returns [`Error::IncompatibleArena`] if this ARENA is a partition.
+  ///
+  /// **Note:** this ARENA should not be modified concurrently, otherwise the copy may be torn.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(5).unwrap();
+  /// bytes.put_slice(b"hello").unwrap();
+  /// bytes.detach();
+  ///
+  /// let copy = arena.deep_clone().unwrap();
+  /// assert_eq!(unsafe { copy.get_bytes(bytes.offset(), 5) }, b"hello");
+  /// ```
+  pub fn deep_clone(&self) -> Result<Arena, Error> {
+    if self.partition_header.is_some() {
+      return Err(Error::IncompatibleArena);
+    }
+
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    let memory = unsafe { self.inner.as_ref() }.deep_clone();
+    Ok(Self {
+      ptr: memory.ptr,
+      data_offset: self.data_offset,
+      max_retries: self.max_retries,
+      unify: self.unify,
+      magic_version: self.magic_version,
+      version: self.version,
+      ro: self.ro,
+      zero_on_free: self.zero_on_free,
+      seqlock: self.seqlock,
+      compaction_threshold: self.compaction_threshold,
+      cap: memory.cap,
+      freelist: self.freelist,
+      on_oom: self.on_oom.clone(),
+      on_high_water: self.on_high_water.clone(),
+      on_event: self.on_event.clone(),
+      #[cfg(feature = "trace")]
+      tracer: self
+        .tracer
+        .as_ref()
+        .map(|tracer| std::sync::Arc::new(Tracer::new(tracer.capacity()))),
+      #[cfg(feature = "std")]
+      parker: self
+        .parker
+        .as_ref()
+        .map(|_| std::sync::Arc::new(Parker::default())),
+      serial: self
+        .serial
+        .as_ref()
+        .map(|_| std::sync::Arc::new(SerialLock::default())),
+      // the copy is not file backed.
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      auto_flush: None,
+      partition_header: None,
+      id: next_arena_id(),
+      inner: unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(memory)) as _) },
+    })
+  }
+
+  /// Copies the live regions of this ARENA into `dst` densely, and returns the map from the old offsets
+  /// to the new offsets, so that the callers can update their indices.
+  ///
+  /// The live regions are the allocated memory of this ARENA minus the segments in the freelist,
+  /// `remap` is called with the old and the new offsets of each live region once it is copied.
+  /// The offsets are only moved by multiples of `8`, so the alignments up to `8` are preserved.
+  ///
+  /// This ARENA is only read, so the readers of this ARENA are not blocked, which is the foundation
+  /// of the online compaction.
+  ///
+  /// `dst` must be empty (nothing allocated yet) and writable.
+  ///
+  /// **Note:** this ARENA should not be modified concurrently, otherwise `dst` may get a torn copy.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut a = arena.alloc_bytes(64).unwrap();
+  /// a.detach();
+  /// let b = arena.alloc_bytes(64).unwrap();
+  /// let mut c = arena.alloc_bytes(5).unwrap();
+  /// c.put_slice(b"hello").unwrap();
+  /// c.detach();
+  /// drop(b);
+  ///
+  /// let dst = Arena::new(ArenaOptions::new());
+  /// let map = arena.compact_into(&dst, |_, _| {}).unwrap();
+  /// let offset = map.get(c.offset() as u32).unwrap();
+  /// assert!(offset < c.offset() as u32);
+  /// assert_eq!(unsafe { dst.get_bytes(offset as usize, 5) }, b"hello");
+  /// ```
+  pub fn compact_into(
+    &self,
+    dst: &Arena,
+    mut remap: impl FnMut(u32, u32),
+  ) -> Result<OffsetMap, Error> {
+    const ALIGNMENT: u32 = mem::align_of::<u64>() as u32;
+
+    if dst.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    if ptr::eq(dst.ptr, self.ptr) || dst.allocated() != dst.data_offset as usize {
+      return Err(Error::IncompatibleArena);
+    }
+
+    let allocated = self.header().allocated.load(Ordering::Acquire);
+    let mut free = self.free_segments();
+    free.sort_unstable();
+
+    // collect the live regions between the free segments.
+    let mut live = std::vec::Vec::with_capacity(free.len() + 1);
+    let mut cursor = self.data_offset;
+    for (offset, size) in free {
+      let start = offset.min(allocated);
+      if start > cursor {
+        live.push((cursor, start - cursor));
+      }
+      cursor = cursor.max(offset.saturating_add(size));
+    }
+    if cursor < allocated {
+      live.push((cursor, allocated - cursor));
+    }
+
+    let mut map = OffsetMap::default();
+    let mut end = dst.data_offset as u64;
+    for &(old, size) in &live {
+      // keep the alignment of the region.
+      let new = end + (old as u64 + ALIGNMENT as u64 - end % ALIGNMENT as u64) % ALIGNMENT as u64;
+      map.push(MovedRegion::new(old, new as u32, size));
+      end = new + size as u64;
+    }
+
+    if end > dst.cap as u64 {
+      return Err(Error::InsufficientSpace {
+        requested: (end - dst.data_offset as u64).min(u32::MAX as u64) as u32,
+        available: dst.remaining() as u32,
+      });
+    }
+
+    for region in map.regions() {
+      // Safety: both regions are in bounds of the two ARENAs, and the two ARENAs do not share the same memory.
+      unsafe {
+        ptr::copy_nonoverlapping(
+          self.ptr.add(region.old_offset() as usize),
+          dst.ptr.add(region.new_offset() as usize),
+          region.size() as usize,
+        );
+      }
+      remap(region.old_offset(), region.new_offset());
+    }
+
+    let end = end as u32;
+    dst.header().allocated.store(end, Ordering::Release);
+    dst.check_high_water(end);
+    Ok(map)
+  }
+
+  /// Carves the memory of this ARENA into several independent ARENAs, e.g. one for keys and one for values.
+  ///
+  /// The `i`-th returned ARENA can allocate `sizes[i]` bytes, and has its own header, so its own
+  /// `allocated` watermark and freelist. The offsets of each returned ARENA are relative to its own partition.
+  /// The returned ARENAs share the memory with this ARENA (the memory is reference counted),
+  /// and this ARENA cannot allocate anymore once the partitions are laid out.
+  ///
+  /// This ARENA must be a fresh (nothing allocated yet), writable and unify ARENA, otherwise
+  /// [`Error::IncompatibleArena`] is returned.
+  ///
+  /// **Note:** the partition layout is not recorded in the header, so it cannot be recovered after
+  /// the ARENA is reopened. That is why the file backed ARENAs (see [`is_persistent`](Self::is_persistent))
+  /// cannot be partitioned, [`Error::IncompatibleArena`] is returned for them as well.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new().with_capacity(4096).with_unify(true));
+  /// let parts = arena.partition(&[1024, 1024]).unwrap();
+  /// assert_eq!(parts.len(), 2);
+  ///
+  /// let mut key = parts[0].alloc_bytes(3).unwrap();
+  /// key.put_slice(b"foo").unwrap();
+  /// let mut value = parts[1].alloc_bytes(3).unwrap();
+  /// value.put_slice(b"bar").unwrap();
+  /// assert_eq!(key.offset(), value.offset());
+  /// ```
+  pub fn partition(&self, sizes: &[u32]) -> Result<std::vec::Vec<Arena>, Error> {
+    const HEADER_SIZE: u32 = mem::size_of::<Header>() as u32;
+
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    // the partitions would be lost after reopening a file, as the layout is not persisted.
+    if !self.unify || self.is_persistent() {
+      return Err(Error::IncompatibleArena);
+    }
+
+    let header = self.header();
+    let allocated = header.allocated.load(Ordering::Acquire);
+    if allocated != self.data_offset {
+      return Err(Error::IncompatibleArena);
+    }
+
+    // lay out the partitions, each partition starts with a well-aligned header.
+    let mut regions = std::vec::Vec::with_capacity(sizes.len());
+    let mut end = allocated as u64;
+    for &size in sizes {
+      // Safety: `end` is in bounds of the ARENA, otherwise we have returned.
+      let pad = unsafe { self.ptr.add(end as usize) }.align_offset(mem::align_of::<Header>());
+      let start = end + pad as u64;
+      end = start + HEADER_SIZE as u64 + size as u64;
+      if end > self.cap as u64 {
+        return Err(Error::InsufficientSpace {
+          requested: (end - allocated as u64).min(u32::MAX as u64) as u32,
+          available: self.remaining() as u32,
+        });
+      }
+      regions.push((start as u32, end as u32 - start as u32));
+    }
+
+    // reserve the partitions in this ARENA.
+    let end = end as u32;
+    if header
+      .allocated
+      .compare_exchange(allocated, end, Ordering::SeqCst, Ordering::Acquire)
+      .is_err()
+    {
+      return Err(Error::IncompatibleArena);
+    }
+    self.check_high_water(end);
+
+    let min_segment_size = header.min_segment_size.load(Ordering::Acquire);
+    Ok(
+      regions
+        .into_iter()
+        .map(|(start, cap)| {
+          let mut arena = self.clone();
+          // Safety: the region is in bounds of the ARENA, and `ptr + start` is well-aligned for the `Header`.
+          unsafe {
+            arena.ptr = self.ptr.add(start as usize);
+            let header_ptr = arena.ptr.cast::<Header>();
+            header_ptr.write(Header::new(HEADER_SIZE, min_segment_size));
+            arena.partition_header = Some(NonNull::new_unchecked(header_ptr));
+          }
+          arena.cap = cap;
+          arena.data_offset = HEADER_SIZE;
+          // a partition has its own offset space.
+          arena.id = next_arena_id();
+          arena
+        })
+        .collect(),
+    )
+  }
+
+  /// Returns `true` if the arena is read-only.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let read_only = arena.read_only();
+  /// ```
+  #[inline]
+  pub const fn read_only(&self) -> bool {
+    self.ro
+  }
+
+  /// Returns a read-only handle to the ARENA.
+  ///
+  /// The returned handle shares the underlying memory with `self` (the memory is reference counted),
+  /// so this is a cheap view. All the allocations and mutable accesses through the returned handle
+  /// will fail or panic as on a read-only memory map.
+  ///
+  /// **Note:** this does not prevent the writes through `self` or the other outstanding mutable clones.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, Error};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let frozen = arena.freeze();
+  /// assert!(frozen.read_only());
+  /// assert!(matches!(frozen.alloc_bytes(8), Err(Error::ReadOnly)));
+  /// ```
+  #[inline]
+  pub fn freeze(&self) -> Self {
+    let mut arena = self.clone();
+    arena.ro = true;
+    arena
+  }
+
+  /// Sets remove on drop, only works on mmap with a file backend.
+  ///
+  /// Default is `false`.
+  ///
+  /// > **WARNING:** Once set to `true`, the backed file will be removed when the ARENA is dropped, even though the file is opened in
+  /// > read-only mode.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// # let arena = Arena::new(ArenaOptions::new());
+  /// arena.remove_on_drop(true);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn remove_on_drop(&self, remove_on_drop: bool) {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref().set_remove_on_drop(remove_on_drop) }
+  }
+
+  /// Sets the option to make the file shrink to the used size when dropped.
+  ///
+  /// This option, when true, will indicate that the file should be shrunk to
+  /// the size of the data written to it when the file is dropped.
+  ///
+  /// Default is `false`.
+  ///
+  /// > **WARNING:** Once set to `true`, the backed file will be shrunk when the ARENA is dropped, even though the file is opened in
+  /// > read-only mode.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// # use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// # let arena = Arena::new(ArenaOptions::new());
+  /// arena.shrink_on_drop(true);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn shrink_on_drop(&self, shrink_on_drop: bool) {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref().set_shrink_on_drop(shrink_on_drop) }
+  }
+
+  /// Returns the path of the mmap file, only returns `Some` when the ARENA is backed by a mmap file.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// # let arena = Arena::new(ArenaOptions::new());
+  /// let path = arena.path();
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub const fn path(&self) -> Option<&std::path::PathBuf> {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref().path() }
+  }
+
+  /// Returns the kind of the backend memory of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, BackendKind};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// assert_eq!(arena.backend_kind(), BackendKind::Vec);
+  /// ```
+  #[inline]
+  pub fn backend_kind(&self) -> BackendKind {
+    // Safety: the inner is always non-null, we only deallocate it when the memory refs is 1.
+    unsafe { self.inner.as_ref().backend_kind() }
+  }
+
+  /// Returns `true` if the ARENA is backed by a memory map of a file, which means the data
+  /// can be flushed to and recovered from the disk.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// assert!(!arena.is_persistent());
+  /// ```
+  #[inline]
+  pub fn is_persistent(&self) -> bool {
+    matches!(
+      self.backend_kind(),
+      BackendKind::Mmap | BackendKind::MmapMut
+    )
+  }
+
+  #[inline]
+  fn header(&self) -> &Header {
+    // Safety:
+    // The inner is always non-null, we only deallocate it when the memory refs is 1,
+    // and the header of a partition lives in the same memory.
+    unsafe {
+      match self.partition_header {
+        Some(header) => header.as_ref(),
+        None => (*self.inner.as_ptr()).header(),
+      }
+    }
+  }
+}
+
+unsafe impl Send for Arena {}
+unsafe impl Sync for Arena {}
+
+impl Arena {
+  /// Creates a new ARENA with the given options.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// ```
+  #[inline]
+  pub fn new(opts: ArenaOptions) -> Self {
+    let memory = Memory::new_vec(&opts);
+    Self::new_in(memory, opts, false)
+  }
+
+  /// Creates a new ARENA with the given options, the memory of the ARENA is allocated
+  /// and deallocated by the given allocator.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// #![feature(allocator_api)]
+  ///
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  /// use std::alloc::Global;
+  ///
+  /// let arena = Arena::new_in_allocator(ArenaOptions::new(), Global);
+  /// ```
+  #[cfg(feature = "allocator-api")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "allocator-api")))]
+  #[inline]
+  pub fn new_in_allocator<A>(opts: ArenaOptions, alloc: A) -> Self
+  where
+    A: std::alloc::Allocator + Send + Sync + 'static,
+  {
+    let memory = Memory::new_vec_in(&opts, alloc);
+    Self::new_in(memory, opts, false)
+  }
+
+  /// Creates a new ARENA backed by a mmap with the given options.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn map_mut<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: ArenaOptions,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+  ) -> std::io::Result<Self> {
+    Memory::map_mut(path, open_options, mmap_options, &opts)
+      .map(|memory| Self::new_in(memory, opts, false))
+  }
+
+  /// Opens a read only ARENA backed by a mmap with the given capacity.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// # {
+  ///   # let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  ///   # let mmap_options = MmapOptions::new();
+  ///   # let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// # }
+  ///
+  /// let open_options = OpenOptions::default().read(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let arena = Arena::map(&path, open_options, mmap_options, 0).unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn map<P: AsRef<std::path::Path>>(
+    path: P,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+    magic_version: u16,
+  ) -> std::io::Result<Self> {
+    Memory::map(path, open_options, mmap_options, magic_version)
+      .map(|memory| Self::new_in(memory, ArenaOptions::new().with_maximum_retries(0), true))
+  }
+
+  /// Opens an existing ARENA backed by a copy-on-write memory map of the file.
+  ///
+  /// The file only needs to be opened for reading, the ARENA is writable, but the allocations and
+  /// the writes go to the private pages, the file on disk is never modified,
+  /// so [`flush`](Self::flush) and [`flush_async`](Self::flush_async) are no-ops.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, BackendKind, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// # {
+  ///   # let open_options = OpenOptions::default().create_new(Some(1024)).read(true).write(true);
+  ///   # let mmap_options = MmapOptions::new();
+  ///   # let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// # }
+  ///
+  /// let open_options = OpenOptions::default().read(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let arena = Arena::map_cow(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// assert_eq!(arena.backend_kind(), BackendKind::MmapCow);
+  /// assert!(!arena.read_only());
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn map_cow<P: AsRef<std::path::Path>>(
+    path: P,
+    opts: ArenaOptions,
+    open_options: OpenOptions,
+    mmap_options: MmapOptions,
+  ) -> std::io::Result<Self> {
+    Memory::map_cow(path, open_options, mmap_options, &opts)
+      .map(|memory| Self::new_in(memory, opts, false))
+  }
+
+  /// Takes a point-in-time snapshot of a live file backed ARENA, without stopping the writers.
+  ///
+  /// The file is mapped again privately (`MAP_PRIVATE`), and every page of the private mapping is
+  /// copied eagerly, so the snapshot is not affected by the subsequent writes to the file. On Linux,
+  /// the untouched pages of a private mapping still reflect the changes to the file, which is why
+  /// the pages are copied up front, so the cost is proportional to the size of the file.
+  ///
+  /// The copy is retried until no allocation or deallocation happens during it, see
+  /// [`read_consistent`](Self::read_consistent), so the header and the freelist of the snapshot are consistent
+  /// if the writers set [`ArenaOptions::with_seqlock`]. However, the writes to the contents of the live allocations
+  /// are not tracked, a value written concurrently may be torn in the snapshot.
+  ///
+  /// The snapshot is a writable [`BackendKind::MmapCow`] ARENA, its writes never reach the file.
+  ///
+  /// Returns an error of [`std::io::ErrorKind::Unsupported`] if the ARENA is not backed by a file,
+  /// or is a [`partition`](Self::partition), and an error of [`std::io::ErrorKind::WouldBlock`]
+  /// if no consistent copy is made in [`ArenaOptions::with_maximum_retries`] retries.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  /// let open_options = OpenOptions::default().create_new(Some(1024)).read(true).write(true);
+  /// let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, MmapOptions::new()).unwrap();
+  /// let mut b = arena.alloc_bytes(5).unwrap();
+  /// b.put_slice(b"hello").unwrap();
+  ///
+  /// let snapshot = arena.snapshot_cow().unwrap();
+  /// arena.zero_range(b.offset(), 5).unwrap();
+  /// assert_eq!(unsafe { snapshot.get_bytes(b.offset(), 5) }, b"hello");
+  /// # drop(b);
+  /// # drop(arena);
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", target_os = "linux"))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", target_os = "linux"))))]
+  pub fn snapshot_cow(&self) -> std::io::Result<Self> {
+    // the pages are at least 4 KiB, so touching every 4 KiB touches every page.
+    const MIN_PAGE_SIZE: usize = 4096;
+
+    let path = match self.path() {
+      Some(path) if self.partition_header.is_none() => path,
+      _ => {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::Unsupported,
+          "only the ARENA backed by a file can be snapshotted",
+        ))
+      }
+    };
+
+    let opts = ArenaOptions::new()
+      .with_magic_version(self.magic_version)
+      .with_freelist(self.freelist)
+      .with_zero_on_free(self.zero_on_free);
+    self
+      .read_consistent(|| {
+        let memory = Memory::map_cow(
+          path,
+          OpenOptions::new().read(true),
+          MmapOptions::new(),
+          &opts,
+        )?;
+        // Safety: the private mapping is writable, and only owned by the new memory,
+        // writing a byte back copies the page.
+        unsafe {
+          for offset in (0..memory.cap as usize).step_by(MIN_PAGE_SIZE) {
+            let p = memory.ptr.add(offset);
+            ptr::write_volatile(p, ptr::read_volatile(p));
+          }
+        }
+        Ok(memory)
+      })
+      .unwrap_or_else(|| {
+        Err(std::io::Error::new(
+          std::io::ErrorKind::WouldBlock,
+          "the ARENA is modified during the snapshot",
+        ))
+      })
+      .map(|memory| Self::new_in(memory, opts, false))
+  }
+
+  /// Reads the persisted header of a file backed ARENA, without memory mapping the file.
+  ///
+  /// Only the leading bytes which contain the header are read, and no lock is acquired,
+  /// so this is cheap for enumerating many ARENA files.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// # {
+  ///   # let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  ///   # let mmap_options = MmapOptions::new();
+  ///   # let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// # }
+  ///
+  /// let header = Arena::peek_header(&path).unwrap();
+  /// assert_eq!(header.capacity(), 100);
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn peek_header<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<HeaderInfo> {
+    use std::io::Read;
+
+    // the memory map is page aligned, so the header is always right after the sanity bytes.
+    const HEADER_OFFSET: usize = mem::align_of::<Header>();
+    const DATA_OFFSET: usize = HEADER_OFFSET + mem::size_of::<Header>();
+
+    let mut file = std::fs::File::open(path)?;
+    let cap = file.metadata()?.len();
+    if cap < DATA_OFFSET as u64 {
+      return Err(invalid_data(TooSmall::new(cap as usize, DATA_OFFSET)));
+    }
+
+    let mut buf = [0u8; DATA_OFFSET];
+    file.read_exact(&mut buf)?;
+
+    let magic_version = u16::from_le_bytes(
+      buf[MAGIC_VERISON_OFFSET..MAGIC_VERISON_OFFSET + MAGIC_VERISON_SIZE]
+        .try_into()
+        .unwrap(),
+    );
+    Memory::sanity_check(None, magic_version, &buf)?;
+
+    // Safety: the buffer contains a whole header, and the header is plain old data.
+    let header = unsafe { ptr::read_unaligned(buf.as_ptr().add(HEADER_OFFSET).cast::<Header>()) };
+    Ok(HeaderInfo {
+      allocated: header.allocated.load(Ordering::Relaxed),
+      discarded: header.discarded.load(Ordering::Relaxed),
+      min_segment_size: header.min_segment_size.load(Ordering::Relaxed),
+      cap,
+    })
+  }
+
+  /// Creates a new ARENA backed by an anonymous mmap with the given capacity.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, MmapOptions};
+  ///
+  /// let mmap_options = MmapOptions::new().len(100);
+  /// let arena = Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap();
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn map_anon(opts: ArenaOptions, mmap_options: MmapOptions) -> std::io::Result<Self> {
+    Memory::map_anon(mmap_options, &opts).map(|memory| Self::new_in(memory, opts, false))
+  }
+
+  /// Creates a new ARENA with the given capacity.
+  ///
+  /// Memory maps are not available on `wasm`, so this method falls back to a `Vec` backed ARENA
+  /// whose capacity is the length configured by [`MmapOptions::len`].
+  ///
+  /// **Note:** the ARENA is memory-only, nothing is persisted.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, MmapOptions};
+  ///
+  /// let mmap_options = MmapOptions::new().len(100);
+  /// let arena = Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap();
+  /// ```
+  #[cfg(all(feature = "memmap", target_family = "wasm"))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", target_family = "wasm"))))]
+  #[inline]
+  pub fn map_anon(opts: ArenaOptions, mmap_options: MmapOptions) -> std::io::Result<Self> {
+    let len = mmap_options.get_len().ok_or_else(|| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "memory map must have a non-zero length",
+      )
+    })?;
+
+    if opts.unify() && (len as usize) < OVERHEAD {
+      return Err(invalid_data(TooSmall::new(len as usize, OVERHEAD)));
+    }
+
+    let memory = Memory::new_vec(&opts.with_capacity(len));
+    Ok(Self::new_in(memory, opts, false))
+  }
+
+  /// Grows the ARENA backed by an anonymous memory map to `new_cap` bytes, the allocated memory
+  /// is kept, and the new space can be used by the following allocations.
+  ///
+  /// On Linux, the mapping is enlarged by `mremap` without copying, on other platforms,
+  /// the memory is copied into a new mapping. The memory may be moved, so all the pointers
+  /// get from the ARENA are invalid after growing, but the offsets are still valid.
+  ///
+  /// Returns an error if:
+  /// - the ARENA is not backed by an anonymous memory map ([`std::io::ErrorKind::Unsupported`]).
+  /// - the ARENA is read-only ([`std::io::ErrorKind::PermissionDenied`]).
+  /// - the ARENA is shared, i.e. [`refs`](Self::refs) is not `1` ([`std::io::ErrorKind::WouldBlock`]).
+  /// - `new_cap` is less than the current capacity ([`std::io::ErrorKind::InvalidInput`]).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, MmapOptions};
+  ///
+  /// let mut arena = Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(1024)).unwrap();
+  /// arena.grow_to(4096).unwrap();
+  /// assert_eq!(arena.capacity(), 4096);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn grow_to(&mut self, new_cap: u32) -> std::io::Result<()> {
+    if self.ro {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        "ARENA is read-only",
+      ));
+    }
+
+    if self.refs() != 1 {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::WouldBlock,
+        "ARENA is shared by other references",
+      ));
+    }
+
+    if new_cap < self.cap {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "new capacity must not be less than the current capacity",
+      ));
+    }
+
+    if new_cap == self.cap {
+      return Ok(());
+    }
+
+    // Safety: the ARENA is the only reference to the memory, and the `&mut self` guarantees
+    // there is no live handle borrowed from it.
+    unsafe {
+      let memory = self.inner.as_mut();
+      memory.grow(new_cap as usize)?;
+      self.ptr = memory.as_mut_ptr();
+      self.cap = memory.cap();
+    }
+    Ok(())
+  }
+
+  /// Ensures at least `needed` bytes can be bumped from the main memory right now, see [`remaining`](Self::remaining),
+  /// growing the ARENA by [`grow_to`](Self::grow_to) if the ARENA is backed by an anonymous memory map.
+  ///
+  /// The memory may be moved by growing, so all the pointers get from the ARENA are invalid after growing,
+  /// but the offsets are still valid.
+  ///
+  /// # Errors
+  /// - [`Error::ReadOnly`] if the ARENA is read-only.
+  /// - [`Error::Locked`] if the ARENA needs to grow, but is shared, i.e. [`refs`](Self::refs) is not `1`.
+  /// - [`Error::InsufficientSpace`] if the ARENA needs to grow, but can not, e.g. the ARENA is not
+  ///   backed by an anonymous memory map.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let mut arena = Arena::new(ArenaOptions::new());
+  /// arena.ensure_capacity(100).unwrap();
+  /// assert!(arena.ensure_capacity(4096).is_err());
+  /// ```
+  pub fn ensure_capacity(&mut self, needed: u32) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    let remaining = self.remaining() as u32;
+    if remaining >= needed {
+      return Ok(());
+    }
+
+    let insufficient = Error::InsufficientSpace {
+      requested: needed,
+      available: remaining,
+    };
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    if let MemoryBackend::AnonymousMmap { .. } = unsafe { &self.inner.as_ref().backend } {
+      let Some(new_cap) = self.cap.checked_add(needed - remaining) else {
+        return Err(insufficient);
+      };
+
+      return self.grow_to(new_cap).map_err(|e| match e.kind() {
+        std::io::ErrorKind::WouldBlock => Error::Locked,
+        _ => insufficient,
+      });
+    }
+
+    Err(insufficient)
+  }
+
+  /// Locks the underlying file for exclusive access, only works on mmap with a file backend.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// arena.lock_exclusive().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn lock_exclusive(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().lock_exclusive() }
+  }
+
+  /// Locks the underlying file for shared access, only works on mmap with a file backend.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// arena.lock_shared().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn lock_shared(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().lock_shared() }
+  }
+
+  /// Try to lock the underlying file for exclusive access, only works on mmap with a file backend.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// arena.try_lock_exclusive().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn try_lock_exclusive(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().try_lock_exclusive() }
+  }
+
+  /// Try to lock the underlying file for shared access, only works on mmap with a file backend.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// arena.try_lock_shared().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn try_lock_shared(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().try_lock_shared() }
+  }
+
+  /// Unlocks the underlying file, only works on mmap with a file backend.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// arena.lock_exclusive().unwrap();
+  ///
+  /// // do some thing
+  /// arena.unlock().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub fn unlock(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().unlock() }
+  }
+
+  /// Flushes the memory-mapped file to disk.
+  ///
+  /// This is a no-op for the ARENA opened by [`map_cow`](Self::map_cow), which has no backing to write.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  ///
+  /// let open_options = OpenOptions::default().create_new(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  /// arena.flush().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  pub fn flush(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().flush() }
+  }
+
+  /// Faults in all the pages of the ARENA, to avoid the latency spikes caused by lazily faulting
+  /// the pages of a newly mapped file on the first access.
+  ///
+  /// On unix, the kernel is advised that the whole mapping will be needed (`MADV_WILLNEED`),
+  /// then one byte of each page is read. Nothing is written, so this can be used on a read-only ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  /// let open_options = OpenOptions::default().create(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  ///
+  /// arena.prefault().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn prefault(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().prefault() }
+  }
+
+  /// Advises the OS that the pages in `offset..offset + len` are not needed, so that they can be reclaimed,
+  /// which reduces the RSS after processing a large transient region.
+  ///
+  /// On unix, the pages fully covered by the range are advised by `MADV_DONTNEED`, the partial pages at
+  /// the both ends are kept. The ARENA keeps working, the pages are faulted in again on the next access:
+  /// - the pages of a file backed ARENA ([`map_mut`](Self::map_mut), [`map`](Self::map)) are read from the file again.
+  /// - the pages of an anonymous ARENA ([`map_anon`](Self::map_anon)) are zeroed.
+  /// - the private changes of a copy-on-write ARENA ([`map_cow`](Self::map_cow)) are dropped, the pages
+  ///   are read from the file again.
+  ///
+  /// This is a no-op for the `Vec` backed ARENAs and on the other platforms.
+  ///
+  /// Returns [`std::io::ErrorKind::InvalidInput`] if the range is out of the bounds of the ARENA.
+  ///
+  /// # Safety
+  /// - There must be no references to the memory in the range, as the content may be changed.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, MmapOptions};
+  ///
+  /// let arena = Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(1 << 16)).unwrap();
+  /// let mut b = arena.alloc_bytes(1 << 14).unwrap();
+  /// b.detach();
+  ///
+  /// unsafe { arena.advise_dontneed(b.offset(), b.capacity()).unwrap() };
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub unsafe fn advise_dontneed(&self, offset: usize, len: usize) -> std::io::Result<()> {
+    if offset
+      .checked_add(len)
+      .map_or(true, |end| end > self.cap as usize)
+    {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "the range is out of the bounds of the ARENA",
+      ));
+    }
+
+    // the offsets of a partition are relative to the partition, not to the whole memory.
+    let memory = self.inner.as_ref();
+    let base = (self.ptr as *const u8).offset_from(memory.as_ptr()) as usize;
+    memory.advise_dontneed(base + offset, len)
+  }
+
+  /// Flushes the memory-mapped file to disk asynchronously.
+  ///
+  /// This is a no-op for the ARENA opened by [`map_cow`](Self::map_cow), which has no backing to write.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, OpenOptions, MmapOptions};
+  ///
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  /// let open_options = OpenOptions::default().create(Some(100)).read(true).write(true);
+  /// let mmap_options = MmapOptions::new();
+  /// let mut arena = Arena::map_mut(&path, ArenaOptions::new(), open_options, mmap_options).unwrap();
+  ///
+  /// arena.flush_async().unwrap();
+  ///
+  /// # std::fs::remove_file(path);
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  pub fn flush_async(&self) -> std::io::Result<()> {
+    unsafe { self.inner.as_ref().flush_async() }
+  }
+
+  /// Allocates an owned slice of memory in the ARENA.
+  ///
+  /// The cost of this method is an extra atomic operation, compared to [`alloc_bytes`](Self::alloc_bytes).
+  #[inline]
+  pub fn alloc_bytes_owned(&self, size: u32) -> Result<BytesMut, Error> {
+    self.alloc_bytes(size).map(|mut b| b.to_owned())
+  }
+
+  /// Allocates a slice of memory in the ARENA.
+  ///
+  /// The [`BytesRefMut`] is zeroed out, unless [`ArenaOptions::with_zero_on_free`] is set to `false`,
+  /// in which case any reused memory may contain stale bytes, i.e. the segments reused from the freelist,
+  /// the main memory given back by a deallocation at its end, and the whole main memory after [`clear`](Self::clear).
+  ///
+  /// If you want a [`BytesMut`], see [`alloc_bytes_owned`](Self::alloc_bytes_owned).
+  #[inline]
+  pub fn alloc_bytes(&self, size: u32) -> Result<BytesRefMut, Error> {
+    self
+      .retry_on_oom(|| self.alloc_bytes_in(size, self.zero_on_free))
+      .map(|a| match a {
+        None => BytesRefMut::null(self),
+        Some(allocated) => unsafe { BytesRefMut::new(self, allocated) },
+      })
+  }
+
+  /// Allocates as many bytes as possible, up to `max`, i.e. `min(max, largest)` bytes, where `largest`
+  /// is the larger one of the [`remaining`](Self::remaining) main memory and the largest segment in the freelist.
+  ///
+  /// The [`capacity`](BytesRefMut::capacity) of the returned [`BytesRefMut`] tells how many bytes are allocated,
+  /// e.g. a streaming writer adapts the size of the chunks to the available space.
+  ///
+  /// Returns [`Error::InsufficientSpace`] only if no bytes are available at all, or the available space
+  /// keeps being taken by the concurrent allocations for [`ArenaOptions::maximum_retries`] times.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let remaining = arena.remaining();
+  ///
+  /// let b = arena.alloc_bytes_up_to(u32::MAX).unwrap();
+  /// assert_eq!(b.capacity(), remaining);
+  /// ```
+  pub fn alloc_bytes_up_to(&self, max: u32) -> Result<BytesRefMut<'_>, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    if max == 0 {
+      return self.alloc_bytes(0);
+    }
+
+    let mut res = Err(Error::InsufficientSpace {
+      requested: max,
+      available: 0,
+    });
+    for _ in 0..=self.max_retries {
+      let largest = self
+        .free_segments()
+        .into_iter()
+        .map(|(_, size)| size - SEGMENT_NODE_SIZE as u32)
+        .max()
+        .unwrap_or(0)
+        .max(self.remaining() as u32);
+      if largest == 0 {
+        return Err(Error::InsufficientSpace {
+          requested: max,
+          available: 0,
+        });
+      }
+
+      // the space may be taken by the concurrent allocations in the meantime, then try again.
+      res = self.alloc_bytes(max.min(largest));
+      if !matches!(res, Err(Error::InsufficientSpace { .. })) {
+        return res;
+      }
+    }
+    res
+  }
+
+  /// Allocates a slice of memory in the ARENA, and reports how the allocation was satisfied.
+  ///
+  /// This is the same as [`alloc_bytes`](Self::alloc_bytes), but also returns the [`AllocStats`]
+  /// of this specific allocation, which is useful for debugging the latency of the allocations.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let (_b, stats) = arena.alloc_bytes_with_stats(8).unwrap();
+  /// assert!(stats.fast_path());
+  /// assert!(!stats.from_free_list());
+  /// ```
+  pub fn alloc_bytes_with_stats(&self, size: u32) -> Result<(BytesRefMut<'_>, AllocStats), Error> {
+    let mut stats = AllocStats::default();
+    self
+      .retry_on_oom(|| {
+        self.alloc_bytes_with_stats_in(
+          size,
+          self.zero_on_free,
+          &mut stats,
+          &mut self.slow_path_backoff(None),
+        )
+      })
+      .map(|a| {
+        let bytes = match a {
+          None => BytesRefMut::null(self),
+          Some(allocated) => unsafe { BytesRefMut::new(self, allocated) },
+        };
+        (bytes, stats)
+      })
+  }
+
+  /// Allocates a slice of memory in the ARENA, like [`alloc_bytes`](Self::alloc_bytes), but the slow path
+  /// gives up after `max_spins` backoff iterations in total.
+  ///
+  /// Returns `Ok(None)` if the allocation gives up due to the contention on the freelist,
+  /// which is distinct from [`Error::InsufficientSpace`] returned when there is genuinely not enough space,
+  /// so the caller can fall back to another ARENA, e.g. another shard of a [`ShardedArena`].
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let b = arena.alloc_bytes_timeout(8, 16).unwrap().unwrap();
+  /// assert_eq!(b.capacity(), 8);
+  /// ```
+  pub fn alloc_bytes_timeout(
+    &self,
+    size: u32,
+    max_spins: u32,
+  ) -> Result<Option<BytesRefMut<'_>>, Error> {
+    if size == 0 {
+      return self.alloc_bytes(0).map(Some);
+    }
+
+    let mut backoff = self.slow_path_backoff(Some(max_spins));
+    let mut stats = AllocStats::default();
+    self
+      .retry_on_oom(|| {
+        match self.alloc_bytes_with_stats_in(size, self.zero_on_free, &mut stats, &mut backoff) {
+          // the size is not zero, so `None` only means the slow path gives up.
+          Err(Error::InsufficientSpace { .. }) if backoff.exhausted() => Ok(None),
+          res => res,
+        }
+      })
+      .map(|a| a.map(|allocated| unsafe { BytesRefMut::new(self, allocated) }))
+  }
+
+  /// Reserves `size` bytes in the ARENA now, the returned [`ReservedSegment`] can be redeemed
+  /// by [`ReservedSegment::take`] later, which never fails, even if the ARENA is full
+  /// or the freelist is contended by then.
+  ///
+  /// If the reservation is dropped without being taken, the bytes are freed normally.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let reserved = arena.reserve_segment(8).unwrap();
+  ///
+  /// // some other allocations
+  ///
+  /// let mut b = reserved.take();
+  /// b.put_u64_le(42).unwrap();
+  /// ```
+  #[inline]
+  pub fn reserve_segment(&self, size: u32) -> Result<ReservedSegment<'_>, Error> {
+    self.alloc_bytes(size).map(ReservedSegment::new)
+  }
+
+  /// Allocates `size` bytes at the given `offset`, which is useful for rebuilding a known layout,
+  /// e.g. replaying a log of the allocations by their offsets.
+  ///
+  /// `offset..offset + size` must be in `[data_offset, capacity)`, and must be free: either beyond
+  /// the [`allocated`](Self::allocated) watermark, which is advanced (the skipped bytes are given to the freelist),
+  /// or in a single segment of the freelist, which is carved (the remaining bytes are given back to the freelist).
+  ///
+  /// Returns [`Error::OutOfBounds`] if the region is out of the data section, or [`Error::OverlappingRegions`]
+  /// if the region overlaps with the live memory, or spans across the freelist segments.
+  ///
+  /// # Safety
+  /// - This method is not lock-free, there must be no concurrent allocations or deallocations
+  ///   on the ARENA, like [`rebuild_free_list`](Self::rebuild_free_list).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let offset = arena.data_offset() as u32 + 64;
+  ///
+  /// let mut b = unsafe { arena.alloc_at(offset, 16).unwrap() };
+  /// b.put_u64_le(42).unwrap();
+  /// assert_eq!(b.offset(), offset as usize);
+  /// assert_eq!(arena.allocated(), offset as usize + 16);
+  /// ```
+  pub unsafe fn alloc_at(&self, offset: u32, size: u32) -> Result<BytesRefMut<'_>, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    let end = offset as u64 + size as u64;
+    if size == 0 || offset < self.data_offset || end > self.cap as u64 {
+      return Err(Error::OutOfBounds {
+        offset,
+        size,
+        capacity: self.cap,
+      });
+    }
+    let end = end as u32;
+
+    let _serial = self.serialize();
+    let header = self.header();
+    let write = self.begin_write();
+    let allocated = header.allocated.load(Ordering::Acquire);
+    if offset >= allocated {
+      header.allocated.store(end, Ordering::Release);
+      self.check_high_water(end);
+      if offset > allocated {
+        self.free_region(allocated, offset - allocated);
+      }
+    } else {
+      // find the segment which covers the region below the watermark.
+      let mut prev: &AtomicU64 = &header.sentinel;
+      let carved = loop {
+        let prev_val = prev.load(Ordering::Acquire);
+        let (prev_size, next_offset) = decode_segment_node(prev_val);
+        if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+          break None;
+        }
+        let Some(node) = self.get_segment_node(next_offset) else {
+          break None;
+        };
+        let (node_size, next_next_offset) = decode_segment_node(node.load(Ordering::Acquire));
+        let segment_end = next_offset as u64 + SEGMENT_NODE_SIZE as u64 + node_size as u64;
+        if next_offset <= offset
+          && end.min(allocated) as u64 <= segment_end
+          && (end <= allocated || segment_end == allocated as u64)
+        {
+          prev.store(
+            encode_segment_node(prev_size, next_next_offset),
+            Ordering::Release,
+          );
+          break Some((next_offset, segment_end as u32));
+        }
+        prev = node;
+      };
+
+      let Some((segment_start, segment_end)) = carved else {
+        drop(write);
+        return Err(Error::OverlappingRegions);
+      };
+
+      if end > allocated {
+        header.allocated.store(end, Ordering::Release);
+        self.check_high_water(end);
+      }
+      if segment_start < offset {
+        self.free_region(segment_start, offset - segment_start);
+      }
+      if end < segment_end {
+        self.free_region(end, segment_end - end);
+      }
+    }
+    drop(write);
+
+    let meta = Meta::new(self.ptr as _, offset, size);
+    if self.zero_on_free {
+      meta.clear(self);
+    }
+
+    #[cfg(feature = "trace")]
+    self.trace(TraceOp::Alloc, offset, size);
+    self.emit(ArenaEvent::Alloc { offset, size });
+    Ok(BytesRefMut::new(self, meta))
+  }
+
+  /// Allocates `size` bytes exactly once for the slot at `header_slot`, which is the lazy initialization
+  /// of a shared structure, even if multiple threads or processes race on the same ARENA.
+  ///
+  /// The slot is a `u32` in the ARENA designated by the caller, which stores the offset of the allocation,
+  /// `0` means uninitialized. If the slot is uninitialized, the memory is allocated and published
+  /// by a compare-and-swap on the slot, the racers which lose the compare-and-swap deallocate their memory.
+  ///
+  /// Returns the offset stored in the slot, and whether this caller performed the allocation. The allocation
+  /// is never deallocated by the ARENA, a zero `size` is rounded up to `1`, so that the offset is never `0`.
+  ///
+  /// Returns [`Error::OutOfBounds`] if the slot is out of the data section, or [`Error::InvalidAlignment`]
+  /// if the slot is not aligned to `4`.
+  ///
+  /// # Safety
+  /// - The slot must be allocated memory, and must be only accessed atomically.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut slot = arena.alloc_aligned_bytes::<u32>(0).unwrap();
+  /// slot.put_u32_le(0).unwrap();
+  /// slot.detach();
+  ///
+  /// let (offset, initialized) = unsafe { arena.alloc_once(slot.offset(), 64).unwrap() };
+  /// assert!(initialized);
+  /// assert_eq!(unsafe { arena.alloc_once(slot.offset(), 64).unwrap() }, (offset, false));
+  /// ```
+  pub unsafe fn alloc_once(&self, header_slot: usize, size: u32) -> Result<(u32, bool), Error> {
+    if header_slot < self.data_offset as usize
+      || header_slot + mem::size_of::<u32>() > self.cap as usize
+    {
+      return Err(Error::OutOfBounds {
+        offset: header_slot as u32,
+        size: mem::size_of::<u32>() as u32,
+        capacity: self.cap,
+      });
+    }
+
+    let ptr = self.ptr.add(header_slot);
+    if ptr as usize % mem::align_of::<AtomicU32>() != 0 {
+      return Err(Error::InvalidAlignment);
+    }
+
+    let slot = &*ptr.cast::<AtomicU32>();
+    let offset = slot.load(Ordering::Acquire);
+    if offset != 0 {
+      return Ok((offset, false));
+    }
+
+    let mut allocated = self.alloc_bytes(size.max(1))?;
+    let new_offset = allocated.offset() as u32;
+    match slot.compare_exchange(0, new_offset, Ordering::AcqRel, Ordering::Acquire) {
+      Ok(_) => {
+        allocated.detach();
+        Ok((new_offset, true))
+      }
+      // the allocation is deallocated when dropped.
+      Err(offset) => Ok((offset, false)),
+    }
+  }
+
+  /// Allocates a buddy block of `1 << order` bytes, whose offset is aligned to `1 << order`,
+  /// so that the buddy of the block is at `offset ^ (1 << order)`, which is the building block
+  /// of a buddy allocator layered on the ARENA.
+  ///
+  /// The block is carved from the main memory, or from a segment of the freelist, the padding
+  /// before and after the block is given back to the freelist. The offset is aligned relative
+  /// to the start of the ARENA, the address is only aligned up to [`ArenaOptions::with_maximum_alignment`].
+  ///
+  /// The returned buffer is deallocated as a normal buffer when dropped, detach it and free it by
+  /// [`dealloc_buddy`](Self::dealloc_buddy) to merge it with its buddy.
+  ///
+  /// Returns [`Error::InvalidAlignment`] if `order` is less than `3` (a block must be able to hold
+  /// a segment node when it is freed) or greater than `31`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let block = arena.alloc_buddy(6).unwrap();
+  /// assert_eq!(block.capacity(), 64);
+  /// assert_eq!(block.offset() % 64, 0);
+  /// ```
+  pub fn alloc_buddy(&self, order: u8) -> Result<BytesRefMut<'_>, Error> {
+    if !(BUDDY_MIN_ORDER..=BUDDY_MAX_ORDER).contains(&order) {
+      return Err(Error::InvalidAlignment);
+    }
+
+    self
+      .retry_on_oom(|| self.alloc_buddy_in(1 << order))
+      .map(|allocated| match allocated {
+        None => BytesRefMut::null(self),
+        Some(allocated) => unsafe { BytesRefMut::new(self, allocated) },
+      })
+  }
+
+  fn alloc_buddy_in(&self, size: u32) -> Result<Option<Meta>, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    let header = self.header();
+    let mut allocated = header.allocated.load(Ordering::Acquire);
+    loop {
+      let start = (allocated as u64 + size as u64 - 1) & !(size as u64 - 1);
+      let end = start + size as u64;
+      if end > self.cap as u64 {
+        break;
+      }
+
+      match header.allocated.compare_exchange_weak(
+        allocated,
+        end as u32,
+        Ordering::SeqCst,
+        Ordering::Acquire,
+      ) {
+        Ok(_) => {
+          self.check_high_water(end as u32);
+          let start = start as u32;
+          if start > allocated {
+            self.free_region(allocated, start - allocated);
+          }
+
+          let allocated = Meta::new(self.ptr as _, start, size);
+          if self.zero_on_free {
+            unsafe { allocated.clear(self) };
+          }
+          return Ok(Some(allocated));
+        }
+        Err(x) => allocated = x,
+      }
+    }
+
+    // allocate through slow path, the segment is padded for the alignment, the data of a segment
+    // is aligned to the segment node, so `size - 8` bytes of padding are enough.
+    let padded = match (size as u64 * 2).checked_sub(SEGMENT_NODE_SIZE as u64) {
+      Some(padded) if padded <= u32::MAX as u64 => padded as u32,
+      _ => {
+        return Err(Error::InsufficientSpace {
+          requested: size,
+          available: self.remaining() as u32,
+        })
+      }
+    };
+    let (segment, _) =
+      self.alloc_slow_path_with(padded, size, false, &mut self.slow_path_backoff(None))?;
+    let (data_start, data_end) = (segment.ptr_offset, segment.ptr_offset + padded);
+    let start = (data_start + size - 1) & !(size - 1);
+    if start > data_start {
+      self.free_region(data_start, start - data_start);
+    }
+    if start + size < data_end {
+      self.free_region(start + size, data_end - start - size);
+    }
+
+    let allocated = Meta::new(self.ptr as _, start, size);
+    if self.zero_on_free {
+      unsafe { allocated.clear(self) };
+    }
+    Ok(Some(allocated))
+  }
+
+  /// Gives the free region to the freelist, or discards it if it is too small to be a segment.
+  #[inline]
+  fn free_region(&self, offset: u32, size: u32) {
+    match self.freelist {
+      Freelist::None => self.discard(offset, size, Discard::SmallSegment),
+      Freelist::Optimistic => {
+        self.optimistic_dealloc(offset, size);
+      }
+      Freelist::Pessimistic => {
+        self.pessimistic_dealloc(offset, size);
+      }
+    }
+  }
+
+  /// Allocates a slice of memory in the ARENA without zeroing it.
+  ///
+  /// Unlike [`alloc_bytes`](Self::alloc_bytes), the memory is never zeroed, the bytes are whatever was there,
+  /// e.g. the stale bytes of a buffer which was freed before. The returned [`BytesRefMut`] is empty,
+  /// write the bytes through the `put_*` methods, or write the whole buffer through
+  /// [`as_mut_ptr`](BytesRefMut::as_mut_ptr) and then call [`assume_init`](BytesRefMut::assume_init).
+  ///
+  /// # Safety
+  /// - The caller must not read the bytes of the buffer before initializing them.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = unsafe { arena.alloc_bytes_uninit(5).unwrap() };
+  /// unsafe {
+  ///   core::ptr::copy_nonoverlapping(b"hello".as_ptr(), bytes.as_mut_ptr(), 5);
+  ///   bytes.assume_init();
+  /// }
+  /// assert_eq!(bytes.as_ref(), b"hello");
+  /// ```
+  #[inline]
+  pub unsafe fn alloc_bytes_uninit(&self, size: u32) -> Result<BytesRefMut<'_>, Error> {
+    self
+      .retry_on_oom(|| self.alloc_bytes_in(size, false))
+      .map(|a| match a {
+        None => BytesRefMut::null(self),
+        Some(allocated) => BytesRefMut::new(self, allocated),
+      })
+  }
+
+  /// Allocates `size` bytes in the ARENA, and reads exactly `size` bytes from `reader` directly into them,
+  /// without an intermediate buffer.
+  ///
+  /// # Errors
+  /// - [`std::io::ErrorKind::UnexpectedEof`] if the reader ends before `size` bytes are read,
+  ///   the allocated memory is given back to the ARENA.
+  /// - [`std::io::ErrorKind::Other`] wrapping the [`Error`] if the allocation fails.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut reader = std::io::Cursor::new(b"hello world".to_vec());
+  /// let bytes = arena.copy_from_reader(&mut reader, 5).unwrap();
+  /// assert_eq!(bytes.as_ref(), b"hello");
+  /// ```
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  pub fn copy_from_reader<R: std::io::Read>(
+    &self,
+    reader: &mut R,
+    size: u32,
+  ) -> std::io::Result<BytesRefMut<'_>> {
+    // Safety: every byte of the buffer is written by `read_exact` before the buffer is marked as initialized.
+    unsafe {
+      let mut bytes = self
+        .alloc_bytes_uninit(size)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+      if size == 0 {
+        return Ok(bytes);
+      }
+
+      // the memory of the ARENA is always initialized, so it is fine to read into it as a slice.
+      let buf = slice::from_raw_parts_mut(bytes.as_mut_ptr(), size as usize);
+      reader.read_exact(buf)?;
+      bytes.assume_init();
+      Ok(bytes)
+    }
+  }
+
+  /// Allocates an owned byte slice that can hold a well-aligned `T` and extra `size` bytes.
+  ///
+  /// The layout of the allocated memory is:
+  ///
+  /// ```text
+  /// | T | [u8; size] |
+  /// ```
+  ///
+  /// # Example
+  ///
+  /// ```ignore
+  /// let mut bytes = arena.alloc_aligned_bytes_owned::<T>(extra).unwrap();
+  /// bytes.put(val).unwrap(); // write `T` to the byte slice.
+  /// ```
+  #[inline]
+  pub fn alloc_aligned_bytes_owned<T>(&self, size: u32) -> Result<BytesMut, Error> {
+    self
+      .alloc_aligned_bytes::<T>(size)
+      .map(|mut b| b.to_owned())
+  }
+
+  /// Allocates a byte slice that can hold a well-aligned `T` and extra `size` bytes.
+  ///
+  /// The layout of the allocated memory is:
+  ///
+  /// ```text
+  /// | T | [u8; size] |
+  /// ```
+  ///
+  /// # Example
+  ///
+  /// ```ignore
+  /// let mut bytes = arena.alloc_aligned_bytes::<T>(extra).unwrap();
+  /// bytes.put(val).unwrap(); // write `T` to the byte slice.
+  /// ```
+  #[inline]
+  pub fn alloc_aligned_bytes<T>(&self, size: u32) -> Result<BytesRefMut, Error> {
+    self
+      .retry_on_oom(|| self.alloc_aligned_bytes_in::<T>(size))
+      .map(|a| match a {
+        None => BytesRefMut::null(self),
+        Some(allocated) => unsafe { BytesRefMut::new(self, allocated) },
+      })
+  }
+
+  /// Allocates a `T` in the ARENA.
+  ///
+  /// # Safety
+  ///
+  /// - If `T` needs to be dropped and callers invoke [`RefMut::detach`],
+  ///   then the caller must ensure that the `T` is dropped before the ARENA is dropped.
+  ///   Otherwise, it will lead to memory leaks.
+  ///
+  /// - If this is file backed ARENA, then `T` must be recoverable from bytes.
+  ///   1. Types require allocation are not recoverable.
+  ///   2. Pointers are not recoverable, like `*const T`, `*mut T`, `NonNull` and any structs contains pointers,
+  ///      although those types are on stack, but they cannot be recovered, when reopens the file.
+  ///
+  /// # Examples
+  ///
+  /// ## Memory leak
+  ///
+  /// The following example demonstrates the memory leak when the `T` is a heap allocated type and detached.
+  ///
+  /// ```ignore
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  ///
+  /// {
+  ///   let mut data = arena.alloc::<Vec<u8>>().unwrap();
+  ///   data.detach();
+  ///   data.write(vec![1, 2, 3]);
+  /// }
+  ///
+  /// drop(arena); // memory leak, the `Vec<u8>` is not dropped.
+  /// ```
+  ///
+  /// ## Undefined behavior
+  ///
+  /// The following example demonstrates the undefined behavior when the `T` is not recoverable.
+  ///
+  /// ```ignore
+  ///
+  /// struct TypeOnHeap {
+  ///   data: Vec<u8>,
+  /// }
+  ///
+  /// let arena = Arena::map_mut("path/to/file", ArenaOptions::new(), OpenOptions::create_new(Some(1000)).read(true).write(true), MmapOptions::default()).unwrap();
+  ///
+  /// let mut data = arena.alloc::<TypeOnHeap>().unwrap();
+  /// data.detach();
+  /// data.write(TypeOnHeap { data: vec![1, 2, 3] });
+  /// let offset = data.offset();
+  /// drop(arena);
+  ///
+  /// // reopen the file
+  /// let arena = Arena::map("path/to/file", OpenOptions::read(true), MmapOptions::default()).unwrap();
+  ///
+  /// let foo = &*arena.get_aligned_pointer::<TypeOnHeap>(offset as usize);
+  /// let b = foo.data[1]; // undefined behavior, the `data`'s pointer stored in the file is not valid anymore.
+  /// ```
+  ///
+  /// ## Good practice
+  ///
+  /// Some examples about how to use this method correctly.
+  ///
+  /// ### Heap allocated type with carefull memory management
+  ///
+  /// ```ignore
+  /// let arena = Arena::new(ArenaOptions::new());
+  ///
+  /// // Do not invoke detach, so when the data is dropped, the drop logic will be handled by the ARENA.
+  /// // automatically.
+  /// {
+  ///   let mut data = arena.alloc::<Vec<u8>>().unwrap();
+  ///   data.write(vec![1, 2, 3]);
+  /// }
+  ///
+  ///
+  /// let mut detached_data = arena.alloc::<Vec<u8>>().unwrap();
+  /// detached_data.detach();
+  /// detached_data.write(vec![4, 5, 6]);
+  ///
+  /// // some other logic
+  ///
+  /// core::ptr::drop_in_place(detached_data.as_mut()); // drop the `Vec` manually.
+  ///
+  /// drop(arena); // it is safe, the `Vec` is already dropped.
+  /// ```
+  ///
+  /// ### Recoverable type with file backed ARENA
+  ///
+  /// ```ignore
+  ///
+  /// struct Recoverable {
+  ///   field1: u64,
+  ///   field2: AtomicU32,
+  /// }
+  ///
+  /// let arena = Arena::map_mut("path/to/file", ArenaOptions::new(), OpenOptions::create_new(Some(1000)).read(true).write(true), MmapOptions::default()).unwrap();
+  ///
+  /// let mut data = arena.alloc::<Recoverable>().unwrap();
+  /// data.write(Recoverable { field1: 10, field2: AtomicU32::new(20) });
+  /// data.detach();
+  /// let offset = data.offset();
+  /// drop(arena);
+  ///
+  /// // reopen the file
+  /// let arena = Arena::map("path/to/file", OpenOptions::read(true), MmapOptions::default()).unwrap();
+  ///
+  /// let foo = &*arena.get_aligned_pointer::<Recoverable>(offset as usize);
+  ///
+  /// assert_eq!(foo.field1, 10);
+  /// assert_eq!(foo.field2.load(Ordering::Acquire), 20);
+  /// ```
+  #[inline]
+  pub unsafe fn alloc<T>(&self) -> Result<RefMut<'_, T>, Error> {
+    if mem::size_of::<T>() == 0 {
+      return Ok(RefMut::new_zst(self));
+    }
+
+    let allocated = self
+      .retry_on_oom(|| self.alloc_in::<T>())?
+      .expect("allocated size is not zero, but get None");
+    let ptr = unsafe { self.get_aligned_pointer_mut::<T>(allocated.memory_offset as usize) };
+    if mem::needs_drop::<T>() {
+      unsafe {
+        let ptr: *mut MaybeUninit<T> = ptr.as_ptr().cast();
+        ptr::write(ptr, MaybeUninit::uninit());
+
+        Ok(RefMut::new(ptr::read(ptr), allocated, self))
+      }
+    } else {
+      Ok(RefMut::new_inline(ptr, allocated, self))
+    }
+  }
+
+  /// Allocates a `T` in the ARENA, like [`alloc`](Self::alloc), but the address of the value is aligned
+  /// to `align` instead of the natural alignment of `T`, e.g. to place a value on its own cache line.
+  ///
+  /// Returns [`Error::InvalidAlignment`] if `align` is not a power of two, or less than `align_of::<T>()`.
+  ///
+  /// # Safety
+  ///
+  /// - See [`alloc`](Self::alloc) for safety.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  ///
+  /// unsafe {
+  ///   let mut data = arena.alloc_aligned::<u64>(64).unwrap();
+  ///   data.write(10);
+  ///   assert_eq!(data.as_mut_ptr().as_ptr() as usize % 64, 0);
+  /// }
+  /// ```
+  pub unsafe fn alloc_aligned<T>(&self, align: usize) -> Result<RefMut<'_, T>, Error> {
+    if !align.is_power_of_two() || align < mem::align_of::<T>() || align > u32::MAX as usize {
+      return Err(Error::InvalidAlignment);
+    }
+
+    if mem::size_of::<T>() == 0 {
+      return Ok(RefMut::new_zst(self));
+    }
+
+    let allocated = self
+      .retry_on_oom(|| self.alloc_aligned_in::<T>(align as u32))?
+      .expect("allocated size is not zero, but get None");
+    let ptr = self
+      .get_pointer_mut(allocated.ptr_offset as usize)
+      .cast::<T>();
+    let ptr = NonNull::new_unchecked(ptr);
+    if mem::needs_drop::<T>() {
+      unsafe {
+        let ptr: *mut MaybeUninit<T> = ptr.as_ptr().cast();
+        ptr::write(ptr, MaybeUninit::uninit());
+
+        Ok(RefMut::new(ptr::read(ptr), allocated, self))
+      }
+    } else {
+      Ok(RefMut::new_inline(ptr, allocated, self))
+    }
+  }
+
+  /// Allocates the uninitialized storage for `n` well-aligned `T`s in the ARENA,
+  /// which can be filled incrementally, then marked as initialized by [`SliceRefMut::assume_init`].
+  ///
+  /// No memory is allocated if `T` is a zero sized type or `n` is `0`.
+  ///
+  /// # Safety
+  /// - See [`alloc`](Self::alloc) for safety.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut slice = unsafe { arena.alloc_uninit_slice::<u64>(4).unwrap() };
+  /// for (i, slot) in slice.as_uninit_slice_mut().iter_mut().enumerate() {
+  ///   slot.write(i as u64);
+  /// }
+  ///
+  /// unsafe { slice.assume_init() };
+  /// assert_eq!(slice.as_slice(), &[0, 1, 2, 3]);
+  /// ```
+  pub unsafe fn alloc_uninit_slice<T>(&self, n: usize) -> Result<SliceRefMut<'_, T>, Error> {
+    if mem::size_of::<T>() == 0 || n == 0 {
+      return Ok(SliceRefMut::new_dangling(self, n));
+    }
+
+    let size = match mem::size_of::<T>()
+      .checked_mul(n)
+      .filter(|size| *size <= u32::MAX as usize)
+    {
+      Some(size) => size as u32,
+      None => {
+        return Err(Error::InsufficientSpace {
+          requested: u32::MAX,
+          available: self.remaining() as u32,
+        })
+      }
+    };
+
+    // the layout of `| T | [u8; size - size_of::<T>()] |` is the same as `[T; n]`.
+    let allocated = self
+      .retry_on_oom(|| self.alloc_aligned_bytes_in::<T>(size - mem::size_of::<T>() as u32))?
+      .expect("allocated size is not zero, but get None");
+    let ptr = self.get_aligned_pointer_mut::<T>(allocated.ptr_offset as usize);
+    Ok(SliceRefMut::new(ptr, n, allocated, self))
+  }
+
+  /// Allocates a `T` in the ARENA. Like [`alloc`](Self::alloc), but returns an `Owned`.
+  ///
+  /// The cost is one more atomic operation than [`alloc`](Self::alloc).
+  ///
+  /// # Safety
+  ///
+  /// - See [`alloc`](Self::alloc) for safety.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  ///
+  /// unsafe {
+  ///   let mut data = arena.alloc_owned::<u64>().unwrap();
+  ///   data.write(10);
+  ///
+  ///   assert_eq!(*data.as_ref(), 10);
+  /// }
+  /// ```
+  #[inline]
+  pub unsafe fn alloc_owned<T>(&self) -> Result<Owned<T>, Error> {
+    self.alloc::<T>().map(|mut r| r.to_owned())
+  }
+
+  /// Clear the ARENA.
+  ///
+  /// See [`try_exclusive`](Self::try_exclusive) for a runtime-checked alternative.
+  ///
+  /// # Safety
+  /// - The current pointers get from the ARENA cannot be used anymore after calling this method.
+  /// - This method is not thread-safe.
+  ///
+  /// # Examples
+  ///
+  /// Undefine behavior:
+  ///
+  /// ```ignore
+  /// let mut data = arena.alloc::<Vec<u8>>().unwrap();
+  ///
+  /// arena.clear();
+  ///
+  /// data.write(vec![1, 2, 3]); // undefined behavior
+  /// ```
+  ///
+  /// Good practice:
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  ///
+  /// unsafe {
+  ///   let mut data = arena.alloc::<Vec<u8>>().unwrap();
+  ///   data.write(vec![1, 2, 3]);
+  ///
+  ///   arena.clear().unwrap();
+  /// }
+  ///
+  /// ```
+  pub unsafe fn clear(&self) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    let write = self.begin_write();
+    match self.partition_header {
+      Some(header) => {
+        let cleared = header.as_ref().cleared(self.data_offset);
+        header.as_ptr().write(cleared);
+      }
+      None => (*self.inner.as_ptr()).clear(),
+    }
+    // the header is rewritten in place, with the seqlock kept.
+    drop(write);
+
+    Ok(())
+  }
+
+  /// Deallocates the memory at the given offset and size, the `offset..offset + size` will be made to a segment,
+  /// returns `true` if the deallocation is successful.
+  ///
+  /// # Safety
+  /// - you must ensure the same `offset..offset + size` is not deallocated twice.
+  /// - `offset` must be larger than the [`Arena::data_offset`].
+  /// - `offset + size` must be less than the [`Arena::allocated`].
+  #[inline]
+  pub unsafe fn dealloc(&self, offset: u32, size: u32) -> bool {
+    #[cfg(feature = "trace")]
+    self.trace(TraceOp::Dealloc, offset, size);
+    self.emit(ArenaEvent::Dealloc { offset, size });
+
+    let _serial = self.serialize();
+    // first try to deallocate the memory back to the main memory.
+    let header = self.header();
+    let _write = self.begin_write();
+    // if the offset + size is the current allocated size, then we can deallocate the memory back to the main memory.
+    if header
+      .allocated
+      .compare_exchange(offset + size, offset, Ordering::SeqCst, Ordering::Relaxed)
+      .is_ok()
+    {
+      return true;
+    }
+
+    match self.freelist {
+      Freelist::None => {
+        self.discard(offset, size, Discard::SmallSegment);
+        true
+      }
+      Freelist::Optimistic => self.optimistic_dealloc(offset, size),
+      Freelist::Pessimistic => self.pessimistic_dealloc(offset, size),
+    }
+  }
+
+  /// Deallocates a buddy block allocated by [`alloc_buddy`](Self::alloc_buddy), the block is merged with its buddy
+  /// (`offset ^ (1 << order)`) if the buddy is a free segment in the freelist, and the merged block is merged
+  /// with its buddy again, until the buddy is not free, then the merged block is deallocated by [`dealloc`](Self::dealloc).
+  ///
+  /// Returns `true` if the deallocation is successful.
+  ///
+  /// # Safety
+  /// - This method is not lock-free, there must be no concurrent allocations or deallocations
+  ///   on the ARENA, like [`rebuild_free_list`](Self::rebuild_free_list).
+  /// - The same as [`dealloc`](Self::dealloc), the block must be detached, and must not be deallocated twice.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut parent = arena.alloc_buddy(7).unwrap();
+  /// parent.detach();
+  /// let mut tail = arena.alloc_bytes(8).unwrap();
+  /// tail.detach();
+  ///
+  /// // split the parent into two buddies of order 6, then free them.
+  /// let offset = parent.offset() as u32;
+  /// unsafe {
+  ///   arena.dealloc_buddy(offset, 6);
+  ///   arena.dealloc_buddy(offset + 64, 6);
+  /// }
+  /// assert!(arena.free_segments().contains(&(offset, 128)));
+  /// ```
+  pub unsafe fn dealloc_buddy(&self, offset: u32, order: u8) -> bool {
+    if !(BUDDY_MIN_ORDER..=BUDDY_MAX_ORDER).contains(&order) {
+      return false;
+    }
+
+    let _write = self.begin_write();
+    let (mut offset, mut size) = (offset, 1u32 << order);
+    while size <= 1 << (BUDDY_MAX_ORDER - 1) {
+      let buddy = offset ^ size;
+      if !self.take_segment(buddy, size) {
+        break;
+      }
+
+      offset = offset.min(buddy);
+      size <<= 1;
+    }
+
+    self.dealloc(offset, size)
+  }
+
+  /// Unlinks the free segment of exactly `offset..offset + size` from the freelist,
+  /// returns `false` if there is no such segment.
+  ///
+  /// This is not lock-free, the caller must make sure there are no concurrent allocations or deallocations.
+  fn take_segment(&self, offset: u32, size: u32) -> bool {
+    if offset < self.data_offset || self.freelist == Freelist::None {
+      return false;
+    }
+
+    let mut prev: &AtomicU64 = &self.header().sentinel;
+    loop {
+      let (prev_size, next_offset) = decode_segment_node(prev.load(Ordering::Acquire));
+      if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+        return false;
+      }
+
+      let Some(node) = self.get_segment_node(next_offset) else {
+        return false;
+      };
+      let (node_size, next_next_offset) = decode_segment_node(node.load(Ordering::Acquire));
+      if next_offset == offset {
+        if node_size as u64 + SEGMENT_NODE_SIZE as u64 != size as u64 {
+          return false;
+        }
+
+        prev.store(
+          encode_segment_node(prev_size, next_next_offset),
+          Ordering::Release,
+        );
+        return true;
+      }
+      prev = node;
+    }
+  }
+
+  /// Returns the free list position to insert the value.
+  /// - `None` means that we should insert to the head.
+  /// - `Some(offset)` means that we should insert after the offset. offset -> new -> next
+  fn find_position(&self, val: u32, check: impl Fn(u32, u32) -> bool) -> (u64, &AtomicU64) {
+    #[cfg(feature = "metrics")]
+    let mut walk = self.walk_counter();
+    let header = self.header();
+    let mut current: &AtomicU64 = &header.sentinel;
+    let mut current_node = current.load(Ordering::Acquire);
+    let (mut current_node_size, mut next_offset) = decode_segment_node(current_node);
+    let backoff = Backoff::new();
+    loop {
+      // the list is empty
+      if current_node_size == SENTINEL_SEGMENT_NODE_SIZE
+        && next_offset == SENTINEL_SEGMENT_NODE_OFFSET
+      {
+        return (current_node, current);
+      }
+
+      // the current is marked as remove and the next is the tail.
+      if current_node_size == REMOVED_SEGMENT_NODE && next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+        return (current_node, current);
+      }
+
+      if current_node_size == REMOVED_SEGMENT_NODE {
+        current = if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+          backoff.snooze();
+          &header.sentinel
+        } else {
+          match self.get_segment_node(next_offset) {
+            Some(next) => next,
+            None => return (current_node, current),
+          }
+        };
+        #[cfg(feature = "metrics")]
+        {
+          walk.depth += 1;
+        }
+        current_node = current.load(Ordering::Acquire);
+        (current_node_size, next_offset) = decode_segment_node(current_node);
+        continue;
+      }
+
+      // the next is the tail, then we should insert the value after the current node.
+      if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+        return (current_node, current);
+      }
+
+      // the next is corrupted, regard the current node as the tail.
+      let Some(next) = self.get_segment_node(next_offset) else {
+        return (current_node, current);
+      };
+      #[cfg(feature = "metrics")]
+      {
+        walk.depth += 1;
+      }
+      let next_node = next.load(Ordering::Acquire);
+      let (next_node_size, next_next_offset) = decode_segment_node(next_node);
+      if next_node_size == REMOVED_SEGMENT_NODE {
+        backoff.snooze();
+        continue;
+      }
+
+      if check(val, next_node_size) {
+        return (current_node, current);
+      }
+
+      current = next;
+      current_node = next_node;
+      current_node_size = next_node_size;
+      next_offset = next_next_offset;
+    }
+  }
+
+  #[allow(clippy::type_complexity)]
+  fn find_prev_and_next(
+    &self,
+    val: u32,
+    check: impl Fn(u32, u32) -> bool,
+    backoff: &mut SlowPathBackoff,
+  ) -> Option<((u64, &AtomicU64), (u64, &AtomicU64))> {
+    #[cfg(feature = "metrics")]
+    let mut walk = self.walk_counter();
+    let header = self.header();
+    let mut current: &AtomicU64 = &header.sentinel;
+    let mut current_node = current.load(Ordering::Acquire);
+    let (mut current_node_size, mut next_offset) = decode_segment_node(current_node);
     loop {
       // the list is empty
       if current_node_size == SENTINEL_SEGMENT_NODE_SIZE
@@ -1872,293 +6006,1110 @@ impl Arena {
         return None;
       }
 
-      // the current is marked as remove and the next is the tail.
-      if current_node_size == REMOVED_SEGMENT_NODE && next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
-        return None;
-      }
+      // the current is marked as remove and the next is the tail.
+      if current_node_size == REMOVED_SEGMENT_NODE && next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+        return None;
+      }
+
+      if current_node_size == REMOVED_SEGMENT_NODE {
+        current = if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+          return None;
+        } else {
+          self.get_segment_node(next_offset)?
+        };
+        #[cfg(feature = "metrics")]
+        {
+          walk.depth += 1;
+        }
+        current_node = current.load(Ordering::Acquire);
+        (current_node_size, next_offset) = decode_segment_node(current_node);
+        continue;
+      }
+
+      // the next is the tail
+      if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
+        return None;
+      }
+
+      let next = self.get_segment_node(next_offset)?;
+      #[cfg(feature = "metrics")]
+      {
+        walk.depth += 1;
+      }
+      let next_node = next.load(Ordering::Acquire);
+      let (next_node_size, next_next_offset) = decode_segment_node(next_node);
+
+      if check(val, next_node_size) {
+        if next_node_size == REMOVED_SEGMENT_NODE {
+          if !backoff.snooze() {
+            return None;
+          }
+          continue;
+        }
+
+        if !self.segment_in_bounds(next_offset, next_node_size) {
+          return None;
+        }
+
+        return Some(((current_node, current), (next_node, next)));
+      }
+
+      current = next;
+      current_node = next_node;
+      current_node_size = next_node_size;
+      next_offset = next_next_offset;
+    }
+  }
+
+  fn optimistic_dealloc(&self, offset: u32, size: u32) -> bool {
+    // check if we have enough space to allocate a new segment in this segment.
+    let Some(segment_node) = self.try_new_segment(offset, size) else {
+      return false;
+    };
+
+    let mut backoff = self.slow_path_backoff(None);
+
+    loop {
+      let (current_node_size_and_next_node_offset, current) = self
+        .find_position(segment_node.data_size, |val, next_node_size| {
+          val >= next_node_size
+        });
+      let (node_size, next_node_offset) =
+        decode_segment_node(current_node_size_and_next_node_offset);
+
+      if node_size == REMOVED_SEGMENT_NODE {
+        // wait other thread to make progress.
+        backoff.snooze();
+        continue;
+      }
+
+      if segment_node.ptr_offset == next_node_offset {
+        // we found ourselves, then we need to refind the position.
+        backoff.snooze();
+        continue;
+      }
+
+      segment_node.update_next_node(next_node_offset);
+
+      match current.compare_exchange(
+        current_node_size_and_next_node_offset,
+        encode_segment_node(node_size, segment_node.ptr_offset),
+        Ordering::AcqRel,
+        Ordering::Relaxed,
+      ) {
+        Ok(_) => {
+          #[cfg(feature = "tracing")]
+          tracing::debug!(
+            "create segment node ({} bytes) at {}, next segment {next_node_offset}",
+            segment_node.data_size,
+            segment_node.ptr_offset
+          );
+
+          self.freelist_changed();
+          self.discard(
+            segment_node.ptr_offset,
+            segment_node.data_offset - segment_node.ptr_offset,
+            Discard::Padding,
+          );
+          return true;
+        }
+        Err(current) => {
+          let (size, _) = decode_segment_node(current);
+          // the current is removed from the list, then we need to refind the position.
+          if size == REMOVED_SEGMENT_NODE {
+            // wait other thread to make progress.
+            backoff.snooze();
+          } else {
+            backoff.spin();
+          }
+        }
+      }
+    }
+  }
+
+  fn pessimistic_dealloc(&self, offset: u32, size: u32) -> bool {
+    // check if we have enough space to allocate a new segment in this segment.
+    let Some(segment_node) = self.try_new_segment(offset, size) else {
+      return false;
+    };
+
+    let mut backoff = self.slow_path_backoff(None);
+
+    loop {
+      let (current_node_size_and_next_node_offset, current) = self
+        .find_position(segment_node.data_size, |val, next_node_size| {
+          val <= next_node_size
+        });
+      let (node_size, next_node_offset) =
+        decode_segment_node(current_node_size_and_next_node_offset);
+
+      if node_size == REMOVED_SEGMENT_NODE {
+        // wait other thread to make progress.
+        backoff.snooze();
+        continue;
+      }
+
+      if segment_node.ptr_offset == next_node_offset {
+        // we found ourselves, then we need to refind the position.
+        backoff.snooze();
+        continue;
+      }
+
+      segment_node.update_next_node(next_node_offset);
+
+      match current.compare_exchange(
+        current_node_size_and_next_node_offset,
+        encode_segment_node(node_size, segment_node.ptr_offset),
+        Ordering::AcqRel,
+        Ordering::Relaxed,
+      ) {
+        Ok(_) => {
+          #[cfg(feature = "tracing")]
+          tracing::debug!(
+            "create segment node ({} bytes) at {}, next segment {next_node_offset}",
+            segment_node.data_size,
+            segment_node.ptr_offset
+          );
+
+          self.freelist_changed();
+          self.discard(
+            segment_node.ptr_offset,
+            segment_node.data_offset - segment_node.ptr_offset,
+            Discard::Padding,
+          );
+          return true;
+        }
+        Err(current) => {
+          let (size, _) = decode_segment_node(current);
+          // the current is removed from the list, then we need to refind the position.
+          if size == REMOVED_SEGMENT_NODE {
+            // wait other thread to make progress.
+            backoff.snooze();
+          } else {
+            backoff.spin();
+          }
+        }
+      }
+    }
+  }
+
+  /// Returns a bytes slice from the ARENA.
+  ///
+  /// # Safety
+  /// - `offset..offset + size` must be allocated memory.
+  /// - `offset` must be less than the capacity of the ARENA.
+  /// - `size` must be less than the capacity of the ARENA.
+  /// - `offset + size` must be less than the capacity of the ARENA.
+  #[inline]
+  pub const unsafe fn get_bytes(&self, offset: usize, size: usize) -> &[u8] {
+    if offset == 0 {
+      return &[];
+    }
+
+    let ptr = self.get_pointer(offset);
+    slice::from_raw_parts(ptr, size)
+  }
+
+  /// Returns a bytes slice from the ARENA, like [`get_bytes`](Self::get_bytes), but takes a [`ByteOffset`],
+  /// so an index of element ([`TypedOffset`]) can not be passed by mistake.
+  ///
+  /// # Safety
+  /// - The same as [`get_bytes`](Self::get_bytes).
+  #[inline]
+  pub const unsafe fn get_bytes_at(&self, offset: ByteOffset, size: usize) -> &[u8] {
+    self.get_bytes(offset.get() as usize, size)
+  }
+
+  /// Returns a reference to the element at the given [`TypedOffset`], i.e. at the byte offset
+  /// `index * size_of::<T>()` of the ARENA.
+  ///
+  /// # Safety
+  /// - The element must be allocated memory, and initialized.
+  /// - The byte offset of the element must be aligned to `T`.
+  /// - The element must not be mutated while the reference is alive.
+  ///
+  /// # Panics
+  /// - If the byte offset overflows `u32`, see [`TypedOffset::byte_offset`].
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, TypedOffset};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_aligned_bytes::<u32>(4).unwrap();
+  /// b.put_u32_le(7).unwrap();
+  ///
+  /// let offset = TypedOffset::<u32>::from_byte_offset(b.byte_offset()).unwrap();
+  /// assert_eq!(unsafe { *arena.get_ref(offset) }, u32::from_le(7));
+  /// ```
+  #[inline]
+  pub unsafe fn get_ref<T>(&self, offset: TypedOffset<T>) -> &T {
+    &*self
+      .ptr
+      .add(offset.byte_offset().get() as usize)
+      .cast::<T>()
+  }
+
+  /// Returns a mutable bytes slice from the ARENA.
+  /// If the ARENA is read-only, then this method will return an empty slice.
+  ///
+  /// # Safety
+  /// - `offset..offset + size` must be allocated memory.
+  /// - `offset` must be less than the capacity of the ARENA.
+  /// - `size` must be less than the capacity of the ARENA.
+  /// - `offset + size` must be less than the capacity of the ARENA.
+  ///
+  /// # Panic
+  /// - If the ARENA is read-only, then this method will panic.
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  pub unsafe fn get_bytes_mut(&self, offset: usize, size: usize) -> &mut [u8] {
+    assert!(!self.ro, "ARENA is read-only");
+
+    if offset == 0 {
+      return &mut [];
+    }
+
+    let ptr = self.get_pointer_mut(offset);
+    if ptr.is_null() {
+      return &mut [];
+    }
+
+    slice::from_raw_parts_mut(ptr, size)
+  }
 
-      if current_node_size == REMOVED_SEGMENT_NODE {
-        current = if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
-          return None;
-        } else {
-          self.get_segment_node(next_offset)
-        };
-        current_node = current.load(Ordering::Acquire);
-        (current_node_size, next_offset) = decode_segment_node(current_node);
-        continue;
-      }
+  /// Returns a mutable bytes slice from the ARENA, or [`Error::ReadOnly`] if the ARENA is read-only.
+  ///
+  /// This is the non-panicking version of [`get_bytes_mut`](Self::get_bytes_mut).
+  ///
+  /// # Safety
+  /// - The same as [`get_bytes_mut`](Self::get_bytes_mut).
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  pub unsafe fn try_get_bytes_mut(&self, offset: usize, size: usize) -> Result<&mut [u8], Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
 
-      // the next is the tail
-      if next_offset == SENTINEL_SEGMENT_NODE_OFFSET {
-        return None;
-      }
+    Ok(self.get_bytes_mut(offset, size))
+  }
 
-      let next = self.get_segment_node(next_offset);
-      let next_node = next.load(Ordering::Acquire);
-      let (next_node_size, next_next_offset) = decode_segment_node(next_node);
+  /// Returns a bytes slice from the ARENA, or [`Error::OutOfBounds`] if `offset..offset + size` is not
+  /// in the live region (`data_offset..allocated`) of the ARENA.
+  ///
+  /// This is the bounds-checked version of [`get_bytes`](Self::get_bytes).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(5).unwrap();
+  /// b.put_slice(b"hello").unwrap();
+  /// b.detach();
+  ///
+  /// assert_eq!(arena.try_bytes(b.offset(), 5).unwrap(), b"hello");
+  /// assert!(arena.try_bytes(b.offset(), 4096).is_err());
+  /// ```
+  #[inline]
+  pub fn try_bytes(&self, offset: usize, size: usize) -> Result<&[u8], Error> {
+    if !self.in_live_region(offset, size) {
+      return Err(Error::OutOfBounds {
+        offset: offset.min(u32::MAX as usize) as u32,
+        size: size.min(u32::MAX as usize) as u32,
+        capacity: self.cap,
+      });
+    }
 
-      if check(val, next_node_size) {
-        if next_node_size == REMOVED_SEGMENT_NODE {
-          backoff.snooze();
-          continue;
-        }
+    // Safety: the region is in the live region of the ARENA.
+    Ok(unsafe { slice::from_raw_parts(self.ptr.add(offset), size) })
+  }
 
-        return Some(((current_node, current), (next_node, next)));
+  /// Swaps the bytes in `[a, a + len)` with the bytes in `[b, b + len)`.
+  ///
+  /// This is a primitive for the in-place reordering of fixed-size records, e.g. sorting.
+  ///
+  /// # Errors
+  /// - [`Error::ReadOnly`] if the ARENA is read-only.
+  /// - [`Error::OutOfBounds`] if one of the regions is out of the capacity of the ARENA.
+  /// - [`Error::OverlappingRegions`] if the two regions overlap.
+  ///
+  /// # Safety
+  /// - Both regions must be allocated memory.
+  /// - The regions must not be accessed by others while swapping.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut a = arena.alloc_bytes(3).unwrap();
+  /// a.put_slice(b"foo").unwrap();
+  /// a.detach();
+  /// let mut b = arena.alloc_bytes(3).unwrap();
+  /// b.put_slice(b"bar").unwrap();
+  /// b.detach();
+  ///
+  /// unsafe {
+  ///   arena.swap_bytes(a.offset() as u32, b.offset() as u32, 3).unwrap();
+  ///   assert_eq!(arena.get_bytes(a.offset(), 3), b"bar");
+  ///   assert_eq!(arena.get_bytes(b.offset(), 3), b"foo");
+  /// }
+  /// ```
+  pub unsafe fn swap_bytes(&self, a: u32, b: u32, len: u32) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    for offset in [a, b] {
+      match offset.checked_add(len) {
+        Some(end) if end <= self.cap => {}
+        _ => {
+          return Err(Error::OutOfBounds {
+            offset,
+            size: len,
+            capacity: self.cap,
+          })
+        }
       }
+    }
 
-      current = self.get_segment_node(next_offset);
-      current_node = next_node;
-      current_node_size = next_node_size;
-      next_offset = next_next_offset;
+    if len == 0 {
+      return Ok(());
+    }
+
+    // both ends are bounded by the capacity, so the additions cannot overflow.
+    if a < b + len && b < a + len {
+      return Err(Error::OverlappingRegions);
     }
+
+    ptr::swap_nonoverlapping(
+      self.ptr.add(a as usize),
+      self.ptr.add(b as usize),
+      len as usize,
+    );
+    Ok(())
   }
 
-  fn optimistic_dealloc(&self, offset: u32, size: u32) -> bool {
-    // check if we have enough space to allocate a new segment in this segment.
-    let Some(segment_node) = self.try_new_segment(offset, size) else {
+  /// Zeroes the bytes in `[offset, offset + len)`, e.g. to scrub the secrets in a logically truncated region.
+  ///
+  /// Unlike the zeroing of [`dealloc`](Self::dealloc), the freelist is untouched. For the memory mapped ARENA,
+  /// the pages are dirtied, call [`flush`](Self::flush) to persist them.
+  ///
+  /// # Errors
+  /// - [`Error::ReadOnly`] if the ARENA is read-only.
+  /// - [`Error::OutOfBounds`] if the range is out of the data section of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(8).unwrap();
+  /// b.put_slice(b"password").unwrap();
+  ///
+  /// arena.zero_range(b.offset(), 8).unwrap();
+  /// assert_eq!(b.as_ref(), &[0; 8]);
+  /// ```
+  pub fn zero_range(&self, offset: usize, len: usize) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    self.check_data_range(offset, len)?;
+
+    // Safety: the range is in bounds of the data section.
+    unsafe {
+      ptr::write_bytes(self.ptr.add(offset), 0, len);
+    }
+    Ok(())
+  }
+
+  /// Fills the bytes in `[offset, offset + len)` with `seed, seed + 1, seed + 2, ...` (wrapping),
+  /// which can be checked by [`verify_pattern`](Self::verify_pattern), e.g. to validate the round-trips in tests.
+  ///
+  /// # Errors
+  /// - [`Error::ReadOnly`] if the ARENA is read-only.
+  /// - [`Error::OutOfBounds`] if the range is out of the data section of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let b = arena.alloc_bytes(4).unwrap();
+  ///
+  /// arena.fill_pattern(b.offset(), 4, 254).unwrap();
+  /// assert_eq!(unsafe { arena.get_bytes(b.offset(), 4) }, &[254, 255, 0, 1]);
+  /// assert!(arena.verify_pattern(b.offset(), 4, 254));
+  /// ```
+  pub fn fill_pattern(&self, offset: usize, len: usize, seed: u8) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    self.check_data_range(offset, len)?;
+
+    // Safety: the range is in bounds of the data section.
+    let buf = unsafe { slice::from_raw_parts_mut(self.ptr.add(offset), len) };
+    let mut val = seed;
+    for b in buf {
+      *b = val;
+      val = val.wrapping_add(1);
+    }
+    Ok(())
+  }
+
+  /// Returns `true` if the bytes in `[offset, offset + len)` are the pattern written by
+  /// [`fill_pattern`](Self::fill_pattern) with the same `seed`.
+  ///
+  /// Returns `false` if the range is out of the data section of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let b = arena.alloc_bytes(4).unwrap();
+  ///
+  /// arena.fill_pattern(b.offset(), 4, 1).unwrap();
+  /// assert!(arena.verify_pattern(b.offset(), 4, 1));
+  /// assert!(!arena.verify_pattern(b.offset(), 4, 2));
+  /// ```
+  pub fn verify_pattern(&self, offset: usize, len: usize, seed: u8) -> bool {
+    if self.check_data_range(offset, len).is_err() {
       return false;
-    };
+    }
 
-    let backoff = Backoff::new();
+    // Safety: the range is in bounds of the data section.
+    let buf = unsafe { slice::from_raw_parts(self.ptr.add(offset), len) };
+    buf
+      .iter()
+      .enumerate()
+      .all(|(i, b)| *b == seed.wrapping_add(i as u8))
+  }
 
-    loop {
-      let (current_node_size_and_next_node_offset, current) = self
-        .find_position(segment_node.data_size, |val, next_node_size| {
-          val >= next_node_size
-        });
-      let (node_size, next_node_offset) =
-        decode_segment_node(current_node_size_and_next_node_offset);
+  /// Returns [`Error::OutOfBounds`] if `[offset, offset + len)` is out of the data section of the ARENA.
+  #[inline]
+  fn check_data_range(&self, offset: usize, len: usize) -> Result<(), Error> {
+    match offset.checked_add(len) {
+      Some(end) if offset >= self.data_offset as usize && end <= self.cap as usize => Ok(()),
+      _ => Err(Error::OutOfBounds {
+        offset: offset.min(u32::MAX as usize) as u32,
+        size: len.min(u32::MAX as usize) as u32,
+        capacity: self.cap,
+      }),
+    }
+  }
+
+  /// Returns the bytes slice of the region identified by the token.
+  ///
+  /// # Safety
+  /// - The token must be returned by a buffer allocated by this ARENA, see [`BytesRefMut::token`].
+  /// - The region must not be freed after the token was created.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(5).unwrap();
+  /// bytes.put_slice(b"hello").unwrap();
+  /// bytes.detach();
+  /// let token = bytes.token();
+  ///
+  /// assert_eq!(unsafe { arena.resolve_token(token) }, b"hello");
+  /// ```
+  #[inline]
+  pub const unsafe fn resolve_token(&self, token: AllocToken) -> &[u8] {
+    self.get_bytes(token.offset(), token.capacity())
+  }
+
+  /// Returns the bytes slice of the buffer allocated by this ARENA.
+  ///
+  /// Unlike resolving the offset or the token of the buffer, the buffer knows which ARENA it
+  /// belongs to, so a buffer allocated by another ARENA is caught in debug builds instead of
+  /// silently reading the memory of an unrelated region.
+  ///
+  /// # Safety
+  /// - The buffer must be allocated by this ARENA, or a clone of this ARENA.
+  ///
+  /// # Panics
+  /// - In debug builds, if the buffer is allocated by another ARENA, see [`id`](Self::id).
+  /// - In debug builds, if the buffer is stale, see [`BytesRefMut::is_valid`].
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(5).unwrap();
+  /// bytes.put_slice(b"hello").unwrap();
+  ///
+  /// assert_eq!(unsafe { arena.resolve_bytes(&bytes) }, b"hello");
+  /// ```
+  #[inline]
+  pub unsafe fn resolve_bytes(&self, bytes: &BytesRefMut<'_>) -> &[u8] {
+    debug_assert_eq!(
+      bytes.arena_id(),
+      self.id,
+      "the buffer is allocated by the ARENA {}, but resolved by the ARENA {}",
+      bytes.arena_id(),
+      self.id,
+    );
+    debug_assert!(
+      bytes.is_valid(),
+      "the buffer is stale, the ARENA is cleared after the buffer is allocated"
+    );
+    self.get_bytes(bytes.offset(), bytes.capacity())
+  }
+
+  /// Returns the mutable bytes slice of the region identified by the token.
+  ///
+  /// # Safety
+  /// - The token must be returned by a buffer allocated by this ARENA, see [`BytesRefMut::token`].
+  /// - The region must not be freed after the token was created.
+  ///
+  /// # Panic
+  /// - If the ARENA is read-only, then this method will panic.
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  pub unsafe fn resolve_token_mut(&self, token: AllocToken) -> &mut [u8] {
+    self.get_bytes_mut(token.offset(), token.capacity())
+  }
+
+  /// Returns a pointer to the memory at the given offset.
+  ///
+  /// # Safety
+  /// - `offset` must be less than the capacity of the ARENA.
+  #[inline]
+  pub const unsafe fn get_pointer(&self, offset: usize) -> *const u8 {
+    if offset == 0 {
+      return self.ptr;
+    }
+    self.ptr.add(offset)
+  }
+
+  /// Returns a pointer to the memory at the given offset.
+  /// If the ARENA is read-only, then this method will return a null pointer.
+  ///
+  /// # Safety
+  /// - `offset` must be less than the capacity of the ARENA.
+  ///
+  /// # Panic
+  /// - If the ARENA is read-only, then this method will panic.
+  #[inline]
+  pub unsafe fn get_pointer_mut(&self, offset: usize) -> *mut u8 {
+    assert!(!self.ro, "ARENA is read-only");
+
+    if offset == 0 {
+      return self.ptr;
+    }
+
+    self.ptr.add(offset)
+  }
+
+  /// Returns a pointer to the memory at the given offset, or [`Error::ReadOnly`] if the ARENA is read-only.
+  ///
+  /// This is the non-panicking version of [`get_pointer_mut`](Self::get_pointer_mut).
+  ///
+  /// # Safety
+  /// - The same as [`get_pointer_mut`](Self::get_pointer_mut).
+  #[inline]
+  pub unsafe fn try_get_pointer_mut(&self, offset: usize) -> Result<*mut u8, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    Ok(self.get_pointer_mut(offset))
+  }
+
+  /// Returns a pointer to the memory at the given offset, or `None` if the offset is not in the
+  /// live region (`data_offset..allocated`) of the ARENA.
+  ///
+  /// This is the bounds-checked version of [`get_pointer`](Self::get_pointer).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(8).unwrap();
+  /// b.detach();
+  ///
+  /// assert!(arena.checked_pointer(b.offset()).is_some());
+  /// assert!(arena.checked_pointer(arena.allocated()).is_none());
+  /// ```
+  #[inline]
+  pub fn checked_pointer(&self, offset: usize) -> Option<*const u8> {
+    if !self.in_live_region(offset, 1) {
+      return None;
+    }
+
+    // Safety: the offset is in the live region of the ARENA.
+    Some(unsafe { self.ptr.add(offset) })
+  }
+
+  /// Returns an aligned pointer to the memory at the given offset.
+  ///
+  /// # Safety
+  /// - `offset..offset + mem::size_of::<T>() + padding` must be allocated memory.
+  /// - `offset` must be less than the capacity of the ARENA.
+  #[inline]
+  pub unsafe fn get_aligned_pointer<T>(&self, offset: usize) -> *const T {
+    if offset == 0 {
+      return ptr::null();
+    }
 
-      if node_size == REMOVED_SEGMENT_NODE {
-        // wait other thread to make progress.
-        backoff.snooze();
-        continue;
-      }
+    let align_offset = align_offset::<T>(offset as u32) as usize;
+    self.ptr.add(align_offset).cast()
+  }
 
-      if segment_node.ptr_offset == next_node_offset {
-        // we found ourselves, then we need to refind the position.
-        backoff.snooze();
-        continue;
-      }
+  /// Returns an aligned pointer to the memory at the given offset.
+  /// If the ARENA is read-only, then this method will return a null pointer.
+  ///
+  /// # Safety
+  /// - `offset..offset + mem::size_of::<T>() + padding` must be allocated memory.
+  /// - `offset` must be less than the capacity of the ARENA.
+  ///
+  /// # Panic
+  /// - If the ARENA is read-only, then this method will panic.
+  #[inline]
+  pub unsafe fn get_aligned_pointer_mut<T>(&self, offset: usize) -> NonNull<T> {
+    assert!(!self.ro, "ARENA is read-only");
 
-      segment_node.update_next_node(next_node_offset);
+    if offset == 0 {
+      return NonNull::dangling();
+    }
 
-      match current.compare_exchange(
-        current_node_size_and_next_node_offset,
-        encode_segment_node(node_size, segment_node.ptr_offset),
-        Ordering::AcqRel,
-        Ordering::Relaxed,
-      ) {
-        Ok(_) => {
-          #[cfg(feature = "tracing")]
-          tracing::debug!(
-            "create segment node ({} bytes) at {}, next segment {next_node_offset}",
-            segment_node.data_size,
-            segment_node.ptr_offset
-          );
+    let align_offset = align_offset::<T>(offset as u32) as usize;
+    let ptr = self.ptr.add(align_offset).cast();
+    NonNull::new_unchecked(ptr)
+  }
 
-          self.increase_discarded(segment_node.data_offset - segment_node.ptr_offset);
-          return true;
-        }
-        Err(current) => {
-          let (size, _) = decode_segment_node(current);
-          // the current is removed from the list, then we need to refind the position.
-          if size == REMOVED_SEGMENT_NODE {
-            // wait other thread to make progress.
-            backoff.snooze();
-          } else {
-            backoff.spin();
-          }
-        }
-      }
+  /// Returns an aligned pointer to the memory at the given offset, or [`Error::ReadOnly`] if the ARENA is read-only.
+  ///
+  /// This is the non-panicking version of [`get_aligned_pointer_mut`](Self::get_aligned_pointer_mut).
+  ///
+  /// # Safety
+  /// - The same as [`get_aligned_pointer_mut`](Self::get_aligned_pointer_mut).
+  #[inline]
+  pub unsafe fn try_get_aligned_pointer_mut<T>(&self, offset: usize) -> Result<NonNull<T>, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
     }
+
+    Ok(self.get_aligned_pointer_mut(offset))
   }
 
-  fn pessimistic_dealloc(&self, offset: u32, size: u32) -> bool {
-    // check if we have enough space to allocate a new segment in this segment.
-    let Some(segment_node) = self.try_new_segment(offset, size) else {
-      return false;
-    };
+  /// Returns an aligned pointer to the memory at the given offset, or `None` if the aligned
+  /// `offset..offset + mem::size_of::<T>()` is not in the live region (`data_offset..allocated`) of the ARENA.
+  ///
+  /// This is the bounds-checked version of [`get_aligned_pointer`](Self::get_aligned_pointer).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_aligned_bytes::<u64>(0).unwrap();
+  /// b.detach();
+  ///
+  /// assert!(arena.checked_aligned_pointer::<u64>(b.offset()).is_some());
+  /// assert!(arena.checked_aligned_pointer::<u64>(b.offset() + 1).is_none());
+  /// ```
+  #[inline]
+  pub fn checked_aligned_pointer<T>(&self, offset: usize) -> Option<*const T> {
+    let align = mem::align_of::<T>();
+    let aligned = offset.checked_add(align - 1)? & !(align - 1);
+    if !self.in_live_region(aligned, mem::size_of::<T>()) {
+      return None;
+    }
 
-    let backoff = Backoff::new();
+    // Safety: the aligned offset is in the live region of the ARENA.
+    Some(unsafe { self.ptr.add(aligned).cast() })
+  }
 
-    loop {
-      let (current_node_size_and_next_node_offset, current) = self
-        .find_position(segment_node.data_size, |val, next_node_size| {
-          val <= next_node_size
-        });
-      let (node_size, next_node_offset) =
-        decode_segment_node(current_node_size_and_next_node_offset);
+  /// Writes `value` at the given offset, the offset will be aligned to the alignment of `T`,
+  /// which is the write half of persisting a recoverable structure, see [`read_at`](Self::read_at).
+  ///
+  /// Returns [`Error::ReadOnly`] if the ARENA is read-only, or [`Error::OutOfBounds`] if the aligned
+  /// `offset..offset + mem::size_of::<T>()` is out of the data section.
+  ///
+  /// # Safety
+  /// - `offset..offset + mem::size_of::<T>() + padding` must be allocated memory, which is not
+  ///   referenced by others.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_aligned_bytes::<u64>(0).unwrap();
+  /// b.detach();
+  ///
+  /// unsafe {
+  ///   arena.write_at(b.offset(), 42u64).unwrap();
+  ///   assert_eq!(arena.read_at::<u64>(b.offset()).unwrap(), 42);
+  /// }
+  /// ```
+  pub unsafe fn write_at<T>(&self, offset: usize, value: T) -> Result<(), Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
 
-      if node_size == REMOVED_SEGMENT_NODE {
-        // wait other thread to make progress.
-        backoff.snooze();
-        continue;
-      }
+    let offset = self.check_typed_bounds::<T>(offset)?;
+    ptr::write_unaligned(self.ptr.add(offset).cast::<T>(), value);
+    Ok(())
+  }
 
-      if segment_node.ptr_offset == next_node_offset {
-        // we found ourselves, then we need to refind the position.
-        backoff.snooze();
-        continue;
-      }
+  /// Reads a `T` at the given offset, the offset will be aligned to the alignment of `T`,
+  /// which is the read half of recovering a persisted structure, see [`write_at`](Self::write_at).
+  ///
+  /// Returns [`Error::OutOfBounds`] if the aligned `offset..offset + mem::size_of::<T>()` is out of the data section.
+  ///
+  /// # Safety
+  /// - `offset..offset + mem::size_of::<T>() + padding` must be allocated memory, which contains a valid `T`.
+  ///
+  /// # Example
+  ///
+  /// See [`write_at`](Self::write_at).
+  pub unsafe fn read_at<T: Copy>(&self, offset: usize) -> Result<T, Error> {
+    let offset = self.check_typed_bounds::<T>(offset)?;
+    Ok(ptr::read_unaligned(self.ptr.add(offset).cast::<T>()))
+  }
 
-      segment_node.update_next_node(next_node_offset);
+  /// Returns `true` if `offset..offset + size` is in the live region (`data_offset..allocated`) of the ARENA.
+  #[inline]
+  fn in_live_region(&self, offset: usize, size: usize) -> bool {
+    let allocated = self.header().allocated.load(Ordering::Acquire) as usize;
+    offset >= self.data_offset as usize
+      && offset
+        .checked_add(size)
+        .map_or(false, |end| end <= allocated)
+  }
 
-      match current.compare_exchange(
-        current_node_size_and_next_node_offset,
-        encode_segment_node(node_size, segment_node.ptr_offset),
-        Ordering::AcqRel,
-        Ordering::Relaxed,
-      ) {
-        Ok(_) => {
-          #[cfg(feature = "tracing")]
-          tracing::debug!(
-            "create segment node ({} bytes) at {}, next segment {next_node_offset}",
-            segment_node.data_size,
-            segment_node.ptr_offset
-          );
+  /// Returns the aligned offset of a `T` at `offset`, if the `T` is in the data section.
+  #[inline]
+  fn check_typed_bounds<T>(&self, offset: usize) -> Result<usize, Error> {
+    let out_of_bounds = || Error::OutOfBounds {
+      offset: offset.min(u32::MAX as usize) as u32,
+      size: mem::size_of::<T>() as u32,
+      capacity: self.cap,
+    };
 
-          self.increase_discarded(segment_node.data_offset - segment_node.ptr_offset);
-          return true;
-        }
-        Err(current) => {
-          let (size, _) = decode_segment_node(current);
-          // the current is removed from the list, then we need to refind the position.
-          if size == REMOVED_SEGMENT_NODE {
-            // wait other thread to make progress.
-            backoff.snooze();
-          } else {
-            backoff.spin();
-          }
-        }
-      }
+    if offset < self.data_offset as usize || offset > self.cap as usize {
+      return Err(out_of_bounds());
+    }
+
+    let align = mem::align_of::<T>() as u64;
+    let aligned = (offset as u64 + align - 1) & !(align - 1);
+    if aligned + mem::size_of::<T>() as u64 > self.cap as u64 {
+      return Err(out_of_bounds());
     }
+    Ok(aligned as usize)
   }
 
-  /// Returns a bytes slice from the ARENA.
+  /// Returns a slice of `T` at the given offset, the offset will be aligned to the alignment of `T`.
   ///
   /// # Safety
-  /// - `offset..offset + size` must be allocated memory.
-  /// - `offset` must be less than the capacity of the ARENA.
-  /// - `size` must be less than the capacity of the ARENA.
-  /// - `offset + size` must be less than the capacity of the ARENA.
+  /// - `offset..offset + mem::size_of::<T>() * len + padding` must be allocated memory.
+  /// - The memory must contain `len` valid values of `T`.
+  ///
+  /// # Panic
+  /// - If the slice is out of the bounds of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_aligned_bytes::<u32>(12).unwrap();
+  /// for i in 0..3u32 {
+  ///   b.put_u32_ne(i).unwrap();
+  /// }
+  /// b.detach();
+  ///
+  /// let slice = unsafe { arena.get_slice::<u32>(b.offset(), 3) };
+  /// assert_eq!(slice, &[0, 1, 2]);
+  /// ```
   #[inline]
-  pub const unsafe fn get_bytes(&self, offset: usize, size: usize) -> &[u8] {
+  pub unsafe fn get_slice<T: Copy>(&self, offset: usize, len: usize) -> &[T] {
     if offset == 0 {
       return &[];
     }
 
-    let ptr = self.get_pointer(offset);
-    slice::from_raw_parts(ptr, size)
+    let align_offset = self.check_slice_bounds::<T>(offset, len);
+    slice::from_raw_parts(self.ptr.add(align_offset).cast(), len)
   }
 
-  /// Returns a mutable bytes slice from the ARENA.
-  /// If the ARENA is read-only, then this method will return an empty slice.
+  /// Returns a mutable slice of `T` at the given offset, the offset will be aligned to the alignment of `T`.
   ///
   /// # Safety
-  /// - `offset..offset + size` must be allocated memory.
-  /// - `offset` must be less than the capacity of the ARENA.
-  /// - `size` must be less than the capacity of the ARENA.
-  /// - `offset + size` must be less than the capacity of the ARENA.
+  /// - `offset..offset + mem::size_of::<T>() * len + padding` must be allocated memory.
+  /// - The memory must contain `len` valid values of `T`.
   ///
   /// # Panic
-  /// - If the ARENA is read-only, then this method will panic.
+  /// - If the ARENA is read-only.
+  /// - If the slice is out of the bounds of the ARENA.
   #[allow(clippy::mut_from_ref)]
   #[inline]
-  pub unsafe fn get_bytes_mut(&self, offset: usize, size: usize) -> &mut [u8] {
+  pub unsafe fn get_slice_mut<T: Copy>(&self, offset: usize, len: usize) -> &mut [T] {
     assert!(!self.ro, "ARENA is read-only");
 
     if offset == 0 {
       return &mut [];
     }
 
-    let ptr = self.get_pointer_mut(offset);
-    if ptr.is_null() {
-      return &mut [];
-    }
+    let align_offset = self.check_slice_bounds::<T>(offset, len);
+    slice::from_raw_parts_mut(self.ptr.add(align_offset).cast(), len)
+  }
 
-    slice::from_raw_parts_mut(ptr, size)
+  /// Returns the aligned offset of the slice, panics if the slice is out of bounds.
+  #[inline]
+  fn check_slice_bounds<T>(&self, offset: usize, len: usize) -> usize {
+    let align_offset = align_offset::<T>(offset as u32) as usize;
+    let end = mem::size_of::<T>()
+      .checked_mul(len)
+      .and_then(|size| size.checked_add(align_offset));
+    match end {
+      Some(end) if end <= self.cap as usize => align_offset,
+      _ => panic!(
+        "slice of {len} elements at offset {offset} is out of the bounds of the ARENA (capacity: {})",
+        self.cap
+      ),
+    }
   }
 
-  /// Returns a pointer to the memory at the given offset.
+  /// Returns the offset to the start of the ARENA.
   ///
   /// # Safety
-  /// - `offset` must be less than the capacity of the ARENA.
+  /// - `ptr` must be allocated by this ARENA.
+  #[inline]
+  pub unsafe fn offset(&self, ptr: *const u8) -> usize {
+    let offset = ptr.offset_from(self.ptr);
+    offset as usize
+  }
+
+  /// Returns `true` if the pointer is in the data section of the ARENA, i.e. in [`data_ptr_range`](Self::data_ptr_range).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let bytes = arena.alloc_bytes(8).unwrap();
+  /// assert!(arena.contains_ptr(bytes.as_ptr()));
+  ///
+  /// let other = [0u8; 8];
+  /// assert!(!arena.contains_ptr(other.as_ptr()));
+  /// ```
+  #[inline]
+  pub fn contains_ptr(&self, ptr: *const u8) -> bool {
+    self.data_ptr_range().contains(&ptr)
+  }
+
+  /// Returns the offset of the pointer to the start of the ARENA, or `None` if the pointer
+  /// is not in the data section of the ARENA, e.g. it is derived from another ARENA.
+  ///
+  /// This is the checked version of [`offset`](Self::offset).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut bytes = arena.alloc_bytes(8).unwrap();
+  /// bytes.detach();
+  /// assert_eq!(arena.try_offset(bytes.as_ptr()), Some(bytes.offset()));
+  ///
+  /// let other = Arena::new(ArenaOptions::new());
+  /// let bytes = other.alloc_bytes(8).unwrap();
+  /// assert_eq!(arena.try_offset(bytes.as_ptr()), None);
+  /// ```
+  #[inline]
+  pub fn try_offset(&self, ptr: *const u8) -> Option<usize> {
+    self
+      .contains_ptr(ptr)
+      .then(|| ptr as usize - self.ptr as usize)
+  }
+
+  /// Fires the hook set by [`with_on_high_water`](Self::with_on_high_water) if `allocated` first crosses the threshold.
+  #[inline]
+  fn check_high_water(&self, allocated: u32) {
+    if let Some(hook) = &self.on_high_water {
+      if allocated as f64 >= self.cap as f64 * hook.ratio
+        && self
+          .header()
+          .high_water
+          .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed)
+          .is_ok()
+      {
+        (hook.f)();
+      }
+    }
+  }
+
+  /// Retries the allocation once more if the hook set by [`with_on_oom`](Self::with_on_oom) asks to.
+  #[inline]
+  fn retry_on_oom(
+    &self,
+    mut f: impl FnMut() -> Result<Option<Meta>, Error>,
+  ) -> Result<Option<Meta>, Error> {
+    // the hook is called out of the write, so that it can read the header consistently.
+    let mut write = || {
+      let _serial = self.serialize();
+      let _write = self.begin_write();
+      f()
+    };
+    let res = match write() {
+      Err(Error::InsufficientSpace {
+        requested,
+        available,
+      }) => match &self.on_oom {
+        Some(hook) if (hook.0)(requested) == OomAction::Retry => write(),
+        _ => Err(Error::InsufficientSpace {
+          requested,
+          available,
+        }),
+      },
+      res => res,
+    };
+
+    if let Ok(Some(meta)) = &res {
+      #[cfg(feature = "trace")]
+      self.trace(TraceOp::Alloc, meta.memory_offset, meta.memory_size);
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      self.auto_flush();
+
+      self.emit(ArenaEvent::Alloc {
+        offset: meta.memory_offset,
+        size: meta.memory_size,
+      });
+    }
+
+    res
+  }
+
+  /// Invokes the hook set by [`with_on_event`](Self::with_on_event), if any.
+  #[inline]
+  fn emit(&self, event: ArenaEvent) {
+    if let Some(hook) = &self.on_event {
+      (hook.0)(event);
+    }
+  }
+
+  #[cfg(feature = "trace")]
   #[inline]
-  pub const unsafe fn get_pointer(&self, offset: usize) -> *const u8 {
-    if offset == 0 {
-      return self.ptr;
+  fn trace(&self, op: TraceOp, offset: u32, size: u32) {
+    if let Some(tracer) = &self.tracer {
+      tracer.record(op, offset, size);
     }
-    self.ptr.add(offset)
   }
 
-  /// Returns a pointer to the memory at the given offset.
-  /// If the ARENA is read-only, then this method will return a null pointer.
-  ///
-  /// # Safety
-  /// - `offset` must be less than the capacity of the ARENA.
-  ///
-  /// # Panic
-  /// - If the ARENA is read-only, then this method will panic.
   #[inline]
-  pub unsafe fn get_pointer_mut(&self, offset: usize) -> *mut u8 {
-    assert!(!self.ro, "ARENA is read-only");
+  fn alloc_bytes_in(&self, size: u32, zero: bool) -> Result<Option<Meta>, Error> {
+    self.alloc_bytes_with_stats_in(
+      size,
+      zero,
+      &mut AllocStats::default(),
+      &mut self.slow_path_backoff(None),
+    )
+  }
 
-    if offset == 0 {
-      return self.ptr;
+  /// Returns a new backoff for the freelist operations, which parks the thread after exhausting the spins
+  /// if [`ArenaOptions::with_park_on_contention`] is set.
+  #[inline]
+  fn slow_path_backoff(&self, max_spins: Option<u32>) -> SlowPathBackoff<'_> {
+    SlowPathBackoff {
+      backoff: Backoff::new(),
+      remaining: max_spins,
+      exhausted: false,
+      #[cfg(feature = "std")]
+      parker: self.parker.as_deref(),
+      #[cfg(not(feature = "std"))]
+      _marker: core::marker::PhantomData,
     }
-
-    self.ptr.add(offset)
   }
 
-  /// Returns an aligned pointer to the memory at the given offset.
-  ///
-  /// # Safety
-  /// - `offset..offset + mem::size_of::<T>() + padding` must be allocated memory.
-  /// - `offset` must be less than the capacity of the ARENA.
+  /// Marks a write of the header or the freelist in progress until the returned guard is dropped,
+  /// if [`ArenaOptions::with_seqlock`] is set.
   #[inline]
-  pub unsafe fn get_aligned_pointer<T>(&self, offset: usize) -> *const T {
-    if offset == 0 {
-      return ptr::null();
+  fn begin_write(&self) -> Option<SeqWrite<'_>> {
+    if self.seqlock {
+      Some(self.header().begin_write())
+    } else {
+      None
     }
+  }
 
-    let align_offset = align_offset::<T>(offset as u32) as usize;
-    self.ptr.add(align_offset).cast()
+  /// Serializes the allocation or the deallocation until the guard is dropped,
+  /// if [`ArenaOptions::with_deterministic`] is set.
+  #[inline]
+  fn serialize(&self) -> Option<SerialGuard<'_>> {
+    self.serial.as_deref().map(SerialLock::lock)
   }
 
-  /// Returns an aligned pointer to the memory at the given offset.
-  /// If the ARENA is read-only, then this method will return a null pointer.
-  ///
-  /// # Safety
-  /// - `offset..offset + mem::size_of::<T>() + padding` must be allocated memory.
-  /// - `offset` must be less than the capacity of the ARENA.
-  ///
-  /// # Panic
-  /// - If the ARENA is read-only, then this method will panic.
+  /// Counts a successful allocation, and flushes the memory map asynchronously on every
+  /// [`ArenaOptions::with_auto_flush_interval`] allocations.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   #[inline]
-  pub unsafe fn get_aligned_pointer_mut<T>(&self, offset: usize) -> NonNull<T> {
-    assert!(!self.ro, "ARENA is read-only");
+  fn auto_flush(&self) {
+    let Some(auto_flush) = &self.auto_flush else {
+      return;
+    };
 
-    if offset == 0 {
-      return NonNull::dangling();
+    let allocations = auto_flush
+      .allocations
+      .fetch_add(1, Ordering::Relaxed)
+      .wrapping_add(1);
+    if allocations % auto_flush.interval == 0 {
+      // the allocation is already done, a failed flush is retried on the next interval.
+      let _ = self.flush_async();
     }
+  }
 
-    let align_offset = align_offset::<T>(offset as u32) as usize;
-    let ptr = self.ptr.add(align_offset).cast();
-    NonNull::new_unchecked(ptr)
+  /// Unparks the threads parked on the freelist, if any, see [`ArenaOptions::with_park_on_contention`].
+  #[inline]
+  fn freelist_changed(&self) {
+    #[cfg(feature = "std")]
+    if let Some(parker) = &self.parker {
+      parker.unpark_all();
+    }
   }
 
-  /// Returns the offset to the start of the ARENA.
-  ///
-  /// # Safety
-  /// - `ptr` must be allocated by this ARENA.
+  /// Returns the error of giving up the slow path due to the contention.
   #[inline]
-  pub unsafe fn offset(&self, ptr: *const u8) -> usize {
-    let offset = ptr.offset_from(self.ptr);
-    offset as usize
+  fn contended(&self, size: u32) -> Error {
+    Error::InsufficientSpace {
+      requested: size,
+      available: self.remaining() as u32,
+    }
   }
 
-  fn alloc_bytes_in(&self, size: u32) -> Result<Option<Meta>, Error> {
+  fn alloc_bytes_with_stats_in(
+    &self,
+    size: u32,
+    zero: bool,
+    stats: &mut AllocStats,
+    backoff: &mut SlowPathBackoff,
+  ) -> Result<Option<Meta>, Error> {
+    *stats = AllocStats::default();
+
     if self.ro {
       return Err(Error::ReadOnly);
     }
@@ -2185,8 +7136,12 @@ impl Arena {
           #[cfg(feature = "tracing")]
           tracing::debug!("allocate {} bytes at offset {} from memory", size, offset);
 
+          self.check_high_water(want);
           let allocated = Meta::new(self.ptr as _, offset, size);
-          unsafe { allocated.clear(self) };
+          if zero {
+            unsafe { allocated.clear(self) };
+          }
+          stats.fast_path = true;
           return Ok(Some(allocated));
         }
         Err(x) => allocated = x,
@@ -2194,45 +7149,111 @@ impl Arena {
     }
 
     // allocate through slow path
+    let (bytes, retries) = self.alloc_slow_path_with(size, size, zero, backoff)?;
+    stats.slow_path_retries = retries;
+    stats.from_free_list = true;
+    Ok(Some(bytes))
+  }
+
+  /// Allocates `size` bytes through the slow path, retries up to the maximum retries with the same `backoff`,
+  /// so that every allocation path honours the park on contention and the spin budget.
+  ///
+  /// Returns the allocated segment and the number of retries, `requested` is only reported
+  /// by the error if there is no freelist.
+  fn alloc_slow_path_with(
+    &self,
+    size: u32,
+    requested: u32,
+    zero: bool,
+    backoff: &mut SlowPathBackoff,
+  ) -> Result<(Meta, u8), Error> {
     let mut i = 0;
 
     loop {
-      match self.freelist {
+      let res = match self.freelist {
         Freelist::None => {
           return Err(Error::InsufficientSpace {
-            requested: size,
+            requested,
             available: self.remaining() as u32,
           })
         }
-        Freelist::Optimistic => match self.alloc_slow_path_optimistic(size) {
-          Ok(bytes) => return Ok(Some(bytes)),
-          Err(e) => {
-            if i == self.max_retries - 1 {
-              return Err(e);
-            }
-          }
-        },
-        Freelist::Pessimistic => match self.alloc_slow_path_pessimistic(size) {
-          Ok(bytes) => return Ok(Some(bytes)),
-          Err(e) => {
-            if i == self.max_retries - 1 {
-              return Err(e);
-            }
+        Freelist::Optimistic => self.alloc_slow_path_optimistic_with(size, zero, backoff),
+        Freelist::Pessimistic => self.alloc_slow_path_pessimistic_with(size, zero, backoff),
+      };
+
+      match res {
+        Ok(allocated) => return Ok((allocated, i)),
+        Err(e) => {
+          if i + 1 >= self.max_retries || backoff.exhausted() {
+            return Err(e);
           }
-        },
+        }
       }
 
       i += 1;
     }
   }
 
+  fn alloc_aligned_in<T>(&self, align: u32) -> Result<Option<Meta>, Error> {
+    if self.ro {
+      return Err(Error::ReadOnly);
+    }
+
+    let header = self.header();
+    let size = mem::size_of::<T>() as u32;
+    let mut allocated = header.allocated.load(Ordering::Acquire);
+    let want = loop {
+      let want = align_address(self.ptr, allocated, align) + size as u64;
+      if want > self.cap as u64 {
+        break size;
+      }
+
+      match header.allocated.compare_exchange_weak(
+        allocated,
+        want as u32,
+        Ordering::SeqCst,
+        Ordering::Acquire,
+      ) {
+        Ok(offset) => {
+          self.check_high_water(want as u32);
+          let mut allocated = Meta::new(self.ptr as _, offset, want as u32 - offset);
+          allocated.align_to_with::<T>(align);
+
+          #[cfg(feature = "tracing")]
+          tracing::debug!(
+            "allocate {} bytes at offset {} from memory",
+            want as u32 - offset,
+            offset
+          );
+
+          if self.zero_on_free {
+            unsafe { allocated.clear(self) };
+          }
+          return Ok(Some(allocated));
+        }
+        Err(x) => allocated = x,
+      }
+    };
+
+    // allocate through slow path, the segment is padded for the alignment.
+    let padded = size.saturating_add(align - 1);
+    let (mut allocated, _) = self.alloc_slow_path_with(
+      padded,
+      want,
+      self.zero_on_free,
+      &mut self.slow_path_backoff(None),
+    )?;
+    allocated.align_to_with::<T>(align);
+    Ok(Some(allocated))
+  }
+
   fn alloc_aligned_bytes_in<T>(&self, extra: u32) -> Result<Option<Meta>, Error> {
     if self.ro {
       return Err(Error::ReadOnly);
     }
 
     if mem::size_of::<T>() == 0 {
-      return self.alloc_bytes_in(extra);
+      return self.alloc_bytes_in(extra, self.zero_on_free);
     }
 
     let header = self.header();
@@ -2253,6 +7274,7 @@ impl Arena {
         Ordering::Acquire,
       ) {
         Ok(offset) => {
+          self.check_high_water(want);
           let mut allocated = Meta::new(self.ptr as _, offset, want - offset);
           allocated.align_bytes_to::<T>();
           #[cfg(feature = "tracing")]
@@ -2268,44 +7290,14 @@ impl Arena {
     };
 
     // allocate through slow path
-    let mut i = 0;
-    loop {
-      match self.freelist {
-        Freelist::None => {
-          return Err(Error::InsufficientSpace {
-            requested: want,
-            available: self.remaining() as u32,
-          })
-        }
-        Freelist::Optimistic => {
-          match self.alloc_slow_path_optimistic(Self::pad::<T>() as u32 + extra) {
-            Ok(mut bytes) => {
-              bytes.align_bytes_to::<T>();
-              return Ok(Some(bytes));
-            }
-            Err(e) => {
-              if i == self.max_retries - 1 {
-                return Err(e);
-              }
-            }
-          }
-        }
-        Freelist::Pessimistic => {
-          match self.alloc_slow_path_pessimistic(Self::pad::<T>() as u32 + extra) {
-            Ok(mut bytes) => {
-              bytes.align_bytes_to::<T>();
-              return Ok(Some(bytes));
-            }
-            Err(e) => {
-              if i == self.max_retries - 1 {
-                return Err(e);
-              }
-            }
-          }
-        }
-      }
-      i += 1;
-    }
+    let (mut bytes, _) = self.alloc_slow_path_with(
+      Self::pad::<T>() as u32 + extra,
+      want,
+      self.zero_on_free,
+      &mut self.slow_path_backoff(None),
+    )?;
+    bytes.align_bytes_to::<T>();
+    Ok(Some(bytes))
   }
 
   fn alloc_in<T>(&self) -> Result<Option<Meta>, Error> {
@@ -2334,6 +7326,7 @@ impl Arena {
         Ordering::Acquire,
       ) {
         Ok(offset) => {
+          self.check_high_water(want);
           let mut allocated = Meta::new(self.ptr as _, offset, want - offset);
           allocated.align_to::<T>();
 
@@ -2344,7 +7337,9 @@ impl Arena {
             offset
           );
 
-          unsafe { allocated.clear(self) };
+          if self.zero_on_free {
+            unsafe { allocated.clear(self) };
+          }
           return Ok(Some(allocated));
         }
         Err(x) => allocated = x,
@@ -2352,54 +7347,33 @@ impl Arena {
     };
 
     // allocate through slow path
-    let mut i = 0;
-
-    loop {
-      match self.freelist {
-        Freelist::None => {
-          return Err(Error::InsufficientSpace {
-            requested: want,
-            available: self.remaining() as u32,
-          })
-        }
-        Freelist::Optimistic => match self.alloc_slow_path_optimistic(Self::pad::<T>() as u32) {
-          Ok(mut allocated) => {
-            allocated.align_to::<T>();
-            return Ok(Some(allocated));
-          }
-          Err(e) => {
-            if i == self.max_retries - 1 {
-              return Err(e);
-            }
-          }
-        },
-        Freelist::Pessimistic => match self.alloc_slow_path_pessimistic(Self::pad::<T>() as u32) {
-          Ok(mut allocated) => {
-            allocated.align_to::<T>();
-            return Ok(Some(allocated));
-          }
-          Err(e) => {
-            if i == self.max_retries - 1 {
-              return Err(e);
-            }
-          }
-        },
-      }
-
-      i += 1;
-    }
+    let (mut allocated, _) = self.alloc_slow_path_with(
+      Self::pad::<T>() as u32,
+      want,
+      self.zero_on_free,
+      &mut self.slow_path_backoff(None),
+    )?;
+    allocated.align_to::<T>();
+    Ok(Some(allocated))
   }
 
-  fn alloc_slow_path_pessimistic(&self, size: u32) -> Result<Meta, Error> {
+  fn alloc_slow_path_pessimistic_with(
+    &self,
+    size: u32,
+    zero: bool,
+    backoff: &mut SlowPathBackoff,
+  ) -> Result<Meta, Error> {
+    #[cfg(feature = "metrics")]
+    self.count_slow_path_attempt();
+    backoff.restart();
+
     if self.ro {
       return Err(Error::ReadOnly);
     }
 
-    let backoff = Backoff::new();
-
     loop {
       let Some(((prev_node_val, prev_node), (next_node_val, next_node))) =
-        self.find_prev_and_next(size, |val, next_node_size| val <= next_node_size)
+        self.find_prev_and_next(size, |val, next_node_size| val <= next_node_size, backoff)
       else {
         return Err(Error::InsufficientSpace {
           requested: size,
@@ -2410,14 +7384,18 @@ impl Arena {
       let (prev_node_size, next_node_offset) = decode_segment_node(prev_node_val);
       if prev_node_size == REMOVED_SEGMENT_NODE {
         // the current node is marked as removed, wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
       let (next_node_size, next_next_node_offset) = decode_segment_node(next_node_val);
       if next_node_size == REMOVED_SEGMENT_NODE {
         // the current node is marked as removed, wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
@@ -2433,7 +7411,9 @@ impl Arena {
         .is_err()
       {
         // wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
@@ -2450,6 +7430,8 @@ impl Arena {
         Ordering::Relaxed,
       ) {
         Ok(_) => {
+          self.freelist_changed();
+
           #[cfg(feature = "tracing")]
           tracing::debug!(
             "allocate {} bytes at offset {} from segment",
@@ -2473,8 +7455,10 @@ impl Arena {
           let mut allocated = Meta::new(self.ptr as _, segment_node.ptr_offset, memory_size);
           allocated.ptr_offset = segment_node.data_offset;
           allocated.ptr_size = size;
-          unsafe {
-            allocated.clear(self);
+          if zero {
+            unsafe {
+              allocated.clear(self);
+            }
           }
           return Ok(allocated);
         }
@@ -2482,9 +7466,11 @@ impl Arena {
           let (node_size, _) = decode_segment_node(current);
           if node_size == REMOVED_SEGMENT_NODE {
             // the current node is marked as removed, wait other thread to make progress.
-            backoff.snooze();
-          } else {
-            backoff.spin();
+            if !backoff.snooze() {
+              return Err(self.contended(size));
+            }
+          } else if !backoff.spin() {
+            return Err(self.contended(size));
           }
         }
       }
@@ -2492,12 +7478,20 @@ impl Arena {
   }
 
   /// It is like a pop operation, we will always allocate from the largest segment.
-  fn alloc_slow_path_optimistic(&self, size: u32) -> Result<Meta, Error> {
+  fn alloc_slow_path_optimistic_with(
+    &self,
+    size: u32,
+    zero: bool,
+    backoff: &mut SlowPathBackoff,
+  ) -> Result<Meta, Error> {
+    #[cfg(feature = "metrics")]
+    self.count_slow_path_attempt();
+    backoff.restart();
+
     if self.ro {
       return Err(Error::ReadOnly);
     }
 
-    let backoff = Backoff::new();
     let header = self.header();
 
     loop {
@@ -2516,21 +7510,38 @@ impl Arena {
 
       if head_node_offset == REMOVED_SEGMENT_NODE {
         // the head node is marked as removed, wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
-      let head = self.get_segment_node(head_node_offset);
+      let Some(head) = self.get_segment_node(head_node_offset) else {
+        return Err(Error::InsufficientSpace {
+          requested: size,
+          available: self.remaining() as u32,
+        });
+      };
       let head_node_size_and_next_node_offset = head.load(Ordering::Acquire);
       let (head_node_size, next_node_offset) =
         decode_segment_node(head_node_size_and_next_node_offset);
 
       if head_node_size == REMOVED_SEGMENT_NODE {
         // the head node is marked as removed, wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
+      // the segment is corrupted, never allocate from it.
+      if !self.segment_in_bounds(head_node_offset, head_node_size) {
+        return Err(Error::InsufficientSpace {
+          requested: size,
+          available: self.remaining() as u32,
+        });
+      }
+
       // The larget segment does not have enough space to allocate, so just return err.
       if size > head_node_size {
         return Err(Error::InsufficientSpace {
@@ -2546,7 +7557,9 @@ impl Arena {
 
       if head_node_size == REMOVED_SEGMENT_NODE {
         // the head node is marked as removed, wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
@@ -2562,7 +7575,9 @@ impl Arena {
         .is_err()
       {
         // wait other thread to make progress.
-        backoff.snooze();
+        if !backoff.snooze() {
+          return Err(self.contended(size));
+        }
         continue;
       }
 
@@ -2574,6 +7589,8 @@ impl Arena {
         Ordering::Relaxed,
       ) {
         Ok(_) => {
+          self.freelist_changed();
+
           #[cfg(feature = "tracing")]
           tracing::debug!(
             "allocate {} bytes at offset {} from segment",
@@ -2597,8 +7614,10 @@ impl Arena {
           let mut allocated = Meta::new(self.ptr as _, segment_node.ptr_offset, memory_size);
           allocated.ptr_offset = segment_node.data_offset;
           allocated.ptr_size = size;
-          unsafe {
-            allocated.clear(self);
+          if zero {
+            unsafe {
+              allocated.clear(self);
+            }
           }
           return Ok(allocated);
         }
@@ -2606,9 +7625,11 @@ impl Arena {
           let (node_size, _) = decode_segment_node(current);
           if node_size == REMOVED_SEGMENT_NODE {
             // The current head is removed from the list, wait other thread to make progress.
-            backoff.snooze();
-          } else {
-            backoff.spin();
+            if !backoff.snooze() {
+              return Err(self.contended(size));
+            }
+          } else if !backoff.spin() {
+            return Err(self.contended(size));
           }
         }
       }
@@ -2636,7 +7657,9 @@ impl Arena {
         continue;
       }
 
-      let head = self.get_segment_node(head_node_offset);
+      let Some(head) = self.get_segment_node(head_node_offset) else {
+        return discarded;
+      };
       let head_node_size_and_next_node_offset = head.load(Ordering::Acquire);
       let (head_node_size, next_node_offset) =
         decode_segment_node(head_node_size_and_next_node_offset);
@@ -2681,7 +7704,11 @@ impl Arena {
       ) {
         Ok(_) => {
           // incresase the discarded memory.
-          self.increase_discarded(segment_node.data_size);
+          self.discard(
+            segment_node.data_offset,
+            segment_node.data_size,
+            Discard::Manual,
+          );
           discarded += segment_node.data_size;
           continue;
         }
@@ -2705,19 +7732,15 @@ impl Arena {
       return false;
     }
 
-    let aligned_offset = align_offset::<AtomicU64>(offset) as usize;
-    let padding = aligned_offset - offset as usize;
-    let segmented_node_size = padding + SEGMENT_NODE_SIZE;
-    if segmented_node_size >= size as usize {
-      return false;
-    }
-
-    let available_bytes = size - segmented_node_size as u32;
-    if available_bytes < self.header().min_segment_size.load(Ordering::Acquire) {
-      return false;
+    // the data offset is aligned to the segment node, so the padding is zero for the segments
+    // at the start of the data section, or after the allocations of multiples of 8 bytes.
+    let node_end = align_offset::<AtomicU64>(offset) as u64 + SEGMENT_NODE_SIZE as u64;
+    match (offset as u64 + size as u64).checked_sub(node_end) {
+      Some(available) if available > 0 => {
+        available >= self.header().min_segment_size.load(Ordering::Acquire) as u64
+      }
+      _ => false,
     }
-
-    true
   }
 
   #[inline]
@@ -2730,13 +7753,13 @@ impl Arena {
     let padding = aligned_offset - offset as usize;
     let segmented_node_size = padding + SEGMENT_NODE_SIZE;
     if segmented_node_size >= size as usize {
-      self.increase_discarded(size);
+      self.discard(offset, size, Discard::Padding);
       return None;
     }
 
     let available_bytes = size - segmented_node_size as u32;
     if available_bytes < self.header().min_segment_size.load(Ordering::Acquire) {
-      self.increase_discarded(size);
+      self.discard(offset, size, Discard::SmallSegment);
       return None;
     }
 
@@ -2749,29 +7772,78 @@ impl Arena {
   }
 
   #[inline]
-  fn get_segment_node(&self, offset: u32) -> &AtomicU64 {
+  fn get_segment_node(&self, offset: u32) -> Option<&AtomicU64> {
+    // the offset comes from the freelist, which may be corrupted, e.g. a damaged file,
+    // so check it before dereferencing.
+    let offset = offset as usize;
+    if offset < self.data_offset as usize
+      || offset % mem::align_of::<AtomicU64>() != 0
+      || offset + SEGMENT_NODE_SIZE > self.cap as usize
+    {
+      #[cfg(feature = "tracing")]
+      tracing::error!(
+        "segment node at offset {offset} is out of bounds, the freelist is corrupted"
+      );
+      return None;
+    }
+
     // Safety: the offset is in bounds and well aligned.
     unsafe {
-      let ptr = self.ptr.add(offset as usize);
-      &*(ptr as *const _)
+      let ptr = self.ptr.add(offset);
+      Some(&*(ptr as *const _))
     }
   }
 
+  /// Returns `true` if the segment at `offset` with `data_size` bytes is in bounds of the ARENA.
+  #[inline]
+  fn segment_in_bounds(&self, offset: u32, data_size: u32) -> bool {
+    offset as u64 + SEGMENT_NODE_SIZE as u64 + data_size as u64 <= self.cap as u64
+  }
+
   #[inline]
-  fn new_in(memory: Memory, max_retries: u8, unify: bool, ro: bool) -> Self {
+  fn new_in(memory: Memory, opts: ArenaOptions, ro: bool) -> Self {
     let ptr = memory.as_mut_ptr();
 
     Self {
       freelist: memory.freelist,
       cap: memory.cap(),
-      unify,
+      unify: memory.unify,
       magic_version: memory.magic_version,
       version: memory.version,
       ptr,
       ro,
-      max_retries,
+      max_retries: opts.maximum_retries(),
+      zero_on_free: opts.zero_on_free(),
+      seqlock: opts.seqlock(),
+      compaction_threshold: opts.compaction_threshold(),
+      on_oom: None,
+      on_high_water: None,
+      on_event: None,
+      #[cfg(feature = "trace")]
+      tracer: (opts.trace_capacity() > 0)
+        .then(|| std::sync::Arc::new(Tracer::new(opts.trace_capacity()))),
+      #[cfg(feature = "std")]
+      parker: opts
+        .park_on_contention()
+        .then(|| std::sync::Arc::new(Parker::default())),
+      serial: opts
+        .deterministic()
+        .then(|| std::sync::Arc::new(SerialLock::default())),
+      // only a writable file backed memory map needs to be flushed.
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      auto_flush: opts
+        .auto_flush_interval()
+        .filter(|_| !ro && matches!(memory.backend_kind(), BackendKind::MmapMut))
+        .map(|interval| {
+          std::sync::Arc::new(AutoFlush {
+            interval,
+            allocations: AtomicU32::new(0),
+          })
+        }),
       data_offset: memory.data_offset as u32,
       inner: unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(memory)) as _) },
+      partition_header: None,
+      id: next_arena_id(),
     }
   }
 
@@ -2782,6 +7854,25 @@ impl Arena {
     size + align - 1
   }
 
+  #[cfg(test)]
+  #[allow(dead_code)]
+  pub(super) fn segment_list_len(&self) -> usize {
+    let mut len = 0;
+    let (_, mut next_node_offset) =
+      decode_segment_node(self.header().sentinel.load(Ordering::Acquire));
+    while next_node_offset != SENTINEL_SEGMENT_NODE_OFFSET {
+      len += 1;
+      next_node_offset = decode_segment_node(
+        self
+          .get_segment_node(next_node_offset)
+          .unwrap()
+          .load(Ordering::Acquire),
+      )
+      .1;
+    }
+    len
+  }
+
   #[cfg(test)]
   #[cfg(feature = "std")]
   #[allow(dead_code)]
@@ -2798,7 +7889,7 @@ impl Arena {
           break;
         }
 
-        current = self.get_segment_node(next_node_offset);
+        current = self.get_segment_node(next_node_offset).unwrap();
         continue;
       }
 
@@ -2807,7 +7898,7 @@ impl Arena {
       if next_node_offset == SENTINEL_SEGMENT_NODE_OFFSET {
         break;
       }
-      current = self.get_segment_node(next_node_offset);
+      current = self.get_segment_node(next_node_offset).unwrap();
     }
   }
 }
@@ -2853,7 +7944,7 @@ impl Drop for Arena {
   }
 }
 
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg(feature = "memmap")]
 #[inline]
 fn invalid_data<E: std::error::Error + Send + Sync + 'static>(e: E) -> std::io::Error {
   std::io::Error::new(std::io::ErrorKind::InvalidData, e)
@@ -2865,12 +7956,27 @@ fn bad_magic() -> std::io::Error {
   std::io::Error::new(std::io::ErrorKind::InvalidData, "arena has bad magic")
 }
 
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[inline]
+fn bad_byte_order() -> std::io::Error {
+  std::io::Error::new(
+    std::io::ErrorKind::InvalidData,
+    "arena was created on a machine with a different byte order",
+  )
+}
+
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 #[inline]
 fn bad_freelist() -> std::io::Error {
   std::io::Error::new(std::io::ErrorKind::InvalidData, "freelist mismatch")
 }
 
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[inline]
+fn bad_data_offset() -> std::io::Error {
+  std::io::Error::new(std::io::ErrorKind::InvalidData, "arena has bad data offset")
+}
+
 #[inline]
 const fn decode_segment_node(val: u64) -> (u32, u32) {
   ((val >> 32) as u32, val as u32)
@@ -2924,6 +8030,15 @@ const fn align_offset<T>(current_offset: u32) -> u32 {
   (current_offset + alignment - 1) & !(alignment - 1)
 }
 
+/// Returns the smallest offset not less than `current_offset`, whose address from `base` is
+/// a multiple of `alignment`, which must be a power of two.
+#[inline]
+fn align_address(base: *const u8, current_offset: u32, alignment: u32) -> u64 {
+  let addr = base as u64 + current_offset as u64;
+  let alignment = alignment as u64;
+  ((addr + alignment - 1) & !(alignment - 1)) - base as u64
+}
+
 #[inline(never)]
 #[cold]
 fn abort() -> ! {
@@ -3402,5 +8517,45 @@ pub use bytes::*;
 mod object;
 pub use object::*;
 
+mod sharded;
+pub use sharded::*;
+
+mod spill;
+pub use spill::*;
+
+mod compact;
+pub use compact::*;
+
+mod stack;
+pub use stack::*;
+
+mod inline_bump;
+pub use inline_bump::*;
+
+mod exclusive;
+pub use exclusive::*;
+
+mod view;
+pub use view::*;
+
+mod offset;
+pub use offset::*;
+
+mod serial;
+use serial::{SerialGuard, SerialLock};
+
+mod rcu;
+pub use rcu::*;
+
+#[cfg(feature = "std")]
+mod park;
+#[cfg(feature = "std")]
+use park::Parker;
+
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "trace")]
+pub use trace::*;
+
 #[cfg(test)]
 mod tests;
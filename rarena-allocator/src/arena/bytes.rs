@@ -2,6 +2,61 @@ use either::Either;
 
 use super::*;
 
+/// A token identifies an allocated region of the ARENA, which can be stored in an external index
+/// and resolved by [`Arena::resolve_token`] later.
+///
+/// The offset and the capacity of the region are packed into a single `u64`,
+/// with the same encoding as the segment nodes of the freelist.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct AllocToken(u64);
+
+impl AllocToken {
+  /// Creates a new token from the offset and the capacity of an allocated region.
+  #[inline]
+  pub const fn new(offset: u32, capacity: u32) -> Self {
+    Self(encode_segment_node(capacity, offset))
+  }
+
+  /// Returns the offset of the region.
+  #[inline]
+  pub const fn offset(&self) -> usize {
+    decode_segment_node(self.0).1 as usize
+  }
+
+  /// Returns the capacity of the region.
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    decode_segment_node(self.0).0 as usize
+  }
+
+  /// Returns the raw representation of the token.
+  #[inline]
+  pub const fn as_u64(&self) -> u64 {
+    self.0
+  }
+
+  /// Creates a token from its raw representation.
+  #[inline]
+  pub const fn from_u64(raw: u64) -> Self {
+    Self(raw)
+  }
+}
+
+impl From<u64> for AllocToken {
+  #[inline]
+  fn from(raw: u64) -> Self {
+    Self(raw)
+  }
+}
+
+impl From<AllocToken> for u64 {
+  #[inline]
+  fn from(token: AllocToken) -> Self {
+    token.0
+  }
+}
+
 /// A owned buffer that allocated by the ARENA
 pub struct BytesMut {
   arena: Either<Arena, NonNull<u8>>,
@@ -92,6 +147,12 @@ impl BytesMut {
     self.allocated.memory_size as usize
   }
 
+  /// Returns a token which can be resolved by [`Arena::resolve_token`] to the buffer later.
+  #[inline]
+  pub const fn token(&self) -> AllocToken {
+    AllocToken::new(self.allocated.ptr_offset, self.allocated.ptr_size)
+  }
+
   /// Detach the buffer from the ARENA, and the buffer will not be collected by ARENA when dropped,
   /// which means the space used by the buffer will never be reclaimed.
   #[inline]
@@ -163,11 +224,17 @@ impl Drop for BytesMut {
 }
 
 /// A buffer that allocated by the ARENA
+///
+/// Only the first [`len`](Self::len) bytes are exposed, the remaining bytes up to the
+/// [`capacity`](Self::capacity) are zeroed if allocated by [`Arena::alloc_bytes`], but may be
+/// uninitialized if allocated by [`Arena::alloc_bytes_uninit`].
 pub struct BytesRefMut<'a> {
   arena: &'a Arena,
   len: usize,
   pub(super) allocated: Meta,
   pub(super) detach: bool,
+  /// The [`generation`](Arena::generation) of the ARENA when the buffer is allocated.
+  generation: u32,
 }
 
 impl<'a> ops::Deref for BytesRefMut<'a> {
@@ -239,18 +306,58 @@ impl<'a> BytesRefMut<'a> {
     self.allocated.ptr_offset as usize
   }
 
+  /// Returns the offset to the pointer of the ARENA as a [`ByteOffset`].
+  #[inline]
+  pub const fn byte_offset(&self) -> ByteOffset {
+    ByteOffset::new(self.allocated.ptr_offset)
+  }
+
   /// Returns the offset to the pointer of the ARENA. Including the padding.
   #[inline]
   pub const fn memory_offset(&self) -> usize {
     self.allocated.memory_offset as usize
   }
 
+  /// Returns the [`id`](Arena::id) of the ARENA which the buffer is allocated by.
+  #[inline]
+  pub const fn arena_id(&self) -> u64 {
+    self.arena.id
+  }
+
   /// Returns how many bytes of memory the value occupies. Including the padding.
   #[inline]
   pub const fn memory_capacity(&self) -> usize {
     self.allocated.memory_size as usize
   }
 
+  /// Returns a token which can be resolved by [`Arena::resolve_token`] to the buffer later.
+  #[inline]
+  pub const fn token(&self) -> AllocToken {
+    AllocToken::new(self.allocated.ptr_offset, self.allocated.ptr_size)
+  }
+
+  /// Returns `false` if the ARENA is cleared after the buffer is allocated, see [`Arena::generation`].
+  ///
+  /// This does not make [`Arena::clear`] safe, but makes the stale buffers detectable,
+  /// a stale buffer is not given back to the ARENA when dropped.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let bytes = arena.alloc_bytes(8).unwrap();
+  /// assert!(bytes.is_valid());
+  ///
+  /// unsafe { arena.clear().unwrap() };
+  /// assert!(!bytes.is_valid());
+  /// ```
+  #[inline]
+  pub fn is_valid(&self) -> bool {
+    self.generation == self.arena.generation()
+  }
+
   /// Returns the length of the buffer.
   #[inline]
   pub const fn len(&self) -> usize {
@@ -270,6 +377,86 @@ impl<'a> BytesRefMut<'a> {
     self.detach = true;
   }
 
+  /// Marks the whole capacity of the buffer as initialized, sets the length of the buffer to its capacity.
+  ///
+  /// # Safety
+  /// - All the bytes in `[0, capacity)` must be initialized, e.g. written through [`as_mut_ptr`](Self::as_mut_ptr),
+  ///   the buffer allocated by [`Arena::alloc_bytes_uninit`] may contain stale bytes.
+  #[inline]
+  pub unsafe fn assume_init(&mut self) {
+    self.len = self.capacity();
+  }
+
+  /// Splits the buffer into two at the given index, without any new allocation.
+  ///
+  /// The first buffer covers `[0, mid)` and the second buffer covers `[mid, capacity)` of the original buffer.
+  /// The two buffers own disjoint parts of the original memory, and each of them gives its own part back
+  /// to the ARENA when dropped, so the original memory is freed exactly once.
+  ///
+  /// # Panics
+  /// - If `mid > capacity`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let bytes = arena.alloc_bytes(64).unwrap();
+  /// let (left, right) = bytes.split_at(32);
+  /// assert_eq!(left.capacity(), 32);
+  /// assert_eq!(right.capacity(), 32);
+  /// assert_eq!(left.offset() + 32, right.offset());
+  /// ```
+  pub fn split_at(mut self, mid: u32) -> (Self, Self) {
+    assert!(
+      mid as usize <= self.capacity(),
+      "mid > capacity ({} > {})",
+      mid,
+      self.capacity()
+    );
+
+    let arena = self.arena;
+    let detach = self.detach;
+    let len = self.len;
+    let allocated = self.allocated;
+    let generation = self.generation;
+    // the memory is handed over to the two new buffers.
+    self.detach = true;
+
+    if allocated.ptr_size == 0 {
+      return (Self::null(arena), Self::null(arena));
+    }
+
+    let split = allocated.ptr_offset + mid;
+    let mut left = allocated;
+    left.memory_size = split - allocated.memory_offset;
+    left.ptr_size = mid;
+
+    let mut right = allocated;
+    right.memory_offset = split;
+    right.memory_size = allocated.memory_offset + allocated.memory_size - split;
+    right.ptr_offset = split;
+    right.ptr_size = allocated.ptr_size - mid;
+
+    (
+      Self {
+        arena,
+        len: len.min(mid as usize),
+        allocated: left,
+        detach,
+        generation,
+      },
+      Self {
+        arena,
+        len: len.saturating_sub(mid as usize),
+        allocated: right,
+        detach,
+        generation,
+      },
+    )
+  }
+
   /// Returns the pointer to the buffer.
   #[inline]
   pub fn as_mut_ptr(&self) -> *mut u8 {
@@ -292,16 +479,18 @@ impl<'a> BytesRefMut<'a> {
       len: 0,
       allocated,
       detach: false,
+      generation: arena.generation(),
     }
   }
 
   #[inline]
-  pub(super) const fn null(arena: &'a Arena) -> Self {
+  pub(super) fn null(arena: &'a Arena) -> Self {
     Self {
       allocated: Meta::null(arena.ptr as _),
       arena,
       len: 0,
       detach: false,
+      generation: arena.generation(),
     }
   }
 
@@ -345,15 +534,56 @@ impl<'a> BytesRefMut<'a> {
 impl<'a> Drop for BytesRefMut<'a> {
   #[inline]
   fn drop(&mut self) {
-    if self.detach {
-      return;
+    // the memory of a stale buffer is reclaimed by the clear.
+    if !self.detach && self.is_valid() {
+      // SAFETY: offset and offset + size are inbounds of the ARENA.
+      unsafe {
+        self
+          .arena
+          .dealloc(self.allocated.memory_offset, self.allocated.memory_size);
+      }
     }
+  }
+}
 
-    // SAFETY: offset and offset + size are inbounds of the ARENA.
-    unsafe {
-      self
-        .arena
-        .dealloc(self.allocated.memory_offset, self.allocated.memory_size);
-    }
+/// A region reserved by [`Arena::reserve_segment`], which pins the space for a later write.
+///
+/// The region is redeemed by [`take`](Self::take) exactly once, if the reservation is dropped
+/// without being taken, the region is freed as a normal buffer.
+pub struct ReservedSegment<'a> {
+  bytes: BytesRefMut<'a>,
+}
+
+impl core::fmt::Debug for ReservedSegment<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("ReservedSegment")
+      .field("offset", &self.bytes.offset())
+      .field("capacity", &self.bytes.capacity())
+      .finish()
+  }
+}
+
+impl<'a> ReservedSegment<'a> {
+  #[inline]
+  pub(super) const fn new(bytes: BytesRefMut<'a>) -> Self {
+    Self { bytes }
+  }
+
+  /// Returns the offset of the reserved region to the pointer of the ARENA.
+  #[inline]
+  pub const fn offset(&self) -> usize {
+    self.bytes.offset()
+  }
+
+  /// Returns the capacity of the reserved region.
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    self.bytes.capacity()
+  }
+
+  /// Redeems the reservation, returns the reserved region as a buffer.
+  #[inline]
+  pub fn take(self) -> BytesRefMut<'a> {
+    self.bytes
   }
 }
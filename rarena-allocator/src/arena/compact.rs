@@ -0,0 +1,97 @@
+use std::vec::Vec;
+
+/// A live region moved by [`Arena::compact_into`](super::Arena::compact_into).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MovedRegion {
+  old: u32,
+  new: u32,
+  size: u32,
+}
+
+impl MovedRegion {
+  #[inline]
+  pub(super) const fn new(old: u32, new: u32, size: u32) -> Self {
+    Self { old, new, size }
+  }
+
+  /// Returns the offset of the region in the source ARENA.
+  #[inline]
+  pub const fn old_offset(&self) -> u32 {
+    self.old
+  }
+
+  /// Returns the offset of the region in the destination ARENA.
+  #[inline]
+  pub const fn new_offset(&self) -> u32 {
+    self.new
+  }
+
+  /// Returns the size of the region.
+  #[inline]
+  pub const fn size(&self) -> u32 {
+    self.size
+  }
+}
+
+/// The map from the offsets of the source ARENA to the offsets of the destination ARENA,
+/// returned by [`Arena::compact_into`](super::Arena::compact_into).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct OffsetMap {
+  regions: Vec<MovedRegion>,
+}
+
+impl OffsetMap {
+  #[inline]
+  pub(super) fn push(&mut self, region: MovedRegion) {
+    self.regions.push(region);
+  }
+
+  /// Returns the new offset of the given old offset, or `None` if the old offset is not in a live region.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(8).unwrap();
+  /// b.detach();
+  ///
+  /// let dst = Arena::new(ArenaOptions::new());
+  /// let map = arena.compact_into(&dst, |_, _| {}).unwrap();
+  /// assert!(map.get(b.offset() as u32).is_some());
+  /// ```
+  pub fn get(&self, old: u32) -> Option<u32> {
+    // the regions are sorted by the old offsets.
+    let idx = match self.regions.binary_search_by_key(&old, |r| r.old) {
+      Ok(idx) => idx,
+      Err(0) => return None,
+      Err(idx) => idx - 1,
+    };
+
+    let region = &self.regions[idx];
+    if old < region.old + region.size {
+      Some(region.new + (old - region.old))
+    } else {
+      None
+    }
+  }
+
+  /// Returns the moved regions, sorted by their old offsets.
+  #[inline]
+  pub fn regions(&self) -> &[MovedRegion] {
+    &self.regions
+  }
+
+  /// Returns the number of the moved regions.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.regions.len()
+  }
+
+  /// Returns `true` if no region is moved.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.regions.is_empty()
+  }
+}
@@ -0,0 +1,114 @@
+use super::*;
+
+/// A guard of the exclusive access to an [`Arena`], returned by [`Arena::try_exclusive`].
+///
+/// The guard mutably borrows the ARENA, which is not shared by any clones, so no handles borrowing
+/// the ARENA (e.g. a [`BytesRefMut`] or a [`RefMut`]) can be alive, and the operations which require
+/// the exclusivity are exposed safely. The operations take `&mut self`, so the handles allocated
+/// through [`arena`](Self::arena) must be dropped before them.
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions};
+///
+/// let mut arena = Arena::new(ArenaOptions::new());
+/// let cloned = arena.clone();
+/// assert!(arena.try_exclusive().is_none());
+///
+/// drop(cloned);
+/// let mut guard = arena.try_exclusive().unwrap();
+/// guard.clear().unwrap();
+/// ```
+pub struct ExclusiveGuard<'a> {
+  arena: &'a mut Arena,
+}
+
+impl core::fmt::Debug for ExclusiveGuard<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("ExclusiveGuard")
+      .field("arena", &self.arena.id())
+      .finish()
+  }
+}
+
+impl<'a> ExclusiveGuard<'a> {
+  #[inline]
+  pub(super) fn new(arena: &'a mut Arena) -> Option<Self> {
+    if arena.refs() != 1 {
+      return None;
+    }
+
+    Some(Self { arena })
+  }
+
+  /// Returns the locked ARENA.
+  #[inline]
+  pub fn arena(&self) -> &Arena {
+    self.arena
+  }
+
+  /// Clears the ARENA, see [`Arena::clear`].
+  ///
+  /// Returns [`Error::Locked`] if the ARENA has been cloned after the guard is acquired.
+  #[inline]
+  pub fn clear(&mut self) -> Result<(), Error> {
+    self.check()?;
+    // Safety: there are no live handles, and no other ARENAs share the memory.
+    unsafe { self.arena.clear() }
+  }
+
+  /// Resets the freelist and rebuilds it from the given `(offset, size)` segments,
+  /// see [`Arena::rebuild_free_list`].
+  ///
+  /// Returns [`Error::Locked`] if the ARENA has been cloned after the guard is acquired.
+  ///
+  /// # Safety
+  /// - The segments must not be used by any allocated buffers, e.g. the detached ones.
+  #[inline]
+  pub unsafe fn rebuild_free_list(&mut self, segments: &[(u32, u32)]) -> Result<(), Error> {
+    self.check()?;
+    self.arena.rebuild_free_list(segments)
+  }
+
+  /// Removes the segments smaller than `min_keep` bytes from the freelist,
+  /// see [`Arena::prune_free_list`].
+  ///
+  /// Returns [`Error::Locked`] if the ARENA has been cloned after the guard is acquired.
+  #[inline]
+  pub fn prune_free_list(&mut self, min_keep: u32) -> Result<usize, Error> {
+    self.check()?;
+    // Safety: there are no live handles, and no other ARENAs share the memory.
+    unsafe { self.arena.prune_free_list(min_keep) }
+  }
+
+  /// Merges the physically adjacent segments in the freelist, see [`Arena::consolidate`].
+  ///
+  /// Returns [`Error::Locked`] if the ARENA has been cloned after the guard is acquired.
+  #[inline]
+  pub fn consolidate(&mut self) -> Result<usize, Error> {
+    self.check()?;
+    // Safety: there are no live handles, and no other ARENAs share the memory.
+    unsafe { self.arena.consolidate() }
+  }
+
+  /// Lowers the watermark over the free segments which end exactly at it, see [`Arena::reclaim_tail`].
+  ///
+  /// Returns [`Error::Locked`] if the ARENA has been cloned after the guard is acquired.
+  #[inline]
+  pub fn reclaim_tail(&mut self) -> Result<usize, Error> {
+    self.check()?;
+    // Safety: there are no live handles, and no other ARENAs share the memory.
+    unsafe { self.arena.reclaim_tail() }
+  }
+
+  /// The ARENA may be cloned through [`arena`](Self::arena) after the guard is acquired,
+  /// the clones do not respect the guard.
+  #[inline]
+  fn check(&self) -> Result<(), Error> {
+    if self.arena.refs() != 1 {
+      return Err(Error::Locked);
+    }
+    Ok(())
+  }
+}
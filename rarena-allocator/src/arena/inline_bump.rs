@@ -0,0 +1,130 @@
+use core::cell::UnsafeCell;
+
+use super::*;
+
+/// The inline storage of an [`InlineBumpArena`], aligned to `8` bytes, the same as the data offset of an [`Arena`].
+#[repr(C, align(8))]
+struct AlignedArray<const N: usize>([u8; N]);
+
+/// A fixed-capacity bump ARENA which owns its `N` bytes inline, e.g. on the stack or in a `static`,
+/// so its memory is never allocated from the heap.
+///
+/// This is only a bump allocator handing out `&mut [u8]`, it is not an [`Arena`]: there is no header,
+/// no freelist, and no handles like [`BytesRefMut`], the bytes are only reclaimed all at once
+/// by [`reset`](Self::reset). The allocations are lock-free and never overlap, so an `InlineBumpArena`
+/// can be shared by the threads. Like the rest of the crate, it still requires the `alloc` feature.
+///
+/// `N` must not be greater than `u32::MAX`.
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Error, InlineBumpArena};
+///
+/// let arena = InlineBumpArena::<64>::new();
+/// let buf = arena.alloc_bytes(60).unwrap();
+/// buf.copy_from_slice(&[1; 60]);
+///
+/// assert!(matches!(arena.alloc_bytes(8), Err(Error::InsufficientSpace { requested: 8, available: 4 })));
+/// ```
+pub struct InlineBumpArena<const N: usize> {
+  buf: UnsafeCell<AlignedArray<N>>,
+  allocated: AtomicU32,
+}
+
+// Safety: the allocated regions never overlap, and each of them is handed out only once.
+unsafe impl<const N: usize> Sync for InlineBumpArena<N> {}
+
+impl<const N: usize> core::fmt::Debug for InlineBumpArena<N> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("InlineBumpArena")
+      .field("capacity", &N)
+      .field("allocated", &self.allocated())
+      .finish()
+  }
+}
+
+impl<const N: usize> Default for InlineBumpArena<N> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const N: usize> InlineBumpArena<N> {
+  /// Creates a new empty ARENA, the bytes are zeroed.
+  ///
+  /// # Panics
+  /// - If `N` is greater than `u32::MAX`.
+  #[inline]
+  pub fn new() -> Self {
+    assert!(N <= u32::MAX as usize, "the capacity must fit in u32");
+
+    Self {
+      buf: UnsafeCell::new(AlignedArray([0; N])),
+      allocated: AtomicU32::new(0),
+    }
+  }
+
+  /// Returns the capacity of the ARENA, which is `N`.
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    N
+  }
+
+  /// Returns the number of bytes allocated.
+  #[inline]
+  pub fn allocated(&self) -> usize {
+    self.allocated.load(Ordering::Acquire) as usize
+  }
+
+  /// Returns the number of bytes which can still be allocated.
+  #[inline]
+  pub fn remaining(&self) -> usize {
+    N - self.allocated()
+  }
+
+  /// Allocates `size` bytes in the ARENA, the bytes are zeroed unless they were allocated and written
+  /// before the last [`reset`](Self::reset).
+  ///
+  /// Returns [`Error::InsufficientSpace`] if there are not enough bytes left.
+  #[allow(clippy::mut_from_ref)]
+  pub fn alloc_bytes(&self, size: u32) -> Result<&mut [u8], Error> {
+    let mut allocated = self.allocated.load(Ordering::Acquire);
+    loop {
+      let want = allocated as u64 + size as u64;
+      if want > N as u64 {
+        return Err(Error::InsufficientSpace {
+          requested: size,
+          available: N as u32 - allocated,
+        });
+      }
+
+      match self.allocated.compare_exchange_weak(
+        allocated,
+        want as u32,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+      ) {
+        // Safety: the region is in bounds, and it is handed out only once until the ARENA is reset,
+        // which requires the exclusive access.
+        Ok(offset) => unsafe {
+          let ptr = self.buf.get().cast::<u8>().add(offset as usize);
+          return Ok(slice::from_raw_parts_mut(ptr, size as usize));
+        },
+        Err(current) => allocated = current,
+      }
+    }
+  }
+
+  /// Resets the ARENA, so that all the bytes can be allocated again.
+  ///
+  /// The bytes are zeroed if `zero` is `true`.
+  #[inline]
+  pub fn reset(&mut self, zero: bool) {
+    if zero {
+      self.buf.get_mut().0.fill(0);
+    }
+    self.allocated.store(0, Ordering::Release);
+  }
+}
@@ -27,6 +27,8 @@ pub struct Owned<T> {
   kind: Kind<T>,
   arena: Arena,
   detached: bool,
+  /// Only set by `write`, so the drop glue never runs on an uninitialized value.
+  initialized: bool,
   pub(super) allocated: Meta,
 }
 
@@ -41,9 +43,21 @@ impl<T> Owned<T> {
     self.detached = true;
   }
 
+  /// Marks the value as initialized, so that it will be dropped when the handle is dropped.
+  ///
+  /// Only needed if the value is written through [`as_mut_ptr`](Self::as_mut_ptr) instead of `write`.
+  ///
+  /// # Safety
+  /// - The value must be initialized.
+  #[inline]
+  pub unsafe fn assume_init(&mut self) {
+    self.initialized = true;
+  }
+
   /// Write a value to the `RefMut`.
   #[inline]
   pub fn write(&mut self, value: T) {
+    self.initialized = true;
     match &mut self.kind {
       Kind::Slot(slot) => unsafe {
         slot.as_mut_ptr().write(value);
@@ -135,7 +149,7 @@ impl<T> Drop for Owned<T> {
       Kind::Slot(slot) => {
         if !self.detached {
           unsafe {
-            if mem::needs_drop::<T>() {
+            if mem::needs_drop::<T>() && self.initialized {
               let ptr = slot.as_mut_ptr();
               if !ptr.is_null() {
                 ptr::drop_in_place(ptr);
@@ -172,6 +186,8 @@ pub struct RefMut<'a, T> {
   kind: Kind<T>,
   arena: &'a Arena,
   detached: bool,
+  /// Only set by `write`, so the drop glue never runs on an uninitialized value.
+  initialized: bool,
   pub(super) allocated: Meta,
 }
 
@@ -186,9 +202,21 @@ impl<'a, T> RefMut<'a, T> {
     self.detached = true;
   }
 
+  /// Marks the value as initialized, so that it will be dropped when the handle is dropped.
+  ///
+  /// Only needed if the value is written through [`as_mut_ptr`](Self::as_mut_ptr) instead of `write`.
+  ///
+  /// # Safety
+  /// - The value must be initialized.
+  #[inline]
+  pub unsafe fn assume_init(&mut self) {
+    self.initialized = true;
+  }
+
   /// Write a value to the `RefMut`.
   #[inline]
   pub fn write(&mut self, value: T) {
+    self.initialized = true;
     match &mut self.kind {
       Kind::Slot(slot) => unsafe {
         slot.as_mut_ptr().write(value);
@@ -232,6 +260,12 @@ impl<'a, T> RefMut<'a, T> {
     self.allocated.memory_offset as usize
   }
 
+  /// Returns the [`id`](Arena::id) of the ARENA which the value is allocated by.
+  #[inline]
+  pub const fn arena_id(&self) -> u64 {
+    self.arena.id
+  }
+
   /// Returns a shared reference to the value.
   ///
   /// # Safety
@@ -273,12 +307,111 @@ impl<'a, T> RefMut<'a, T> {
     }
   }
 
+  /// Returns the raw bytes of the value in the ARENA, the length of the bytes is `size_of::<T>()`.
+  ///
+  /// # Safety
+  /// - The value must be initialized.
+  /// - `T` must be plain old data, which has no padding bytes, no pointers and
+  ///   every bit pattern of `T` is valid, e.g. a `#[repr(C)]` struct of integers without padding.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut value = unsafe { arena.alloc::<u32>().unwrap() };
+  /// value.write(0x01020304);
+  /// assert_eq!(unsafe { value.as_bytes() }, 0x01020304u32.to_ne_bytes());
+  /// ```
+  #[inline]
+  pub unsafe fn as_bytes(&self) -> &[u8]
+  where
+    T: Copy,
+  {
+    self.arena.get_bytes(
+      self.allocated.ptr_offset as usize,
+      self.allocated.ptr_size as usize,
+    )
+  }
+
+  /// Converts the typed handle into a [`BytesRefMut`] over the same memory, without freeing it.
+  ///
+  /// The length of the returned buffer is `size_of::<T>()`, and the memory is given back to
+  /// the ARENA when the returned buffer is dropped, unless it is detached.
+  ///
+  /// # Safety
+  /// - The same as [`as_bytes`](Self::as_bytes).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut value = unsafe { arena.alloc::<u32>().unwrap() };
+  /// value.write(0x01020304);
+  ///
+  /// let bytes = unsafe { value.into_bytes_handle() };
+  /// assert_eq!(&bytes[..], 0x01020304u32.to_ne_bytes());
+  /// ```
+  #[inline]
+  pub unsafe fn into_bytes_handle(mut self) -> BytesRefMut<'a>
+  where
+    T: Copy,
+  {
+    // `T: Copy` never needs drop, so the value is either inlined in the ARENA or a ZST.
+    match self.kind {
+      Kind::Dangling(_) => BytesRefMut::null(self.arena),
+      _ => {
+        self.detached = true;
+        let mut bytes = BytesRefMut::new(self.arena, self.allocated);
+        bytes.assume_init();
+        bytes
+      }
+    }
+  }
+
+  /// Leaks the handle into a [`LeakedRef`], which owns a clone of the ARENA, so it is not tied to
+  /// the lifetime of the borrowed ARENA, and keeps the memory alive until it is dropped.
+  /// The cloned ARENA is reachable by [`LeakedRef::arena`].
+  ///
+  /// # Panics
+  /// - If the value is not initialized by [`write`](Self::write) or [`assume_init`](Self::assume_init),
+  ///   and `T` is not a zero sized type. A [`LeakedRef`] derefs to `T` without `unsafe`, so it can
+  ///   never hold an uninitialized value.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new());
+  /// let mut value = unsafe { arena.alloc::<u64>().unwrap() };
+  /// value.write(42);
+  ///
+  /// let leaked = value.leak();
+  /// drop(arena);
+  /// assert_eq!(*leaked, 42);
+  /// ```
+  pub fn leak(mut self) -> LeakedRef<T> {
+    assert!(
+      self.initialized || matches!(self.kind, Kind::Dangling(_)),
+      "the value must be initialized before leaking"
+    );
+
+    LeakedRef {
+      owned: self.to_owned(),
+    }
+  }
+
   #[inline]
   pub(super) const fn new(slot: MaybeUninit<T>, allocated: Meta, arena: &'a Arena) -> Self {
     Self {
       kind: Kind::Slot(slot),
       arena,
       detached: false,
+      initialized: false,
       allocated,
     }
   }
@@ -289,6 +422,7 @@ impl<'a, T> RefMut<'a, T> {
       kind: Kind::Inline(value),
       arena,
       detached: false,
+      initialized: false,
       allocated,
     }
   }
@@ -300,6 +434,7 @@ impl<'a, T> RefMut<'a, T> {
       allocated: Meta::null(arena.ptr as _),
       arena,
       detached: false,
+      initialized: false,
     }
   }
 
@@ -312,6 +447,7 @@ impl<'a, T> RefMut<'a, T> {
       arena: self.arena.clone(),
       kind: mem::take(&mut self.kind),
       detached: false,
+      initialized: self.initialized,
       allocated: self.allocated,
     }
   }
@@ -323,7 +459,7 @@ impl<'a, T> Drop for RefMut<'a, T> {
       Kind::Slot(slot) => {
         if !self.detached {
           unsafe {
-            if mem::needs_drop::<T>() {
+            if mem::needs_drop::<T>() && self.initialized {
               let ptr = slot.as_mut_ptr();
               if !ptr.is_null() {
                 ptr::drop_in_place(ptr);
@@ -352,3 +488,222 @@ impl<'a, T> Drop for RefMut<'a, T> {
     }
   }
 }
+
+/// A mutable reference to a slice of `T`s in the ARENA, returned by [`Arena::alloc_uninit_slice`].
+///
+/// The elements are uninitialized until [`assume_init`](Self::assume_init) is called,
+/// after that, the elements are dropped when the handle is dropped.
+#[must_use = "The elements are uninitialized, and must be initialized before they are used."]
+pub struct SliceRefMut<'a, T> {
+  ptr: NonNull<T>,
+  len: usize,
+  arena: &'a Arena,
+  detached: bool,
+  initialized: bool,
+  pub(super) allocated: Meta,
+}
+
+impl<T> core::fmt::Debug for SliceRefMut<'_, T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("SliceRefMut")
+      .field("offset", &self.offset())
+      .field("len", &self.len)
+      .field("initialized", &self.initialized)
+      .finish()
+  }
+}
+
+impl<'a, T> SliceRefMut<'a, T> {
+  /// Detach the slice from the ARENA, which means when the slice is dropped,
+  /// the underlying memory will not be collected for futhur allocation.
+  ///
+  /// # Safety
+  /// - If the elements are initialized and `T` needs drop, then users should take care of dropping them by themselves.
+  #[inline]
+  pub unsafe fn detach(&mut self) {
+    self.detached = true;
+  }
+
+  /// Marks all the elements as initialized, so that they can be accessed by [`as_slice`](Self::as_slice),
+  /// and will be dropped when the handle is dropped.
+  ///
+  /// # Safety
+  /// - All the elements must be initialized, e.g. by [`as_uninit_slice_mut`](Self::as_uninit_slice_mut).
+  #[inline]
+  pub unsafe fn assume_init(&mut self) {
+    self.initialized = true;
+  }
+
+  /// Returns the number of the elements.
+  #[inline]
+  pub const fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the slice has no elements.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns `true` if the elements are marked as initialized by [`assume_init`](Self::assume_init).
+  #[inline]
+  pub const fn is_initialized(&self) -> bool {
+    self.initialized
+  }
+
+  /// Returns the offset of the first element to the pointer of the ARENA.
+  ///
+  /// If this value is `0`, then no memory is allocated, e.g. `T` is ZST (zero sized type) or the slice is empty.
+  #[inline]
+  pub const fn offset(&self) -> usize {
+    self.allocated.ptr_offset as usize
+  }
+
+  /// Returns how many bytes of memory the slice occupies.
+  ///
+  /// If this value is `0`, then no memory is allocated, e.g. `T` is ZST (zero sized type) or the slice is empty.
+  #[inline]
+  pub const fn memory_size(&self) -> usize {
+    self.allocated.memory_size as usize
+  }
+
+  /// Returns the offset to the pointer of the ARENA.
+  ///
+  /// If this value is `0`, then no memory is allocated, e.g. `T` is ZST (zero sized type) or the slice is empty.
+  #[inline]
+  pub const fn memory_offset(&self) -> usize {
+    self.allocated.memory_offset as usize
+  }
+
+  /// Returns the [`id`](Arena::id) of the ARENA which the slice is allocated by.
+  #[inline]
+  pub const fn arena_id(&self) -> u64 {
+    self.arena.id
+  }
+
+  /// Returns the elements as a slice of [`MaybeUninit`], which can be written to.
+  #[inline]
+  pub fn as_uninit_slice_mut(&mut self) -> &mut [MaybeUninit<T>] {
+    // Safety: the pointer is well-aligned and valid for `len` elements, or dangling for the ZSTs and empty slices.
+    unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr().cast(), self.len) }
+  }
+
+  /// Returns the initialized elements.
+  ///
+  /// # Panics
+  /// - If the elements are not marked as initialized by [`assume_init`](Self::assume_init).
+  #[inline]
+  pub fn as_slice(&self) -> &[T] {
+    assert!(self.initialized, "the elements must be initialized");
+    // Safety: the elements are initialized.
+    unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+  }
+
+  /// Returns the initialized elements mutably.
+  ///
+  /// # Panics
+  /// - If the elements are not marked as initialized by [`assume_init`](Self::assume_init).
+  #[inline]
+  pub fn as_mut_slice(&mut self) -> &mut [T] {
+    assert!(self.initialized, "the elements must be initialized");
+    // Safety: the elements are initialized.
+    unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+  }
+
+  #[inline]
+  pub(super) const fn new(ptr: NonNull<T>, len: usize, allocated: Meta, arena: &'a Arena) -> Self {
+    Self {
+      ptr,
+      len,
+      arena,
+      detached: false,
+      initialized: false,
+      allocated,
+    }
+  }
+
+  #[inline]
+  pub(super) const fn new_dangling(arena: &'a Arena, len: usize) -> Self {
+    Self {
+      ptr: NonNull::dangling(),
+      len,
+      allocated: Meta::null(arena.ptr as _),
+      arena,
+      detached: false,
+      initialized: false,
+    }
+  }
+}
+
+impl<T> Drop for SliceRefMut<'_, T> {
+  fn drop(&mut self) {
+    if !self.detached {
+      if self.initialized && mem::needs_drop::<T>() {
+        // Safety: the elements are initialized.
+        unsafe {
+          ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len));
+        }
+      }
+
+      if self.allocated.memory_size != 0 {
+        // SAFETY: offset and offset + size are inbounds of the ARENA.
+        unsafe {
+          self
+            .arena
+            .dealloc(self.allocated.memory_offset, self.allocated.memory_size);
+        }
+      }
+    }
+  }
+}
+
+/// An initialized value `T` in the ARENA, which owns a clone of the ARENA, returned by [`RefMut::leak`].
+///
+/// The value is dropped, and the memory is given back to the ARENA, when the handle is dropped.
+#[derive(Debug)]
+pub struct LeakedRef<T> {
+  owned: Owned<T>,
+}
+
+impl<T> LeakedRef<T> {
+  /// Returns the ARENA which the value belongs to.
+  #[inline]
+  pub const fn arena(&self) -> &Arena {
+    &self.owned.arena
+  }
+
+  /// Returns the offset of `T` to the pointer of the ARENA.
+  ///
+  /// If this value is `0`, then the `T` is ZST (zero sized type).
+  #[inline]
+  pub const fn offset(&self) -> usize {
+    self.owned.offset()
+  }
+
+  /// Returns the offset to the pointer of the ARENA.
+  ///
+  /// If this value is `0`, then the `T` is ZST (zero sized type).
+  #[inline]
+  pub const fn memory_offset(&self) -> usize {
+    self.owned.memory_offset()
+  }
+}
+
+impl<T> ops::Deref for LeakedRef<T> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &Self::Target {
+    // Safety: the value is checked to be initialized when leaking.
+    unsafe { self.owned.as_ref() }
+  }
+}
+
+impl<T> ops::DerefMut for LeakedRef<T> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    // Safety: the value is checked to be initialized when leaking.
+    unsafe { self.owned.as_mut() }
+  }
+}
@@ -0,0 +1,147 @@
+use core::marker::PhantomData;
+
+/// A byte offset into the ARENA, which can not be confused with the index of an element,
+/// see [`TypedOffset`].
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions, ByteOffset};
+///
+/// let arena = Arena::new(ArenaOptions::new());
+/// let mut b = arena.alloc_bytes(5).unwrap();
+/// b.put_slice(b"hello").unwrap();
+///
+/// let offset: ByteOffset = b.byte_offset();
+/// assert_eq!(unsafe { arena.get_bytes_at(offset, 5) }, b"hello");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct ByteOffset(u32);
+
+impl ByteOffset {
+  /// Creates a new byte offset.
+  #[inline]
+  pub const fn new(offset: u32) -> Self {
+    Self(offset)
+  }
+
+  /// Returns the byte offset.
+  #[inline]
+  pub const fn get(&self) -> u32 {
+    self.0
+  }
+}
+
+impl From<u32> for ByteOffset {
+  #[inline]
+  fn from(offset: u32) -> Self {
+    Self(offset)
+  }
+}
+
+impl From<ByteOffset> for u32 {
+  #[inline]
+  fn from(offset: ByteOffset) -> Self {
+    offset.0
+  }
+}
+
+/// The index of an element of type `T` in the ARENA, which is viewed as an array of `T`,
+/// so the byte offset of the element is `index * size_of::<T>()`.
+///
+/// A `TypedOffset<T>` can not be passed where a [`ByteOffset`], or a `TypedOffset` of another type
+/// is expected:
+///
+/// ```compile_fail
+/// use rarena_allocator::{Arena, ArenaOptions, ByteOffset};
+///
+/// let arena = Arena::new(ArenaOptions::new());
+/// let _ = unsafe { arena.get_ref::<u64>(ByteOffset::new(8)) };
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions, TypedOffset};
+///
+/// let arena = Arena::new(ArenaOptions::new());
+/// let mut b = arena.alloc_aligned_bytes::<u64>(16).unwrap();
+/// b.put_u64_le(1).unwrap();
+/// b.put_u64_le(2).unwrap();
+///
+/// let first = TypedOffset::<u64>::from_byte_offset(b.byte_offset()).unwrap();
+/// let second = TypedOffset::<u64>::new(first.index() + 1);
+/// assert_eq!(unsafe { *arena.get_ref(second) }, u64::from_le(2));
+/// ```
+#[repr(transparent)]
+pub struct TypedOffset<T>(u32, PhantomData<fn() -> T>);
+
+impl<T> TypedOffset<T> {
+  /// Creates a new typed offset of the element at `index`.
+  #[inline]
+  pub const fn new(index: u32) -> Self {
+    Self(index, PhantomData)
+  }
+
+  /// Returns the typed offset of the element at the given byte offset, or `None` if the byte offset
+  /// is not a multiple of `size_of::<T>()`, or `T` is zero-sized.
+  #[inline]
+  pub const fn from_byte_offset(offset: ByteOffset) -> Option<Self> {
+    let size = core::mem::size_of::<T>() as u32;
+    if size == 0 || offset.0 % size != 0 {
+      return None;
+    }
+
+    Some(Self::new(offset.0 / size))
+  }
+
+  /// Returns the index of the element.
+  #[inline]
+  pub const fn index(&self) -> u32 {
+    self.0
+  }
+
+  /// Returns the byte offset of the element, i.e. `index * size_of::<T>()`.
+  ///
+  /// # Panics
+  /// - If the byte offset overflows `u32`.
+  #[inline]
+  pub const fn byte_offset(&self) -> ByteOffset {
+    match self.0.checked_mul(core::mem::size_of::<T>() as u32) {
+      Some(offset) => ByteOffset(offset),
+      None => panic!("the byte offset of the element overflows u32"),
+    }
+  }
+}
+
+impl<T> core::fmt::Debug for TypedOffset<T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_tuple("TypedOffset").field(&self.0).finish()
+  }
+}
+
+impl<T> Clone for TypedOffset<T> {
+  #[inline]
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for TypedOffset<T> {}
+
+impl<T> PartialEq for TypedOffset<T> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.0 == other.0
+  }
+}
+
+impl<T> Eq for TypedOffset<T> {}
+
+impl<T> core::hash::Hash for TypedOffset<T> {
+  #[inline]
+  fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+    self.0.hash(state)
+  }
+}
@@ -0,0 +1,65 @@
+use core::{
+  sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+  time::Duration,
+};
+use std::{
+  sync::Mutex,
+  thread::{self, Thread},
+  vec::Vec,
+};
+
+/// The longest time a thread stays parked, so that a wakeup is never lost for good, e.g. the freelist
+/// is changed by another process sharing the same file, or the change happens right before parking.
+const PARK_TIMEOUT: Duration = Duration::from_micros(200);
+
+/// Parks the threads which exhausted the spins on a contended freelist, and unparks them when the
+/// freelist is changed, see [`ArenaOptions::with_park_on_contention`](crate::ArenaOptions::with_park_on_contention).
+#[derive(Debug, Default)]
+pub(super) struct Parker {
+  /// Bumped on every change of the freelist.
+  epoch: AtomicU64,
+  /// The number of the parked threads, so that unparking is a single load when nobody is parked.
+  parked: AtomicUsize,
+  threads: Mutex<Vec<Thread>>,
+}
+
+impl Parker {
+  /// Parks the current thread until the freelist is changed, or [`PARK_TIMEOUT`] elapses.
+  pub(super) fn park(&self) {
+    let epoch = self.epoch.load(Ordering::SeqCst);
+    let current = thread::current();
+    self.parked.fetch_add(1, Ordering::SeqCst);
+    self.lock().push(current.clone());
+
+    // the freelist may be changed before the thread is registered.
+    if self.epoch.load(Ordering::SeqCst) == epoch {
+      thread::park_timeout(PARK_TIMEOUT);
+    }
+
+    let mut threads = self.lock();
+    if let Some(idx) = threads.iter().position(|t| t.id() == current.id()) {
+      threads.swap_remove(idx);
+    }
+    drop(threads);
+    self.parked.fetch_sub(1, Ordering::SeqCst);
+  }
+
+  /// Records a change of the freelist, and unparks the parked threads, if any.
+  #[inline]
+  pub(super) fn unpark_all(&self) {
+    self.epoch.fetch_add(1, Ordering::SeqCst);
+    if self.parked.load(Ordering::SeqCst) == 0 {
+      return;
+    }
+
+    for thread in self.lock().iter() {
+      thread.unpark();
+    }
+  }
+
+  #[inline]
+  fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Thread>> {
+    // the list is always consistent, so a poisoned lock is still usable.
+    self.threads.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
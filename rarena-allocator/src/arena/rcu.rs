@@ -0,0 +1,143 @@
+use super::*;
+
+use std::boxed::Box;
+
+/// A double-buffered ARENA, the readers pin the current [`Arena`] while a writer builds the next one
+/// aside, and then publishes it by [`RcuArena::swap`], which waits for the readers of the old one to drain.
+///
+/// The readers are counted in two slots, one per grace period, so a swap only waits for the readers
+/// which may have observed the old ARENA, the readers pinning the new one never block it.
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions, RcuArena};
+///
+/// let rcu = RcuArena::new(Arena::new(ArenaOptions::new()));
+///
+/// let next = Arena::new(ArenaOptions::new());
+/// let offset = {
+///   let mut b = next.alloc_bytes(5).unwrap();
+///   b.put_slice(b"hello").unwrap();
+///   b.detach();
+///   b.offset()
+/// };
+///
+/// let old = rcu.swap(next);
+/// assert_eq!(old.allocated(), old.data_offset());
+///
+/// let guard = rcu.read();
+/// assert_eq!(unsafe { guard.get_bytes(offset, 5) }, b"hello");
+/// ```
+pub struct RcuArena {
+  current: AtomicPtr<Arena>,
+  /// The grace period, the readers register in the slot of `epoch & 1`.
+  epoch: AtomicUsize,
+  readers: [AtomicUsize; 2],
+  /// Serializes the writers.
+  swapping: AtomicBool,
+}
+
+impl core::fmt::Debug for RcuArena {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("RcuArena")
+      .field("current", &*self.read())
+      .finish()
+  }
+}
+
+impl RcuArena {
+  /// Creates a new double-buffered ARENA, which publishes the given ARENA.
+  #[inline]
+  pub fn new(arena: Arena) -> Self {
+    Self {
+      current: AtomicPtr::new(Box::into_raw(Box::new(arena))),
+      epoch: AtomicUsize::new(0),
+      readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+      swapping: AtomicBool::new(false),
+    }
+  }
+
+  /// Pins the current ARENA, which is not reclaimed until the guard is dropped.
+  ///
+  /// The guard should be short-lived, a [`swap`](Self::swap) waits for it.
+  pub fn read(&self) -> RcuReadGuard<'_> {
+    let backoff = Backoff::new();
+    loop {
+      let slot = self.epoch.load(Ordering::SeqCst) & 1;
+      self.readers[slot].fetch_add(1, Ordering::SeqCst);
+      // the writer moved to the next grace period, register again, so that it does not wait forever.
+      if self.epoch.load(Ordering::SeqCst) & 1 == slot {
+        let arena = self.current.load(Ordering::SeqCst);
+        return RcuReadGuard {
+          rcu: self,
+          slot,
+          // Safety: the ARENA is not reclaimed while a reader is registered in its grace period.
+          arena: unsafe { &*arena },
+        };
+      }
+
+      self.readers[slot].fetch_sub(1, Ordering::SeqCst);
+      backoff.snooze();
+    }
+  }
+
+  /// Publishes the new ARENA, waits for the readers which may still pin the old one, and returns
+  /// the old one, which can be dropped or reused by the caller.
+  ///
+  /// The concurrent swaps are serialized.
+  pub fn swap(&self, new: Arena) -> Arena {
+    let backoff = Backoff::new();
+    while self
+      .swapping
+      .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+      .is_err()
+    {
+      backoff.snooze();
+    }
+
+    let old = self
+      .current
+      .swap(Box::into_raw(Box::new(new)), Ordering::SeqCst);
+    // the readers registered from now on load the new ARENA, only the previous grace period may see the old one.
+    let slot = self.epoch.fetch_add(1, Ordering::SeqCst) & 1;
+    let backoff = Backoff::new();
+    while self.readers[slot].load(Ordering::SeqCst) != 0 {
+      backoff.snooze();
+    }
+
+    self.swapping.store(false, Ordering::Release);
+    // Safety: the old ARENA is unpublished, and all its readers are drained.
+    *unsafe { Box::from_raw(old) }
+  }
+}
+
+impl Drop for RcuArena {
+  fn drop(&mut self) {
+    // Safety: no reader borrows `self` any more.
+    drop(unsafe { Box::from_raw(self.current.load(Ordering::Acquire)) });
+  }
+}
+
+/// A pinned read of the current ARENA of an [`RcuArena`], see [`RcuArena::read`].
+pub struct RcuReadGuard<'a> {
+  rcu: &'a RcuArena,
+  slot: usize,
+  arena: &'a Arena,
+}
+
+impl ops::Deref for RcuReadGuard<'_> {
+  type Target = Arena;
+
+  #[inline]
+  fn deref(&self) -> &Self::Target {
+    self.arena
+  }
+}
+
+impl Drop for RcuReadGuard<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    self.rcu.readers[self.slot].fetch_sub(1, Ordering::SeqCst);
+  }
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+/// Serializes the allocations and the deallocations of an ARENA, so that the offsets are reproducible,
+/// see [`ArenaOptions::with_deterministic`].
+///
+/// The lock is not reentrant, and the hooks of the ARENA may run while it is held,
+/// so a hook which allocates in the ARENA deadlocks, which is documented on the option.
+#[derive(Debug, Default)]
+pub(super) struct SerialLock {
+  locked: AtomicBool,
+}
+
+impl SerialLock {
+  /// Spins until the lock is acquired.
+  #[inline]
+  pub(super) fn lock(&self) -> SerialGuard<'_> {
+    let backoff = Backoff::new();
+    while self
+      .locked
+      .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+      .is_err()
+    {
+      backoff.snooze();
+    }
+    SerialGuard(self)
+  }
+}
+
+/// Releases the [`SerialLock`] when dropped.
+pub(super) struct SerialGuard<'a>(&'a SerialLock);
+
+impl Drop for SerialGuard<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    self.0.locked.store(false, Ordering::Release);
+  }
+}
@@ -0,0 +1,214 @@
+use super::*;
+
+use std::{boxed::Box, vec::Vec};
+
+/// A composite allocator which holds N inner [`Arena`]s, each with its own memory region.
+///
+/// The allocations are routed to the shards by a round-robin counter, so the
+/// contention on a single `allocated` counter is spread across the shards.
+/// The handles returned by [`ShardedArena::alloc_bytes`] are tagged with their shard index,
+/// which must be used to resolve the bytes later.
+pub struct ShardedArena {
+  shards: Box<[Arena]>,
+  next: AtomicUsize,
+}
+
+impl core::fmt::Debug for ShardedArena {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("ShardedArena")
+      .field("shards", &self.shards)
+      .finish()
+  }
+}
+
+impl ShardedArena {
+  /// Creates a new sharded ARENA with `n` shards, each shard is a [`Arena`] created with the given options.
+  ///
+  /// # Panics
+  /// - If `n` is zero.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{ShardedArena, ArenaOptions};
+  ///
+  /// let arena = ShardedArena::new(4, ArenaOptions::new());
+  /// assert_eq!(arena.num_shards(), 4);
+  /// ```
+  pub fn new(n: usize, opts: ArenaOptions) -> Self {
+    assert!(n > 0, "the number of shards must be greater than zero");
+    Self::from_arenas((0..n).map(|_| Arena::new(opts)).collect())
+  }
+
+  /// Creates a new sharded ARENA from the given ARENAs.
+  ///
+  /// # Panics
+  /// - If `arenas` is empty.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ShardedArena, ArenaOptions};
+  ///
+  /// let arena = ShardedArena::from_arenas(vec![
+  ///   Arena::new(ArenaOptions::new()),
+  ///   Arena::new(ArenaOptions::new()),
+  /// ]);
+  /// assert_eq!(arena.num_shards(), 2);
+  /// ```
+  pub fn from_arenas(arenas: Vec<Arena>) -> Self {
+    assert!(
+      !arenas.is_empty(),
+      "the number of shards must be greater than zero"
+    );
+    Self {
+      shards: arenas.into_boxed_slice(),
+      next: AtomicUsize::new(0),
+    }
+  }
+
+  /// Returns the number of shards.
+  #[inline]
+  pub fn num_shards(&self) -> usize {
+    self.shards.len()
+  }
+
+  /// Returns the shard at the given index, or `None` if the index is out of bounds.
+  #[inline]
+  pub fn shard(&self, index: usize) -> Option<&Arena> {
+    self.shards.get(index)
+  }
+
+  /// Returns all the shards.
+  #[inline]
+  pub fn shards(&self) -> &[Arena] {
+    &self.shards
+  }
+
+  /// Returns the sum of the capacities of all the shards.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{ShardedArena, ArenaOptions};
+  ///
+  /// let arena = ShardedArena::new(4, ArenaOptions::new());
+  /// assert_eq!(arena.total_size(), arena.shards().iter().map(|a| a.capacity()).sum());
+  /// ```
+  #[inline]
+  pub fn total_size(&self) -> usize {
+    self.shards.iter().map(Arena::capacity).sum()
+  }
+
+  /// Returns the sum of the remaining bytes of all the shards.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{ShardedArena, ArenaOptions};
+  ///
+  /// let arena = ShardedArena::new(4, ArenaOptions::new());
+  /// let remaining = arena.total_remaining();
+  /// let _b = arena.alloc_bytes(8).unwrap();
+  /// assert!(arena.total_remaining() < remaining);
+  /// ```
+  #[inline]
+  pub fn total_remaining(&self) -> usize {
+    self.shards.iter().map(Arena::remaining).sum()
+  }
+
+  /// Allocates a slice of memory in one of the shards.
+  ///
+  /// The shard is chosen by a round-robin counter, if the chosen shard does not have enough space,
+  /// the other shards are tried in order, the error of the chosen shard is returned if all of them fail.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{ShardedArena, ArenaOptions};
+  ///
+  /// let arena = ShardedArena::new(4, ArenaOptions::new());
+  /// let mut b = arena.alloc_bytes(5).unwrap();
+  /// b.put_slice(b"hello").unwrap();
+  /// b.detach();
+  ///
+  /// let (shard, offset) = (b.shard(), b.offset());
+  /// assert_eq!(unsafe { arena.get_bytes(shard, offset, 5) }, b"hello");
+  /// ```
+  pub fn alloc_bytes(&self, size: u32) -> Result<ShardedBytesRefMut<'_>, Error> {
+    let n = self.shards.len();
+    let start = self.next.fetch_add(1, Ordering::Relaxed) % n;
+
+    let mut err = None;
+    for i in 0..n {
+      let shard = (start + i) % n;
+      match self.shards[shard].alloc_bytes(size) {
+        Ok(bytes) => return Ok(ShardedBytesRefMut { shard, bytes }),
+        Err(e) => {
+          if err.is_none() {
+            err = Some(e);
+          }
+        }
+      }
+    }
+
+    Err(err.unwrap())
+  }
+
+  /// Returns a bytes slice from the given shard.
+  ///
+  /// # Safety
+  /// - `shard` must be less than [`num_shards`](Self::num_shards).
+  /// - The same requirements as [`Arena::get_bytes`].
+  #[inline]
+  pub unsafe fn get_bytes(&self, shard: usize, offset: usize, size: usize) -> &[u8] {
+    self.shards[shard].get_bytes(offset, size)
+  }
+
+  /// Returns a mutable bytes slice from the given shard.
+  ///
+  /// # Safety
+  /// - `shard` must be less than [`num_shards`](Self::num_shards).
+  /// - The same requirements as [`Arena::get_bytes_mut`].
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  pub unsafe fn get_bytes_mut(&self, shard: usize, offset: usize, size: usize) -> &mut [u8] {
+    self.shards[shard].get_bytes_mut(offset, size)
+  }
+}
+
+/// A buffer allocated by the [`ShardedArena`], tagged with the index of its shard.
+pub struct ShardedBytesRefMut<'a> {
+  shard: usize,
+  bytes: BytesRefMut<'a>,
+}
+
+impl<'a> ops::Deref for ShardedBytesRefMut<'a> {
+  type Target = BytesRefMut<'a>;
+
+  #[inline]
+  fn deref(&self) -> &Self::Target {
+    &self.bytes
+  }
+}
+
+impl<'a> ops::DerefMut for ShardedBytesRefMut<'a> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.bytes
+  }
+}
+
+impl<'a> ShardedBytesRefMut<'a> {
+  /// Returns the index of the shard which the buffer belongs to.
+  #[inline]
+  pub const fn shard(&self) -> usize {
+    self.shard
+  }
+
+  /// Consumes the handle and returns the index of the shard and the inner buffer.
+  #[inline]
+  pub fn into_parts(self) -> (usize, BytesRefMut<'a>) {
+    (self.shard, self.bytes)
+  }
+}
@@ -0,0 +1,140 @@
+use super::*;
+
+/// The arena which a buffer allocated by the [`SpillArena`] comes from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Tier {
+  /// The primary ARENA.
+  Primary,
+  /// The spill ARENA, used when the primary ARENA does not have enough space.
+  Spill,
+}
+
+/// A tiered allocator created by [`Arena::with_spill`], which allocates from the primary [`Arena`],
+/// and transparently spills to the secondary [`Arena`] (e.g. a file backed one) when the primary
+/// does not have enough space.
+///
+/// The handles returned by [`SpillArena::alloc_bytes`] are tagged with the [`Tier`] they come from,
+/// which must be used to resolve the bytes later.
+#[derive(Debug, Clone)]
+pub struct SpillArena {
+  primary: Arena,
+  spill: Arena,
+}
+
+impl SpillArena {
+  #[inline]
+  pub(super) const fn new(primary: Arena, spill: Arena) -> Self {
+    Self { primary, spill }
+  }
+
+  /// Returns the primary ARENA.
+  #[inline]
+  pub const fn primary(&self) -> &Arena {
+    &self.primary
+  }
+
+  /// Returns the spill ARENA.
+  #[inline]
+  pub const fn spill(&self) -> &Arena {
+    &self.spill
+  }
+
+  /// Returns the ARENA of the given tier.
+  #[inline]
+  pub const fn tier(&self, tier: Tier) -> &Arena {
+    match tier {
+      Tier::Primary => &self.primary,
+      Tier::Spill => &self.spill,
+    }
+  }
+
+  /// Allocates a slice of memory in the primary ARENA, or in the spill ARENA if the primary
+  /// fails with [`Error::InsufficientSpace`].
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{Arena, ArenaOptions, Tier};
+  ///
+  /// let arena = Arena::new(ArenaOptions::new().with_capacity(64));
+  /// let arena = arena.with_spill(Arena::new(ArenaOptions::new()));
+  ///
+  /// let mut b = arena.alloc_bytes(128).unwrap();
+  /// assert_eq!(b.tier(), Tier::Spill);
+  /// b.put_slice(b"hello").unwrap();
+  /// b.detach();
+  ///
+  /// let (tier, offset) = (b.tier(), b.offset());
+  /// assert_eq!(&unsafe { arena.get_bytes(tier, offset, 5) }[..5], b"hello");
+  /// ```
+  pub fn alloc_bytes(&self, size: u32) -> Result<SpillBytesRefMut<'_>, Error> {
+    match self.primary.alloc_bytes(size) {
+      Ok(bytes) => Ok(SpillBytesRefMut {
+        tier: Tier::Primary,
+        bytes,
+      }),
+      Err(Error::InsufficientSpace { .. }) => {
+        self.spill.alloc_bytes(size).map(|bytes| SpillBytesRefMut {
+          tier: Tier::Spill,
+          bytes,
+        })
+      }
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Returns a bytes slice from the ARENA of the given tier.
+  ///
+  /// # Safety
+  /// - The same requirements as [`Arena::get_bytes`].
+  #[inline]
+  pub unsafe fn get_bytes(&self, tier: Tier, offset: usize, size: usize) -> &[u8] {
+    self.tier(tier).get_bytes(offset, size)
+  }
+
+  /// Returns a mutable bytes slice from the ARENA of the given tier.
+  ///
+  /// # Safety
+  /// - The same requirements as [`Arena::get_bytes_mut`].
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  pub unsafe fn get_bytes_mut(&self, tier: Tier, offset: usize, size: usize) -> &mut [u8] {
+    self.tier(tier).get_bytes_mut(offset, size)
+  }
+}
+
+/// A buffer allocated by the [`SpillArena`], tagged with the [`Tier`] it comes from.
+pub struct SpillBytesRefMut<'a> {
+  tier: Tier,
+  bytes: BytesRefMut<'a>,
+}
+
+impl<'a> ops::Deref for SpillBytesRefMut<'a> {
+  type Target = BytesRefMut<'a>;
+
+  #[inline]
+  fn deref(&self) -> &Self::Target {
+    &self.bytes
+  }
+}
+
+impl<'a> ops::DerefMut for SpillBytesRefMut<'a> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.bytes
+  }
+}
+
+impl<'a> SpillBytesRefMut<'a> {
+  /// Returns the tier which the buffer comes from.
+  #[inline]
+  pub const fn tier(&self) -> Tier {
+    self.tier
+  }
+
+  /// Consumes the handle and returns the tier and the inner buffer.
+  #[inline]
+  pub fn into_parts(self) -> (Tier, BytesRefMut<'a>) {
+    (self.tier, self.bytes)
+  }
+}
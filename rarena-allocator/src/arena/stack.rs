@@ -0,0 +1,137 @@
+use super::*;
+
+/// The offset of the top node of an empty stack, offset `0` is never allocated by the ARENA.
+const EMPTY: u32 = 0;
+
+/// A lock-free stack (Treiber stack) of the fixed-size nodes in the ARENA, which can be used to
+/// build the recyclers on top of the memory of the ARENA.
+///
+/// The first `8` bytes of each node are used as the link to the next node while the node is in the stack.
+/// The head of the stack is tagged by a counter, which is bumped by every successful update, so that
+/// the ABA problem is avoided, like the freelist of the ARENA.
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions, ArenaStack};
+///
+/// let arena = Arena::new(ArenaOptions::new());
+/// let stack = ArenaStack::new(&arena);
+///
+/// let mut node = arena.alloc_aligned_bytes::<u64>(8).unwrap();
+/// node.detach();
+/// unsafe { stack.push(node.offset() as u32) };
+/// assert_eq!(stack.pop(), Some(node.offset() as u32));
+/// assert_eq!(stack.pop(), None);
+/// ```
+#[derive(Debug)]
+pub struct ArenaStack {
+  arena: Arena,
+  /// The tag in the high 32 bits, the offset of the top node in the low 32 bits.
+  head: AtomicU64,
+}
+
+impl ArenaStack {
+  /// Creates an empty stack of the nodes in the given ARENA.
+  #[inline]
+  pub fn new(arena: &Arena) -> Self {
+    Self {
+      arena: arena.clone(),
+      head: AtomicU64::new(encode_segment_node(0, EMPTY)),
+    }
+  }
+
+  /// Returns the ARENA which the nodes belong to.
+  #[inline]
+  pub const fn arena(&self) -> &Arena {
+    &self.arena
+  }
+
+  /// Returns `true` if the stack is empty.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    decode_segment_node(self.head.load(Ordering::Acquire)).1 == EMPTY
+  }
+
+  /// Pushes the node at the given offset onto the stack.
+  ///
+  /// # Safety
+  /// - The node must be allocated by the ARENA, and at least `8` bytes long.
+  /// - The node must not be in the stack, and must not be accessed by others until it is popped.
+  ///
+  /// # Panics
+  /// - If the ARENA is read-only.
+  /// - If the offset is not `8` bytes aligned, or out of the bounds of the ARENA.
+  pub unsafe fn push(&self, offset: u32) {
+    assert!(!self.arena.ro, "ARENA is read-only");
+    let node = self
+      .link(offset)
+      .expect("the node must be aligned to 8 bytes and in bounds of the ARENA");
+
+    let backoff = Backoff::new();
+    let mut head = self.head.load(Ordering::Acquire);
+    loop {
+      let (tag, top) = decode_segment_node(head);
+      node.store(encode_segment_node(0, top), Ordering::Release);
+
+      match self.head.compare_exchange_weak(
+        head,
+        encode_segment_node(tag.wrapping_add(1), offset),
+        Ordering::AcqRel,
+        Ordering::Acquire,
+      ) {
+        Ok(_) => return,
+        Err(current) => {
+          head = current;
+          backoff.spin();
+        }
+      }
+    }
+  }
+
+  /// Pops the top node from the stack, returns its offset, or `None` if the stack is empty.
+  pub fn pop(&self) -> Option<u32> {
+    let backoff = Backoff::new();
+    let mut head = self.head.load(Ordering::Acquire);
+    loop {
+      let (tag, top) = decode_segment_node(head);
+      if top == EMPTY {
+        return None;
+      }
+
+      // the node may be popped and reused by others concurrently, then the tag of the head
+      // must have been changed, so the stale `next` is never installed.
+      let next = decode_segment_node(self.link(top)?.load(Ordering::Acquire)).1;
+      match self.head.compare_exchange_weak(
+        head,
+        encode_segment_node(tag.wrapping_add(1), next),
+        Ordering::AcqRel,
+        Ordering::Acquire,
+      ) {
+        Ok(_) => return Some(top),
+        Err(current) => {
+          head = current;
+          backoff.spin();
+        }
+      }
+    }
+  }
+
+  /// Returns the link to the next node, which is stored in the first `8` bytes of the node at the given offset.
+  ///
+  /// The link lives in the raw memory of the ARENA, which is never constructed as a loom atomic,
+  /// so it is always a `core` atomic, even if the `loom` feature is enabled.
+  #[inline]
+  fn link(&self, offset: u32) -> Option<&core::sync::atomic::AtomicU64> {
+    let offset = offset as usize;
+    if offset < self.arena.data_offset as usize
+      || offset % mem::align_of::<u64>() != 0
+      || offset + mem::size_of::<u64>() > self.arena.cap as usize
+    {
+      return None;
+    }
+
+    // Safety: the offset is in bounds and well aligned.
+    unsafe { Some(&*(self.arena.ptr.add(offset) as *const core::sync::atomic::AtomicU64)) }
+  }
+}
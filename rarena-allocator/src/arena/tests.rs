@@ -76,6 +76,17 @@ fn alloc_bytes_mmap_anon_unify() {
   });
 }
 
+#[test]
+#[cfg(all(feature = "memmap", target_family = "wasm"))]
+fn alloc_bytes_mmap_anon_wasm() {
+  run(|| {
+    let mmap_options = MmapOptions::default().len(ARENA_SIZE);
+    let a = Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap();
+    assert_eq!(a.capacity(), ARENA_SIZE as usize + 8);
+    alloc_bytes(a);
+  });
+}
+
 fn alloc_offset_and_size(a: Arena) {
   #[derive(Debug)]
   #[repr(C)]
@@ -455,6 +466,310 @@ fn recoverable() {
   });
 }
 
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn reopen_in(opts: ArenaOptions) -> std::io::Result<()> {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_mmap");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+
+  let (allocated, offset) = {
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new().with_minimum_segment_size(32),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let mut b = a.alloc_bytes(10).unwrap();
+    b.put_slice(b"0123456789").unwrap();
+    b.detach();
+    let offset = b.offset();
+
+    // free a segment, it must survive the reopen
+    let freed = a.alloc_bytes(64).unwrap();
+    let mut tail = a.alloc_bytes(10).unwrap();
+    tail.detach();
+    drop(freed);
+    (a.allocated(), offset)
+  };
+
+  let open_options = OpenOptions::default().read(true).write(true);
+  let a = Arena::map_mut(&p, opts, open_options, MmapOptions::default())?;
+  assert_eq!(a.allocated(), allocated);
+  assert_eq!(a.minimum_segment_size(), 32);
+  assert_eq!(unsafe { a.get_bytes(offset, 10) }, b"0123456789");
+
+  // consume out the main memory, then the freed segment must still be in the freelist
+  let mut rest = a.alloc_bytes(a.remaining() as u32).unwrap();
+  rest.detach();
+  assert_eq!(a.remaining(), 0);
+  let b = a.alloc_bytes(10).unwrap();
+  assert!(b.offset() > offset && b.offset() < allocated);
+  Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn reopen_with_allocations() {
+  run(|| {
+    reopen_in(ArenaOptions::new().with_minimum_segment_size(32)).unwrap();
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn reopen_with_different_minimum_segment_size() {
+  run(|| {
+    reopen_in(ArenaOptions::new().with_minimum_segment_size(64)).unwrap();
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn reopen_strict() {
+  run(|| {
+    let err = reopen_in(
+      ArenaOptions::new()
+        .with_minimum_segment_size(64)
+        .with_strict_reopen(true),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn last_modified() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_last_modified");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert!(a.last_modified().is_none());
+
+    a.flush().unwrap();
+    let first = a.last_modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    a.flush_async().unwrap();
+    let second = a.last_modified().unwrap();
+    assert!(second > first);
+    drop(a);
+
+    let a = Arena::map(p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    assert!(a.last_modified().unwrap() > second);
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn flush_read_only() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_flush_read_only");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    a.flush().unwrap();
+    drop(a);
+
+    // the header of a read-only mapping must not be touched.
+    let a = Arena::map(p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    let modified = a.last_modified();
+    a.flush().unwrap();
+    a.flush_async().unwrap();
+    assert_eq!(a.last_modified(), modified);
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn reopen_older_version() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_reopen_older_version");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    a.flush().unwrap();
+    drop(a);
+
+    let mut data = std::fs::read(&p).unwrap();
+    data[VERSION_OFFSET..VERSION_OFFSET + VERSION_SIZE].copy_from_slice(&0u16.to_le_bytes());
+    std::fs::write(&p, data).unwrap();
+
+    let err = Arena::map(p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn try_lock() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_try_lock");
+    let open_options = OpenOptions::default()
+      .create(Some(ARENA_SIZE))
+      .read(true)
+      .write(true)
+      .try_lock(true);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options.clone(),
+      MmapOptions::default(),
+    )
+    .unwrap();
+
+    let err = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options.clone(),
+      MmapOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+    // the lock is released when the ARENA is dropped
+    drop(a);
+    Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn peek_header() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_peek_header");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let allocated = {
+      let a = Arena::map_mut(
+        &p,
+        ArenaOptions::new().with_minimum_segment_size(32),
+        open_options,
+        MmapOptions::default(),
+      )
+      .unwrap();
+      let mut b = a.alloc_bytes(100).unwrap();
+      b.detach();
+      a.allocated()
+    };
+
+    let header = Arena::peek_header(&p).unwrap();
+    assert_eq!(header.allocated() as usize, allocated);
+    assert_eq!(header.discarded(), 0);
+    assert_eq!(header.minimum_segment_size(), 32);
+    assert_eq!(header.capacity(), ARENA_SIZE as u64);
+
+    std::fs::write(&p, [0u8; 8]).unwrap();
+    let err = Arena::peek_header(&p).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn open_create_without_truncate() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_open_create_without_truncate");
+    let open_options = OpenOptions::default()
+      .create(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+
+    let (offset, allocated) = {
+      let a = Arena::map_mut(
+        &p,
+        ArenaOptions::new(),
+        open_options.clone(),
+        MmapOptions::default(),
+      )
+      .unwrap();
+      let mut b = a.alloc_bytes(5).unwrap();
+      b.put_slice(b"hello").unwrap();
+      b.detach();
+      (b.offset(), a.allocated())
+    };
+
+    // the existing populated file is opened, not clobbered.
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(a.allocated(), allocated);
+    assert_eq!(unsafe { a.get_bytes(offset, 5) }, b"hello");
+    drop(a);
+
+    // truncate regards the file as a new one.
+    let open_options = OpenOptions::default()
+      .read(true)
+      .write(true)
+      .truncate(true)
+      .size_hint(ARENA_SIZE * 2);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(a.allocated(), a.data_offset());
+    assert_eq!(a.capacity(), ARENA_SIZE as usize * 2);
+  });
+}
+
 #[test]
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 fn test_too_small() {
@@ -467,6 +782,193 @@ fn test_too_small() {
   );
 }
 
+fn alloc_bytes_uninit_in(a: Arena) {
+  // dirty a segment and give it back to the freelist
+  let mut dirty = a.alloc_bytes(a.remaining() as u32).unwrap();
+  dirty.put_slice(&[0xFF; 16]).unwrap();
+  drop(dirty);
+
+  unsafe {
+    let mut b = a.alloc_bytes_uninit(16).unwrap();
+    assert_eq!(b.capacity(), 16);
+    assert!(b.is_empty());
+    core::ptr::copy_nonoverlapping(b"0123456789abcdef".as_ptr(), b.as_mut_ptr(), 16);
+    b.assume_init();
+    assert_eq!(b.len(), 16);
+    assert_eq!(b.as_ref(), b"0123456789abcdef");
+  }
+
+  let b = a.alloc_bytes(16).unwrap();
+  assert_eq!(unsafe { a.get_bytes(b.offset(), 16) }, &[0; 16]);
+}
+
+#[test]
+fn alloc_bytes_uninit() {
+  run(|| alloc_bytes_uninit_in(Arena::new(ArenaOptions::new())))
+}
+
+#[test]
+fn alloc_bytes_uninit_unify() {
+  run(|| alloc_bytes_uninit_in(Arena::new(ArenaOptions::new().with_unify(true))))
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn on_oom() {
+  use std::sync::{Arc, Mutex};
+
+  let reserved: Arc<Mutex<Option<BytesMut>>> = Arc::new(Mutex::new(None));
+  let r = reserved.clone();
+  let a = Arena::new(ArenaOptions::new()).with_on_oom(Arc::new(move |_| {
+    // give the reserved memory back to the ARENA, then the allocation can be retried.
+    match r.lock().unwrap().take() {
+      Some(_) => OomAction::Retry,
+      None => OomAction::Fail,
+    }
+  }));
+
+  *reserved.lock().unwrap() = Some(a.alloc_bytes_owned(a.remaining() as u32).unwrap());
+  assert_eq!(a.remaining(), 0);
+
+  let b = a.alloc_bytes(100).unwrap();
+  assert_eq!(b.capacity(), 100);
+  assert!(reserved.lock().unwrap().is_none());
+
+  assert!(matches!(
+    a.alloc_bytes(a.remaining() as u32 + 1),
+    Err(Error::InsufficientSpace { .. })
+  ));
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn on_high_water() {
+  use std::sync::Arc;
+
+  let fired = Arc::new(AtomicUsize::new(0));
+  let f = fired.clone();
+  let a = Arena::new(ArenaOptions::new()).with_on_high_water(
+    0.8,
+    Arc::new(move || {
+      f.fetch_add(1, Ordering::SeqCst);
+    }),
+  );
+
+  let mut b = a.alloc_bytes(ARENA_SIZE / 2).unwrap();
+  b.detach();
+  assert!(a.used_ratio() < 0.8);
+  assert_eq!(fired.load(Ordering::SeqCst), 0);
+
+  let high_water = (a.capacity() as f64 * 0.8) as usize;
+  let mut b = a
+    .alloc_bytes((high_water - a.allocated()) as u32 + 1)
+    .unwrap();
+  b.detach();
+  assert!(a.used_ratio() >= 0.8);
+  assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+  let mut b = a.alloc_bytes(10).unwrap();
+  b.detach();
+  assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+  // the hook is re-armed by clear
+  unsafe { a.clear().unwrap() };
+  let mut b = a.alloc_bytes(ARENA_SIZE * 9 / 10).unwrap();
+  b.detach();
+  assert_eq!(fired.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn on_high_water_invalid_ratio() {
+  for ratio in [0.0, -0.5, 1.5, f64::NAN] {
+    let res = std::panic::catch_unwind(|| {
+      Arena::new(ArenaOptions::new()).with_on_high_water(ratio, std::sync::Arc::new(|| {}))
+    });
+    assert!(res.is_err(), "ratio {ratio} is accepted");
+  }
+
+  let _ = Arena::new(ArenaOptions::new()).with_on_high_water(1.0, std::sync::Arc::new(|| {}));
+}
+
+fn alloc_token_in(a: Arena) {
+  let mut b = a.alloc_bytes(10).unwrap();
+  b.put_slice(b"0123456789").unwrap();
+  b.detach();
+  let token = AllocToken::from(u64::from(b.token()));
+  assert_eq!(token.offset(), b.offset());
+  assert_eq!(token.capacity(), b.capacity());
+  drop(b);
+
+  unsafe {
+    assert_eq!(a.resolve_token(token), b"0123456789");
+    a.resolve_token_mut(token).copy_from_slice(b"9876543210");
+    assert_eq!(a.resolve_token(token), b"9876543210");
+  }
+
+  let null = a.alloc_bytes(0).unwrap();
+  assert_eq!(unsafe { a.resolve_token(null.token()) }, b"");
+}
+
+#[test]
+fn alloc_token() {
+  run(|| alloc_token_in(Arena::new(ArenaOptions::new())))
+}
+
+#[test]
+fn alloc_token_unify() {
+  run(|| alloc_token_in(Arena::new(ArenaOptions::new().with_unify(true))))
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn backend_kind_vec() {
+  run(|| {
+    let l = Arena::new(ArenaOptions::new());
+    assert_eq!(l.backend_kind(), BackendKind::Vec);
+    assert!(!l.is_persistent());
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn backend_kind_mmap() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_backend_kind_mmap");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(l.backend_kind(), BackendKind::MmapMut);
+    assert!(l.is_persistent());
+    drop(l);
+
+    let l = Arena::map(p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    assert_eq!(l.backend_kind(), BackendKind::Mmap);
+    assert!(l.is_persistent());
+  });
+}
+
+#[test]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn backend_kind_mmap_anon() {
+  run(|| {
+    let mmap_options = MmapOptions::default().len(ARENA_SIZE);
+    let l = Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap();
+    assert_eq!(l.backend_kind(), BackendKind::AnonymousMmap);
+    assert!(!l.is_persistent());
+  });
+}
+
 #[cfg(not(feature = "loom"))]
 fn check_data_offset(l: Arena, offset: usize) {
   let data_offset = l.data_offset();
@@ -480,7 +982,7 @@ fn check_data_offset(l: Arena, offset: usize) {
 #[cfg(not(feature = "loom"))]
 fn check_data_offset_vec() {
   run(|| {
-    check_data_offset(Arena::new(ArenaOptions::new()), 1);
+    check_data_offset(Arena::new(ArenaOptions::new()), 8);
   });
 }
 
@@ -488,7 +990,7 @@ fn check_data_offset_vec() {
 #[cfg(not(feature = "loom"))]
 fn check_data_offset_vec_unify() {
   run(|| {
-    check_data_offset(Arena::new(ArenaOptions::new().with_unify(true)), 32);
+    check_data_offset(Arena::new(ArenaOptions::new().with_unify(true)), 80);
   });
 }
 
@@ -506,7 +1008,7 @@ fn check_data_offset_mmap() {
     let mmap_options = MmapOptions::default();
     check_data_offset(
       Arena::map_mut(p, ArenaOptions::new(), open_options, mmap_options).unwrap(),
-      32,
+      80,
     );
   });
 }
@@ -518,7 +1020,7 @@ fn check_data_offset_mmap_anon() {
     let mmap_options = MmapOptions::default().len(ARENA_SIZE);
     check_data_offset(
       Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap(),
-      1,
+      8,
     );
   });
 }
@@ -530,71 +1032,245 @@ fn check_data_offset_mmap_anon_unify() {
     let mmap_options = MmapOptions::default().len(ARENA_SIZE);
     check_data_offset(
       Arena::map_anon(ArenaOptions::new().with_unify(true), mmap_options).unwrap(),
-      32,
+      80,
     );
   });
 }
 
-#[cfg(all(not(feature = "loom"), feature = "std"))]
-fn discard_freelist_in(l: Arena) {
-  use std::sync::{Arc, Barrier};
-
-  let b = Arc::new(Barrier::new(5));
-  let allocated = Arc::new(crossbeam_queue::ArrayQueue::new(5));
-  let mut handles = std::vec::Vec::new();
-
-  // make some segments
-  for i in 1..=5 {
-    let l = l.clone();
-    let b = b.clone();
-    let allocated = allocated.clone();
-    handles.push(std::thread::spawn(move || {
-      b.wait();
-      let bytes = l.alloc_bytes_owned(i * 50).unwrap();
-      let _ = allocated.push(bytes);
-    }));
-  }
-
-  for handle in handles {
-    handle.join().unwrap();
-  }
+#[cfg(not(feature = "loom"))]
+fn data_alignment_in(l: Arena) {
+  assert_eq!(l.data().as_ptr() as usize % 64, 0);
+  assert_eq!(l.allocated(), l.data_offset());
 
-  let remaining = l.remaining();
-  let mut remaining = l.alloc_bytes(remaining as u32).unwrap();
-  remaining.detach();
-  drop(allocated);
+  let b = l.alloc_bytes(10).unwrap();
+  assert_eq!(b.as_ptr() as usize % 64, 0);
+  drop(b);
 
-  l.discard_freelist().unwrap();
+  unsafe { l.clear().unwrap() };
+  assert_eq!(l.data().as_ptr() as usize % 64, 0);
 }
 
-#[cfg(all(not(feature = "loom"), feature = "std"))]
 #[test]
-fn discard_freelist() {
+#[cfg(not(feature = "loom"))]
+fn data_alignment_vec() {
   run(|| {
-    discard_freelist_in(Arena::new(ArenaOptions::new()));
+    data_alignment_in(Arena::new(ArenaOptions::new().with_data_alignment(64)));
   });
 }
 
-#[cfg(all(not(feature = "loom"), feature = "std"))]
 #[test]
-fn discard_freelist_unify() {
+#[cfg(not(feature = "loom"))]
+fn data_alignment_vec_unify() {
   run(|| {
-    discard_freelist_in(Arena::new(ArenaOptions::new().with_unify(true)));
+    data_alignment_in(Arena::new(
+      ArenaOptions::new().with_unify(true).with_data_alignment(64),
+    ));
   });
 }
 
 #[test]
 #[cfg_attr(miri, ignore)]
 #[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
-fn discard_freelist_mmap() {
+fn data_alignment_mmap() {
   run(|| {
     let dir = tempfile::tempdir().unwrap();
-    let p = dir.path().join("test_discard_freelist_mmap");
+    let p = dir.path().join("test_data_alignment_mmap");
     let open_options = OpenOptions::default()
       .create_new(Some(ARENA_SIZE))
       .read(true)
       .write(true);
-    let mmap_options = MmapOptions::default();
+    let opts = ArenaOptions::new().with_data_alignment(64);
+    data_alignment_in(Arena::map_mut(&p, opts, open_options, MmapOptions::default()).unwrap());
+
+    // the data offset is persisted, so reopen with the default options is consistent.
+    let open_options = OpenOptions::default().read(true).write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(l.data_offset(), 128);
+    drop(l);
+
+    let l = Arena::map(p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    assert_eq!(l.data_offset(), 128);
+  });
+}
+
+#[test]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn data_alignment_mmap_anon() {
+  run(|| {
+    let mmap_options = MmapOptions::default().len(ARENA_SIZE);
+    data_alignment_in(
+      Arena::map_anon(ArenaOptions::new().with_data_alignment(64), mmap_options).unwrap(),
+    );
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn prune_free_list_in(l: Arena) {
+  // fragment the main memory with small segments and a large one.
+  let mut segments = std::vec::Vec::new();
+  for size in [40, 48, 56, 200] {
+    segments.push(l.alloc_bytes(size).unwrap());
+    let mut sep = l.alloc_bytes(8).unwrap();
+    sep.detach();
+  }
+  drop(segments);
+  assert_eq!(l.segment_list_len(), 4);
+
+  let discarded = l.discarded();
+  let removed = unsafe { l.prune_free_list(100).unwrap() };
+  assert_eq!(removed, 3);
+  assert_eq!(l.segment_list_len(), 1);
+  assert!(l.discarded() > discarded);
+
+  // the large segment is still usable.
+  let mut rest = l.alloc_bytes(l.remaining() as u32).unwrap();
+  rest.detach();
+  let b = l.alloc_bytes(100).unwrap();
+  assert_eq!(b.capacity(), 100);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn prune_free_list_optimistic() {
+  run(|| {
+    prune_free_list_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn prune_free_list_pessimistic() {
+  run(|| {
+    prune_free_list_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn consolidate_in(l: Arena) {
+  let largest = |l: &Arena| l.free_segments().iter().map(|&(_, size)| size).max();
+
+  // fragment the main memory into two runs of adjacent small segments, split by a live buffer.
+  let mut segments = std::vec::Vec::new();
+  for _ in 0..16 {
+    segments.push(l.alloc_bytes(32).unwrap());
+  }
+  let mut sep = l.alloc_bytes(8).unwrap();
+  sep.detach();
+  for _ in 0..8 {
+    segments.push(l.alloc_bytes(32).unwrap());
+  }
+  let mut tail = l.alloc_bytes(8).unwrap();
+  tail.detach();
+  drop(segments);
+  assert_eq!(l.segment_list_len(), 24);
+  assert_eq!(largest(&l), Some(32));
+
+  let discarded = l.discarded();
+  let merges = unsafe { l.consolidate().unwrap() };
+  assert_eq!(merges, 22);
+  assert_eq!(l.segment_list_len(), 2);
+  assert_eq!(largest(&l), Some(16 * 32));
+  // the nodes of the merged segments are reclaimed.
+  assert_eq!(l.discarded(), discarded - 22 * SEGMENT_NODE_SIZE as u32);
+  assert!(l.validate_free_list().is_ok());
+
+  // nothing to merge.
+  assert_eq!(unsafe { l.consolidate().unwrap() }, 0);
+  assert_eq!(l.discarded(), discarded - 22 * SEGMENT_NODE_SIZE as u32);
+
+  // the merged run is usable.
+  let mut rest = l.alloc_bytes(l.remaining() as u32).unwrap();
+  rest.detach();
+  let b = l.alloc_bytes(16 * 32 - SEGMENT_NODE_SIZE as u32).unwrap();
+  assert_eq!(b.capacity(), 16 * 32 - SEGMENT_NODE_SIZE);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn consolidate_optimistic() {
+  run(|| {
+    consolidate_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn consolidate_pessimistic() {
+  run(|| {
+    consolidate_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn discard_freelist_in(l: Arena) {
+  use std::sync::{Arc, Barrier};
+
+  let b = Arc::new(Barrier::new(5));
+  let allocated = Arc::new(crossbeam_queue::ArrayQueue::new(5));
+  let mut handles = std::vec::Vec::new();
+
+  // make some segments
+  for i in 1..=5 {
+    let l = l.clone();
+    let b = b.clone();
+    let allocated = allocated.clone();
+    handles.push(std::thread::spawn(move || {
+      b.wait();
+      let bytes = l.alloc_bytes_owned(i * 50).unwrap();
+      let _ = allocated.push(bytes);
+    }));
+  }
+
+  for handle in handles {
+    handle.join().unwrap();
+  }
+
+  let remaining = l.remaining();
+  let mut remaining = l.alloc_bytes(remaining as u32).unwrap();
+  remaining.detach();
+  drop(allocated);
+
+  l.discard_freelist().unwrap();
+}
+
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+#[test]
+fn discard_freelist() {
+  run(|| {
+    discard_freelist_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+#[test]
+fn discard_freelist_unify() {
+  run(|| {
+    discard_freelist_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn discard_freelist_mmap() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_discard_freelist_mmap");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let mmap_options = MmapOptions::default();
     discard_freelist_in(
       Arena::map_mut(p, ArenaOptions::new(), open_options, mmap_options).unwrap(),
     );
@@ -639,6 +1315,68 @@ fn allocate_slow_path(l: Arena) {
   }
 }
 
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn alloc_once_in(l: Arena) {
+  use std::sync::{Arc, Barrier};
+
+  const THREADS: usize = 8;
+
+  let slot = {
+    let mut slot = l.alloc_aligned_bytes::<u32>(0).unwrap();
+    slot.put_u32_le(0).unwrap();
+    slot.detach();
+    slot.offset()
+  };
+
+  let b = Arc::new(Barrier::new(THREADS));
+  let handles = (0..THREADS)
+    .map(|_| {
+      let l = l.clone();
+      let b = b.clone();
+      std::thread::spawn(move || {
+        b.wait();
+        unsafe { l.alloc_once(slot, 64).unwrap() }
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  let results = handles
+    .into_iter()
+    .map(|h| h.join().unwrap())
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(results.iter().filter(|(_, init)| *init).count(), 1);
+  let offset = results[0].0;
+  assert_ne!(offset, 0);
+  assert!(results.iter().all(|(o, _)| *o == offset));
+  assert_eq!(unsafe { l.alloc_once(slot, 64) }.unwrap(), (offset, false));
+
+  // the published allocation is never handed out again.
+  let mut b = l.alloc_bytes(64).unwrap();
+  b.detach();
+  assert!(b.offset() + b.capacity() <= offset as usize || b.offset() >= offset as usize + 64);
+
+  assert!(matches!(
+    unsafe { l.alloc_once(l.capacity(), 64) },
+    Err(Error::OutOfBounds { .. })
+  ));
+  assert_eq!(
+    unsafe { l.alloc_once(slot + 1, 64) },
+    Err(Error::InvalidAlignment)
+  );
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn alloc_once() {
+  run(|| alloc_once_in(Arena::new(ArenaOptions::new())));
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn alloc_once_unify() {
+  run(|| alloc_once_in(Arena::new(ArenaOptions::new().with_unify(true))));
+}
+
 #[cfg(all(not(feature = "loom"), feature = "std"))]
 fn allocate_slow_path_concurrent_create_segments(l: Arena) {
   use std::sync::{Arc, Barrier};
@@ -797,3 +1535,4549 @@ fn reopen() {
   assert_eq!(l.data_offset(), data_offset);
   drop(l);
 }
+
+#[cfg(not(feature = "loom"))]
+fn clone_into_in(src: Arena, dst: Arena) {
+  let mut offsets = std::vec::Vec::new();
+  for i in 0..5u8 {
+    let mut b = src.alloc_bytes(16).unwrap();
+    b.put_slice(&[i; 16]).unwrap();
+    b.detach();
+    offsets.push(b.offset());
+  }
+
+  // make a segment in the freelist
+  {
+    let _segment = src.alloc_bytes(100).unwrap();
+    let mut b = src.alloc_bytes(8).unwrap();
+    b.detach();
+  }
+
+  src.clone_into(&dst).unwrap();
+  assert_eq!(dst.allocated(), src.allocated());
+  assert_eq!(dst.discarded(), src.discarded());
+
+  for (i, offset) in offsets.into_iter().enumerate() {
+    let data = unsafe { dst.get_bytes(offset, 16) };
+    assert_eq!(data, &[i as u8; 16]);
+  }
+
+  // the segment in the freelist is also cloned.
+  let remaining = dst.remaining();
+  let mut b = dst.alloc_bytes(remaining as u32).unwrap();
+  b.detach();
+  dst.alloc_bytes(50).unwrap();
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn clone_into_vec() {
+  run(|| {
+    clone_into_in(
+      Arena::new(ArenaOptions::new()),
+      Arena::new(ArenaOptions::new().with_capacity(ARENA_SIZE * 2)),
+    );
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn clone_into_vec_unify() {
+  run(|| {
+    clone_into_in(
+      Arena::new(ArenaOptions::new().with_unify(true)),
+      Arena::new(
+        ArenaOptions::new()
+          .with_unify(true)
+          .with_capacity(ARENA_SIZE * 2),
+      ),
+    );
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn clone_into_incompatible() {
+  let src = Arena::new(ArenaOptions::new());
+  let dst = Arena::new(ArenaOptions::new().with_unify(true));
+  assert_eq!(src.clone_into(&dst), Err(Error::IncompatibleArena));
+
+  let dst = Arena::new(ArenaOptions::new().with_freelist(Freelist::Pessimistic));
+  assert_eq!(src.clone_into(&dst), Err(Error::IncompatibleArena));
+
+  let dst = Arena::new(ArenaOptions::new());
+  let mut b = dst.alloc_bytes(10).unwrap();
+  b.detach();
+  assert_eq!(src.clone_into(&dst), Err(Error::IncompatibleArena));
+}
+
+#[cfg(not(feature = "loom"))]
+fn deep_clone_in(l: Arena) {
+  let offset = {
+    let mut b = l.alloc_bytes(16).unwrap();
+    b.put_slice(&[1; 16]).unwrap();
+    b.detach();
+    b.offset()
+  };
+
+  // make a segment in the freelist
+  {
+    let _segment = l.alloc_bytes(100).unwrap();
+    let mut b = l.alloc_bytes(8).unwrap();
+    b.detach();
+  }
+
+  let copy = l.deep_clone().unwrap();
+  assert_eq!(l.refs(), 1);
+  assert_eq!(copy.refs(), 1);
+  assert_eq!(copy.data_offset(), l.data_offset());
+  assert_eq!(copy.allocated(), l.allocated());
+  assert_eq!(copy.discarded(), l.discarded());
+  assert_eq!(copy.data(), l.data());
+  assert_eq!(copy.validate_free_list(), l.validate_free_list());
+  assert_eq!(copy.validate_free_list().unwrap().segments(), 1);
+  assert_eq!(unsafe { copy.get_bytes(offset, 16) }, &[1; 16]);
+
+  // mutate the copy, the original is unchanged.
+  let (allocated, data) = (l.allocated(), l.data().to_vec());
+  unsafe { copy.get_bytes_mut(offset, 16) }.fill(2);
+  let mut tail = copy.alloc_bytes(32).unwrap();
+  tail.detach();
+  assert_eq!(unsafe { l.get_bytes(offset, 16) }, &[1; 16]);
+  assert_eq!(l.allocated(), allocated);
+  assert_eq!(l.data(), data.as_slice());
+  assert!(copy.allocated() > allocated);
+  drop(l);
+  assert_eq!(unsafe { copy.get_bytes(offset, 16) }, &[2; 16]);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn deep_clone() {
+  run(|| {
+    deep_clone_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn deep_clone_unify() {
+  run(|| {
+    deep_clone_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn deep_clone_map_anon() {
+  run(|| {
+    let mmap_options = MmapOptions::default().len(ARENA_SIZE);
+    deep_clone_in(Arena::map_anon(ArenaOptions::new(), mmap_options).unwrap());
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn zero_on_free_in(l: Arena, zeroed: bool) {
+  let mut a = l.alloc_bytes(64).unwrap();
+  a.put_slice(&[0xAA; 64]).unwrap();
+  let mut hold = l.alloc_bytes(8).unwrap();
+  hold.detach();
+  drop(a);
+
+  let remaining = l.remaining();
+  let mut rest = l.alloc_bytes(remaining as u32).unwrap();
+  rest.detach();
+
+  // allocate from the freelist
+  let b = l.alloc_bytes(32).unwrap();
+  let data = unsafe { l.get_bytes(b.offset(), b.capacity()) };
+  if zeroed {
+    assert!(data.iter().all(|&x| x == 0));
+  } else {
+    assert!(data.iter().all(|&x| x == 0xAA));
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn zero_on_free() {
+  run(|| {
+    zero_on_free_in(Arena::new(ArenaOptions::new()), true);
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn zero_on_free_disabled() {
+  run(|| {
+    zero_on_free_in(
+      Arena::new(ArenaOptions::new().with_zero_on_free(false)),
+      false,
+    );
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn split_at_in(l: Arena) {
+  let allocated = l.allocated();
+  let bytes = l.alloc_bytes(64).unwrap();
+  let offset = bytes.offset();
+  let (mut left, mut right) = bytes.split_at(32);
+  assert_eq!(left.offset(), offset);
+  assert_eq!(right.offset(), offset + 32);
+  assert_eq!(left.capacity(), 32);
+  assert_eq!(right.capacity(), 32);
+
+  left.put_slice(&[1; 32]).unwrap();
+  right.put_slice(&[2; 32]).unwrap();
+  assert!(left.put_u8(1).is_err());
+  assert!(right.put_u8(2).is_err());
+
+  let data = unsafe { l.get_bytes(offset, 64) };
+  assert_eq!(&data[..32], &[1; 32]);
+  assert_eq!(&data[32..], &[2; 32]);
+
+  // each half gives back its own part, the original memory is freed exactly once.
+  drop(right);
+  assert_eq!(l.allocated(), offset + 32);
+  drop(left);
+  assert_eq!(l.allocated(), allocated);
+  assert_eq!(l.discarded(), 0);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn split_at() {
+  run(|| {
+    split_at_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn split_at_unify() {
+  run(|| {
+    split_at_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn sharded_arena() {
+  use std::sync::{Arc, Barrier};
+
+  const THREADS: usize = 8;
+  const ALLOCS: usize = 100;
+
+  let a = Arc::new(ShardedArena::new(
+    4,
+    ArenaOptions::new().with_capacity(64 * 1024),
+  ));
+  let total_size = a.total_size();
+  assert_eq!(total_size, a.shards().iter().map(|s| s.capacity()).sum());
+
+  let b = Arc::new(Barrier::new(THREADS));
+  let handles = (0..THREADS)
+    .map(|t| {
+      let a = a.clone();
+      let b = b.clone();
+      std::thread::spawn(move || {
+        b.wait();
+        (0..ALLOCS)
+          .map(|i| {
+            let mut bytes = a.alloc_bytes(16).unwrap();
+            bytes.put_u64_le(t as u64).unwrap();
+            bytes.put_u64_le(i as u64).unwrap();
+            bytes.detach();
+            (t, i, bytes.shard(), bytes.offset())
+          })
+          .collect::<std::vec::Vec<_>>()
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  let mut used = std::collections::HashSet::new();
+  for handle in handles {
+    for (t, i, shard, offset) in handle.join().unwrap() {
+      assert!(used.insert((shard, offset)));
+      let bytes = unsafe { a.get_bytes(shard, offset, 16) };
+      assert_eq!(&bytes[..8], (t as u64).to_le_bytes());
+      assert_eq!(&bytes[8..], (i as u64).to_le_bytes());
+    }
+  }
+
+  // every shard gets its share of the allocations.
+  for s in a.shards() {
+    assert!(s.allocated() > s.data_offset());
+  }
+  assert!(a.total_remaining() <= total_size - THREADS * ALLOCS * 16);
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "metrics"))]
+fn discarded_breakdown() {
+  run(|| {
+    // manual
+    let a = Arena::new(ArenaOptions::new());
+    a.increase_discarded(100);
+    assert_eq!(a.discarded_manual(), 100);
+    assert_eq!(a.discarded_padding(), 0);
+    assert_eq!(a.discarded_small_segments(), 0);
+
+    // segments smaller than the minimum segment size
+    let a = Arena::new(ArenaOptions::new().with_minimum_segment_size(64));
+    let b1 = a.alloc_bytes(32).unwrap();
+    let _b2 = a.alloc_bytes(8).unwrap();
+    drop(b1);
+    assert_eq!(a.discarded_small_segments(), 32);
+    assert_eq!(a.discarded_padding(), 0);
+    assert_eq!(a.discarded_manual(), 0);
+
+    // the node of a new freelist segment
+    let a = Arena::new(ArenaOptions::new().with_minimum_segment_size(8));
+    let b1 = a.alloc_bytes(128).unwrap();
+    let _b2 = a.alloc_bytes(8).unwrap();
+    drop(b1);
+    assert!(a.discarded_padding() > 0);
+    assert_eq!(a.discarded_small_segments(), 0);
+    assert_eq!(a.discarded_manual(), 0);
+    assert_eq!(a.discarded(), a.discarded_padding());
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "metrics"))]
+fn free_list_traversals() {
+  run(|| {
+    const SEGMENTS: usize = 16;
+
+    let a = Arena::new(
+      ArenaOptions::new()
+        .with_capacity(64 * 1024)
+        .with_freelist(Freelist::Pessimistic),
+    );
+    assert_eq!(a.free_list_traversals(), 0);
+    assert_eq!(a.max_free_list_depth(), 0);
+
+    // fragment the ARENA, the pinned buffers keep the freed ones from merging into the main memory.
+    let mut freed = std::vec::Vec::new();
+    let mut pinned = std::vec::Vec::new();
+    for i in 0..SEGMENTS as u32 {
+      freed.push(a.alloc_bytes(64 + i * 16).unwrap());
+      pinned.push(a.alloc_bytes(8).unwrap());
+    }
+    drop(freed);
+    assert_eq!(a.free_segments().len(), SEGMENTS);
+    let traversals = a.free_list_traversals();
+    assert!(traversals > 0);
+
+    // exhaust the main memory, so the allocations go through the freelist.
+    let rest = a.alloc_bytes(a.remaining() as u32).unwrap();
+    assert_eq!(a.remaining(), 0);
+
+    // only the largest segment, the tail of the ascending list, fits.
+    let largest = a
+      .free_segments()
+      .iter()
+      .map(|(_, size)| *size)
+      .max()
+      .unwrap();
+    let b = a.alloc_bytes(largest - 8).unwrap();
+    assert!(a.free_list_traversals() >= traversals + SEGMENTS as u64);
+    assert!(a.max_free_list_depth() >= SEGMENTS as u64);
+    assert!(a.max_free_list_depth() <= a.free_list_traversals());
+    drop((b, rest, pinned));
+
+    // shared by the clones.
+    assert_eq!(a.clone().free_list_traversals(), a.free_list_traversals());
+  });
+}
+
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn get_slice_in(l: Arena) {
+  let mut b = l.alloc_aligned_bytes::<u64>(64).unwrap();
+  for i in 0..8u64 {
+    b.put_u64_ne(i * 10).unwrap();
+  }
+  b.detach();
+
+  let offset = b.offset();
+  let slice = unsafe { l.get_slice::<u64>(offset, 8) };
+  assert_eq!(slice, &[0, 10, 20, 30, 40, 50, 60, 70]);
+
+  unsafe { l.get_slice_mut::<u64>(offset, 8)[7] = 700 };
+  assert_eq!(unsafe { l.get_slice::<u64>(offset, 8) }[7], 700);
+
+  let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+    l.get_slice::<u64>(offset, l.capacity());
+  }));
+  assert!(res.is_err());
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn get_slice() {
+  run(|| {
+    get_slice_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn get_slice_unify() {
+  run(|| {
+    get_slice_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[cfg(all(not(feature = "loom"), feature = "metrics"))]
+fn maximum_retries_zero_in(l: Arena) {
+  // exhaust the main memory, then make a segment which is too small for the following allocation.
+  let mut b = l.alloc_bytes(l.remaining() as u32 - 64).unwrap();
+  b.detach();
+  let small = l.alloc_bytes(32).unwrap();
+  let mut b = l.alloc_bytes(l.remaining() as u32).unwrap();
+  b.detach();
+  drop(small);
+
+  let attempts = l.slow_path_attempts();
+  assert!(l.alloc_bytes(1000).is_err());
+  assert_eq!(l.slow_path_attempts() - attempts, 1);
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "metrics"))]
+fn maximum_retries_zero_optimistic() {
+  run(|| {
+    maximum_retries_zero_in(Arena::new(
+      ArenaOptions::new()
+        .with_maximum_retries(0)
+        .with_freelist(Freelist::Optimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "metrics"))]
+fn maximum_retries_zero_pessimistic() {
+  run(|| {
+    maximum_retries_zero_in(Arena::new(
+      ArenaOptions::new()
+        .with_maximum_retries(0)
+        .with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "trace"))]
+fn trace() {
+  run(|| {
+    let a = Arena::new(
+      ArenaOptions::new()
+        .with_trace_capacity(4)
+        .with_minimum_segment_size(64),
+    );
+    let b1 = a.alloc_bytes(8).unwrap();
+    let b2 = a.alloc_bytes(16).unwrap();
+    let (o1, o2) = (b1.offset() as u32, b2.offset() as u32);
+    let _b3 = a.alloc_bytes(8).unwrap();
+    drop(b1);
+    drop(b2);
+    a.increase_discarded(100);
+
+    // the capacity is 4, so only the last 4 events are kept.
+    assert_eq!(
+      a.drain_trace(),
+      [
+        TraceEvent::new(TraceOp::Discard, o1, 8),
+        TraceEvent::new(TraceOp::Dealloc, o2, 16),
+        TraceEvent::new(TraceOp::Discard, o2, 16),
+        TraceEvent::new(TraceOp::Discard, 0, 100),
+      ]
+    );
+    a.increase_discarded(100);
+    assert_eq!(a.drain_trace(), [TraceEvent::new(TraceOp::Discard, 0, 100)]);
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "allocator-api"))]
+fn new_in_allocator() {
+  use std::{
+    alloc::{AllocError, Allocator, Global},
+    sync::Arc,
+  };
+
+  #[derive(Clone, Default)]
+  struct CountingAllocator {
+    allocated: Arc<AtomicUsize>,
+    deallocated: Arc<AtomicUsize>,
+  }
+
+  unsafe impl Allocator for CountingAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+      self.allocated.fetch_add(1, Ordering::SeqCst);
+      Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+      self.deallocated.fetch_add(1, Ordering::SeqCst);
+      Global.deallocate(ptr, layout)
+    }
+  }
+
+  run(|| {
+    let alloc = CountingAllocator::default();
+    let a = Arena::new_in_allocator(ArenaOptions::new(), alloc.clone());
+    assert_eq!(alloc.allocated.load(Ordering::SeqCst), 1);
+
+    let mut b = a.alloc_bytes(8).unwrap();
+    b.put_u64_le(8).unwrap();
+    drop(b);
+
+    let a2 = a.clone();
+    drop(a);
+    assert_eq!(alloc.deallocated.load(Ordering::SeqCst), 0);
+    drop(a2);
+    assert_eq!(alloc.deallocated.load(Ordering::SeqCst), 1);
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn prefault() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_prefault");
+    let open_options = OpenOptions::default()
+      .create_new(Some(3 * 4096 + 17))
+      .read(true)
+      .write(true);
+
+    let offset = {
+      let a = Arena::map_mut(
+        &p,
+        ArenaOptions::new(),
+        open_options,
+        MmapOptions::default(),
+      )
+      .unwrap();
+      a.prefault().unwrap();
+      let mut b = a.alloc_bytes(5).unwrap();
+      b.put_slice(b"hello").unwrap();
+      b.detach();
+      a.flush().unwrap();
+      b.offset()
+    };
+
+    let a = Arena::map(
+      &p,
+      OpenOptions::default().read(true),
+      MmapOptions::default(),
+      0,
+    )
+    .unwrap();
+    a.prefault().unwrap();
+    assert_eq!(unsafe { a.get_bytes(offset, 5) }, b"hello");
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn ref_mut_as_bytes_in(l: Arena) {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  #[repr(C)]
+  struct Pod {
+    a: u64,
+    b: u32,
+    c: u16,
+    d: u16,
+  }
+
+  let pod = Pod {
+    a: 1,
+    b: 2,
+    c: 3,
+    d: 4,
+  };
+  let expected: [u8; mem::size_of::<Pod>()] = unsafe { mem::transmute(pod) };
+
+  let mut value = unsafe { l.alloc::<Pod>().unwrap() };
+  value.write(pod);
+  assert_eq!(unsafe { value.as_bytes() }, expected);
+
+  let offset = value.offset();
+  let allocated = l.allocated();
+  let mut bytes = unsafe { value.into_bytes_handle() };
+  assert_eq!(bytes.offset(), offset);
+  assert_eq!(&bytes[..], expected);
+  // the memory is not freed by the conversion.
+  assert_eq!(l.allocated(), allocated);
+
+  bytes.detach();
+  drop(bytes);
+  assert_eq!(l.allocated(), allocated);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn ref_mut_as_bytes() {
+  run(|| {
+    ref_mut_as_bytes_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn ref_mut_as_bytes_unify() {
+  run(|| {
+    ref_mut_as_bytes_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn usable_capacity() {
+  #[derive(Clone, Copy)]
+  #[repr(C, align(64))]
+  struct Align64([u8; 64]);
+
+  run(|| {
+    for alignment in [8, 64, 4096] {
+      for cap in [1, 16, 64, 100] {
+        for unify in [false, true] {
+          let opts = ArenaOptions::new()
+            .with_capacity(cap)
+            .with_maximum_alignment(alignment)
+            .with_unify(unify);
+
+          let a = Arena::new(opts);
+          assert!(a.usable_capacity() >= cap as usize);
+          assert_eq!(a.usable_capacity(), a.remaining());
+          let b = a.alloc_bytes(cap).unwrap();
+          assert_eq!(b.capacity(), cap as usize);
+          drop(b);
+
+          let b = a.alloc_bytes(a.usable_capacity() as u32).unwrap();
+          assert_eq!(a.remaining(), 0);
+          drop(b);
+
+          if alignment >= 64 && cap >= 64 {
+            let a = Arena::new(opts);
+            let b = a.alloc_aligned_bytes::<Align64>(cap - 64).unwrap();
+            assert_eq!(b.as_ptr() as usize % 64, 0);
+          }
+        }
+      }
+    }
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn data_chunks_in(l: Arena) {
+  for i in 0..10u8 {
+    let mut b = l.alloc_bytes(i as u32 * 3 + 1).unwrap();
+    b.put_u8(i).unwrap();
+    b.detach();
+  }
+
+  let data = l.data();
+  for chunk in [1, 7, 64, data.len(), data.len() + 1] {
+    let chunks = l.data_chunks(chunk).collect::<std::vec::Vec<_>>();
+    assert!(chunks.iter().all(|c| c.len() <= chunk));
+    assert_eq!(chunks.concat(), data);
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn data_chunks() {
+  run(|| {
+    data_chunks_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn data_chunks_unify() {
+  run(|| {
+    data_chunks_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn data_chunks_mmap() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_data_chunks_mmap");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let mmap_options = MmapOptions::default();
+    data_chunks_in(Arena::map_mut(p, ArenaOptions::new(), open_options, mmap_options).unwrap());
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn try_get_mut_read_only() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_try_get_mut_read_only");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+
+    let offset = {
+      let a = Arena::map_mut(
+        &p,
+        ArenaOptions::new(),
+        open_options,
+        MmapOptions::default(),
+      )
+      .unwrap();
+      let mut b = a.alloc_bytes(8).unwrap();
+      b.detach();
+      unsafe {
+        assert_eq!(a.try_get_bytes_mut(b.offset(), 8).unwrap().len(), 8);
+        assert!(a.try_get_pointer_mut(b.offset()).is_ok());
+        assert!(a.try_get_aligned_pointer_mut::<u64>(b.offset()).is_ok());
+      }
+      b.offset()
+    };
+
+    let a = Arena::map(
+      &p,
+      OpenOptions::default().read(true),
+      MmapOptions::default(),
+      0,
+    )
+    .unwrap();
+    unsafe {
+      assert!(matches!(
+        a.try_get_bytes_mut(offset, 8),
+        Err(Error::ReadOnly)
+      ));
+      assert!(matches!(
+        a.try_get_pointer_mut(offset),
+        Err(Error::ReadOnly)
+      ));
+      assert!(matches!(
+        a.try_get_aligned_pointer_mut::<u64>(offset),
+        Err(Error::ReadOnly)
+      ));
+    }
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn corrupted_freelist_in(l: Arena) {
+  // exhaust the main memory, so that the allocation must go through the freelist.
+  let b1 = l.alloc_bytes(64).unwrap();
+  let mut b = l.alloc_bytes(l.remaining() as u32).unwrap();
+  b.detach();
+
+  // plant an out of range `next` into the freelist, as if the file was damaged.
+  let header = l.header();
+  header.sentinel.store(
+    encode_segment_node(SENTINEL_SEGMENT_NODE_SIZE, u32::MAX - 7),
+    Ordering::Release,
+  );
+  assert!(matches!(
+    l.alloc_bytes(8),
+    Err(Error::InsufficientSpace { .. })
+  ));
+  // deallocation walks the freelist, and must not crash either.
+  drop(b1);
+
+  // plant an in bounds node whose size is out of range.
+  let node_offset = align_offset::<AtomicU64>(l.data_offset() as u32 + 8);
+  unsafe {
+    let node = &*(l.get_pointer(node_offset as usize) as *const AtomicU64);
+    node.store(
+      encode_segment_node(u32::MAX - 1, SENTINEL_SEGMENT_NODE_OFFSET),
+      Ordering::Release,
+    );
+  }
+  header.sentinel.store(
+    encode_segment_node(SENTINEL_SEGMENT_NODE_SIZE, node_offset),
+    Ordering::Release,
+  );
+  assert!(matches!(
+    l.alloc_bytes(8),
+    Err(Error::InsufficientSpace { .. })
+  ));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn corrupted_freelist_optimistic() {
+  run(|| {
+    corrupted_freelist_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Optimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn corrupted_freelist_pessimistic() {
+  run(|| {
+    corrupted_freelist_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[cfg(not(feature = "loom"))]
+fn rebuild_free_list_in(opts: ArenaOptions) {
+  let src = Arena::new(opts);
+  let mut bufs = (0..10u32)
+    .map(|i| {
+      let mut b = src.alloc_bytes(32 + i * 16).unwrap();
+      b.put_u32_le(i).unwrap();
+      b
+    })
+    .collect::<std::vec::Vec<_>>();
+  // the last one is never freed, so the freed buffers go to the freelist.
+  let mut last = bufs.pop().unwrap();
+  last.detach();
+  for (i, b) in bufs.iter_mut().enumerate() {
+    if i % 2 == 1 {
+      b.detach();
+    }
+  }
+  drop(bufs);
+
+  let segments = src.free_segments();
+  assert!(segments.len() >= 4);
+
+  // restore the data into a new ARENA, then rebuild the freelist.
+  let dst = Arena::new(opts);
+  assert_eq!(dst.data_offset(), src.data_offset());
+  let mut b = dst.alloc_bytes(src.data().len() as u32).unwrap();
+  b.put_slice(src.data()).unwrap();
+  b.detach();
+  assert_eq!(dst.allocated(), src.allocated());
+
+  unsafe { dst.rebuild_free_list(&segments).unwrap() };
+  assert_eq!(dst.free_segments(), segments);
+  assert_eq!(dst.data(), src.data());
+
+  // invalid segments are rejected.
+  let (offset, size) = segments[0];
+  unsafe {
+    assert_eq!(
+      dst.rebuild_free_list(&[(offset, size), (offset + 8, size)]),
+      Err(Error::InvalidSegment {
+        offset: offset.max(offset + 8),
+        size
+      })
+    );
+    assert!(dst.free_segments().is_empty());
+    let allocated = dst.allocated() as u32;
+    assert_eq!(
+      dst.rebuild_free_list(&[(allocated, 64)]),
+      Err(Error::InvalidSegment {
+        offset: allocated,
+        size: 64
+      })
+    );
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn rebuild_free_list_optimistic() {
+  run(|| {
+    rebuild_free_list_in(
+      ArenaOptions::new()
+        .with_capacity(4096)
+        .with_freelist(Freelist::Optimistic),
+    );
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn rebuild_free_list_pessimistic() {
+  run(|| {
+    rebuild_free_list_in(
+      ArenaOptions::new()
+        .with_capacity(4096)
+        .with_freelist(Freelist::Pessimistic),
+    );
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn ref_mut_drop_uninit() {
+  use std::sync::atomic::AtomicUsize as StdAtomicUsize;
+
+  static DROPS: StdAtomicUsize = StdAtomicUsize::new(0);
+
+  struct Guard(std::vec::Vec<u8>);
+
+  impl Drop for Guard {
+    fn drop(&mut self) {
+      DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+  }
+
+  let a = Arena::new(ArenaOptions::new());
+
+  // panics between the allocation and the write.
+  let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    let _value = unsafe { a.alloc::<Guard>().unwrap() };
+    panic!("panic before write");
+  }));
+  assert!(res.is_err());
+  assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+  let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    let _value = unsafe { a.alloc_owned::<Guard>().unwrap() };
+    panic!("panic before write");
+  }));
+  assert!(res.is_err());
+  assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+  // the initialized value is dropped.
+  let mut value = unsafe { a.alloc::<Guard>().unwrap() };
+  value.write(Guard(std::vec![1, 2, 3]));
+  drop(value);
+  assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn map_anon_len() {
+  let a = Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(1 << 20)).unwrap();
+  assert_eq!(a.capacity(), 1 << 20);
+
+  let a = Arena::map_anon(
+    ArenaOptions::new().with_unify(true),
+    MmapOptions::new().len(1 << 20),
+  )
+  .unwrap();
+  assert_eq!(a.capacity(), 1 << 20);
+
+  let err = Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(0)).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+  let err = Arena::map_anon(
+    ArenaOptions::new().with_unify(true),
+    MmapOptions::new().len(OVERHEAD as u32 - 1),
+  )
+  .unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn map_mut_reserve_overhead() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_map_mut_reserve_overhead");
+  let open_options = OpenOptions::default()
+    .create_new(Some(16))
+    .read(true)
+    .write(true);
+  let err = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options.clone(),
+    MmapOptions::default(),
+  )
+  .unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  std::fs::remove_file(&p).unwrap();
+
+  let a = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options.reserve_overhead(true),
+    MmapOptions::default(),
+  )
+  .unwrap();
+  let len = std::fs::metadata(&p).unwrap().len();
+  assert_eq!(len, (16 + OVERHEAD + SEGMENT_NODE_ALIGNMENT - 1) as u64);
+  assert_eq!(a.capacity() as u64, len);
+  assert!(a.remaining() >= 16);
+  a.alloc_bytes(16).unwrap().detach();
+  drop(a);
+
+  // an existing file is not resized.
+  let open_options = OpenOptions::default()
+    .create(Some(16))
+    .read(true)
+    .write(true)
+    .reserve_overhead(true);
+  let a = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  )
+  .unwrap();
+  assert_eq!(a.capacity() as u64, len);
+  assert_eq!(std::fs::metadata(&p).unwrap().len(), len);
+}
+
+fn swap_bytes_in(l: Arena) {
+  let mut guard = l.alloc_bytes(4).unwrap();
+  guard.put_slice(&[0xFF; 4]).unwrap();
+  guard.detach();
+  let mut a = l.alloc_bytes(16).unwrap();
+  a.put_slice(&[0xAA; 16]).unwrap();
+  a.detach();
+  let mut b = l.alloc_bytes(16).unwrap();
+  b.put_slice(&[0xBB; 16]).unwrap();
+  b.detach();
+  let mut tail = l.alloc_bytes(4).unwrap();
+  tail.put_slice(&[0xFF; 4]).unwrap();
+  tail.detach();
+
+  let (ao, bo) = (a.offset() as u32, b.offset() as u32);
+  unsafe {
+    l.swap_bytes(ao, bo, 16).unwrap();
+    assert_eq!(l.get_bytes(ao as usize, 16), &[0xBB; 16]);
+    assert_eq!(l.get_bytes(bo as usize, 16), &[0xAA; 16]);
+    assert_eq!(l.get_bytes(guard.offset(), 4), &[0xFF; 4]);
+    assert_eq!(l.get_bytes(tail.offset(), 4), &[0xFF; 4]);
+
+    assert_eq!(l.swap_bytes(ao, ao + 8, 16), Err(Error::OverlappingRegions));
+    assert_eq!(
+      l.swap_bytes(ao, l.capacity() as u32 - 8, 16),
+      Err(Error::OutOfBounds {
+        offset: l.capacity() as u32 - 8,
+        size: 16,
+        capacity: l.capacity() as u32,
+      })
+    );
+    l.swap_bytes(ao, bo, 0).unwrap();
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn swap_bytes() {
+  run(|| {
+    swap_bytes_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn swap_bytes_unify() {
+  run(|| {
+    swap_bytes_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn swap_bytes_read_only() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_swap_bytes_read_only");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+  let offset = {
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let mut b = a.alloc_bytes(16).unwrap();
+    b.detach();
+    a.flush().unwrap();
+    b.offset() as u32
+  };
+
+  let a = Arena::map(&p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+  assert_eq!(
+    unsafe { a.swap_bytes(offset, offset + 8, 8) },
+    Err(Error::ReadOnly)
+  );
+}
+
+fn freeze_in(l: Arena) {
+  let mut b = l.alloc_bytes(5).unwrap();
+  b.put_slice(b"hello").unwrap();
+  b.detach();
+
+  let frozen = l.freeze();
+  assert!(frozen.read_only());
+  assert!(!l.read_only());
+  assert!(matches!(frozen.alloc_bytes(8), Err(Error::ReadOnly)));
+  assert_eq!(unsafe { frozen.get_bytes(b.offset(), 5) }, b"hello");
+
+  // the original handle can still write, and the writes are visible through the frozen one.
+  let mut c = l.alloc_bytes(5).unwrap();
+  c.put_slice(b"world").unwrap();
+  c.detach();
+  assert_eq!(unsafe { frozen.get_bytes(c.offset(), 5) }, b"world");
+  assert_eq!(frozen.allocated(), l.allocated());
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn freeze() {
+  run(|| {
+    freeze_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn freeze_unify() {
+  run(|| {
+    freeze_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(all(feature = "numa", not(feature = "loom")))]
+fn numa_node() {
+  // binding is best-effort, the allocation must succeed even if node 0 is not available.
+  let a = Arena::new(
+    ArenaOptions::new()
+      .with_capacity(1 << 20)
+      .with_numa_node(Some(0)),
+  );
+  let mut b = a.alloc_bytes(1024).unwrap();
+  b.put_slice(&[1; 1024]).unwrap();
+
+  // a node which does not exist falls back to the default policy.
+  let a = Arena::new(ArenaOptions::new().with_numa_node(Some(u16::MAX as u32)));
+  a.alloc_bytes(8).unwrap();
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  {
+    let a = Arena::map_anon(
+      ArenaOptions::new().with_numa_node(Some(0)),
+      MmapOptions::new().len(1 << 20),
+    )
+    .unwrap();
+    let mut b = a.alloc_bytes(1024).unwrap();
+    b.put_slice(&[1; 1024]).unwrap();
+  }
+}
+
+fn alloc_bytes_with_stats_in(l: Arena) {
+  let (mut b, stats) = l
+    .alloc_bytes_with_stats(l.remaining() as u32 - 128)
+    .unwrap();
+  b.detach();
+  assert!(stats.fast_path());
+  assert!(!stats.from_free_list());
+  assert_eq!(stats.slow_path_retries(), 0);
+
+  // exhaust the main memory, so the following allocation must come from the freelist.
+  let middle = l.alloc_bytes(64).unwrap();
+  let mut b = l.alloc_bytes(l.remaining() as u32).unwrap();
+  b.detach();
+  drop(middle);
+
+  let (_b, stats) = l.alloc_bytes_with_stats(16).unwrap();
+  assert!(!stats.fast_path());
+  assert!(stats.from_free_list());
+  assert_eq!(stats.slow_path_retries(), 0);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_with_stats_optimistic() {
+  run(|| {
+    alloc_bytes_with_stats_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Optimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_with_stats_pessimistic() {
+  run(|| {
+    alloc_bytes_with_stats_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+fn compact_into_in(src: Arena, dst: Arena) {
+  let mut offsets = std::vec::Vec::new();
+  let mut bufs = std::vec::Vec::new();
+  for i in 0..6u8 {
+    let mut b = src.alloc_bytes(64).unwrap();
+    b.put_slice(&[i; 64]).unwrap();
+    offsets.push(b.offset() as u32);
+    bufs.push(b);
+  }
+
+  // free the 2nd and the 4th buffers to make gaps, keep the others.
+  for (i, mut b) in bufs.into_iter().enumerate() {
+    if i % 2 == 1 && i != 5 {
+      drop(b);
+    } else {
+      b.detach();
+    }
+  }
+  assert_eq!(src.free_segments().len(), 2);
+
+  let mut remapped = std::vec::Vec::new();
+  let map = src
+    .compact_into(&dst, |old, new| remapped.push((old, new)))
+    .unwrap();
+  assert_eq!(map.len(), 3);
+  assert_eq!(
+    remapped,
+    map
+      .regions()
+      .iter()
+      .map(|r| (r.old_offset(), r.new_offset()))
+      .collect::<std::vec::Vec<_>>()
+  );
+
+  for (i, offset) in offsets.iter().enumerate() {
+    if i % 2 == 1 && i != 5 {
+      // the padding before the segment node may be kept, but the segment itself is dropped.
+      assert!(map.get(offset + 8).is_none());
+      continue;
+    }
+
+    let new = map.get(*offset).unwrap();
+    assert_eq!(new % 8, offset % 8);
+    assert_eq!(unsafe { dst.get_bytes(new as usize, 64) }, &[i as u8; 64]);
+  }
+
+  // the live regions are densely packed, only the alignment paddings are left.
+  let live: u32 = map.regions().iter().map(|r| r.size()).sum();
+  let dense = dst.allocated() as u32 - dst.data_offset() as u32;
+  assert!(dense >= live && dense - live < 8 * map.len() as u32);
+  assert!(dst.allocated() < src.allocated());
+  assert!(dst.free_segments().is_empty());
+
+  // the destination must be empty.
+  assert_eq!(
+    src.compact_into(&dst, |_, _| {}).unwrap_err(),
+    Error::IncompatibleArena
+  );
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn compact_into() {
+  run(|| {
+    compact_into_in(
+      Arena::new(ArenaOptions::new()),
+      Arena::new(ArenaOptions::new()),
+    );
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn compact_into_unify() {
+  run(|| {
+    compact_into_in(
+      Arena::new(ArenaOptions::new().with_unify(true)),
+      Arena::new(ArenaOptions::new().with_unify(true)),
+    );
+  });
+}
+
+fn ptr_range_in(l: Arena) {
+  let data = l.data_ptr_range();
+  let full = l.full_ptr_range();
+  assert_eq!(full.end, data.end);
+  assert_eq!(data.start as usize - full.start as usize, l.data_offset());
+  assert_eq!(full.end as usize - full.start as usize, l.capacity());
+
+  let b = l.alloc_bytes(l.remaining() as u32).unwrap();
+  assert!(data.contains(&b.as_ptr()));
+  assert!(data.contains(&unsafe { b.as_ptr().add(b.capacity() - 1) }));
+  assert_eq!(unsafe { b.as_ptr().add(b.capacity()) }, data.end);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn ptr_range() {
+  run(|| {
+    ptr_range_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn ptr_range_unify() {
+  run(|| {
+    ptr_range_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+fn partition_in(l: Arena) {
+  let parts = l.partition(&[256, 512, 1024]).unwrap();
+  assert_eq!(parts.len(), 3);
+  assert!(l.alloc_bytes(l.remaining() as u32 + 1).is_err());
+  assert!(matches!(l.partition(&[8]), Err(Error::IncompatibleArena)));
+
+  let full = l.full_ptr_range();
+  let mut prev_end = l.data_ptr_range().start;
+  for (i, (part, size)) in parts.iter().zip([256, 512, 1024]).enumerate() {
+    assert_eq!(part.remaining(), size);
+    assert_eq!(part.refs(), 4);
+
+    // the partitions are disjoint, and in bounds of the ARENA.
+    let range = part.full_ptr_range();
+    assert!(range.start >= prev_end && range.end <= full.end);
+    prev_end = range.end;
+
+    // fill the partition, the allocations stay in the partition.
+    let mut b = part.alloc_bytes(size as u32 / 2).unwrap();
+    b.put_slice(&std::vec![i as u8; size / 2]).unwrap();
+    b.detach();
+    let mut c = part.alloc_bytes(size as u32 / 2).unwrap();
+    c.put_slice(&std::vec![i as u8; size / 2]).unwrap();
+    c.detach();
+    assert!(part.data_ptr_range().contains(&b.as_ptr()));
+    assert!(part.data_ptr_range().contains(&c.as_ptr()));
+    assert_eq!(part.remaining(), 0);
+    assert!(part.alloc_bytes(1).is_err());
+
+    // each partition has its own freelist.
+    unsafe { part.dealloc(b.memory_offset() as u32, b.memory_capacity() as u32) };
+    assert_eq!(part.free_segments().len(), 1);
+  }
+
+  for (i, part) in parts.iter().enumerate() {
+    let data = part.data();
+    let end = data.len();
+    assert!(data[end - 16..].iter().all(|b| *b == i as u8));
+    assert_eq!(part.free_segments().len(), 1);
+
+    unsafe { part.clear().unwrap() };
+    assert_eq!(part.allocated(), part.data_offset());
+    assert!(part.free_segments().is_empty());
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn partition() {
+  run(|| {
+    partition_in(Arena::new(
+      ArenaOptions::new().with_capacity(4096).with_unify(true),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn partition_non_unify() {
+  run(|| {
+    let l = Arena::new(ArenaOptions::new().with_capacity(4096));
+    assert!(matches!(l.partition(&[8]), Err(Error::IncompatibleArena)));
+    assert_eq!(l.allocated(), l.data_offset());
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn partition_map_mut() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_partition_map_mut");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+  let l = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  )
+  .unwrap();
+  assert!(matches!(l.partition(&[8]), Err(Error::IncompatibleArena)));
+  assert_eq!(l.allocated(), l.data_offset());
+
+  partition_in(
+    Arena::map_anon(
+      ArenaOptions::new().with_unify(true),
+      MmapOptions::new().len(4096),
+    )
+    .unwrap(),
+  );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn header_bytes() {
+  // the `allocated` field follows the `sentinel` and the `last_modified` fields.
+  fn decode_allocated(header: &[u8]) -> usize {
+    u32::from_ne_bytes(header[16..20].try_into().unwrap()) as usize
+  }
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_header_bytes");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+  let a = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  )
+  .unwrap();
+  assert_eq!(a.header_bytes().len(), mem::size_of::<Header>());
+  assert_eq!(decode_allocated(a.header_bytes()), a.allocated());
+
+  let mut b = a.alloc_bytes(100).unwrap();
+  b.detach();
+  assert_eq!(decode_allocated(a.header_bytes()), a.allocated());
+
+  assert!(Arena::new(ArenaOptions::new()).header_bytes().is_empty());
+  let a = Arena::new(ArenaOptions::new().with_unify(true));
+  assert_eq!(a.header_bytes().len(), mem::size_of::<Header>());
+  assert_eq!(decode_allocated(a.header_bytes()), a.allocated());
+}
+
+fn reset_discarded_in(l: Arena) {
+  // the segment is too small to be reused, so it is discarded.
+  let small = l.alloc_bytes(4).unwrap();
+  let mut b = l.alloc_bytes(8).unwrap();
+  b.detach();
+  drop(small);
+  l.increase_discarded(100);
+  assert!(l.discarded() > 100);
+
+  let allocated = l.allocated();
+  l.reset_discarded();
+  assert_eq!(l.discarded(), 0);
+  assert_eq!(l.allocated(), allocated);
+  #[cfg(feature = "metrics")]
+  {
+    assert_eq!(l.discarded_manual(), 0);
+    assert_eq!(l.discarded_small_segments(), 0);
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reset_discarded() {
+  run(|| {
+    reset_discarded_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reset_discarded_unify() {
+  run(|| {
+    reset_discarded_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn stats() {
+  use core::sync::atomic::{AtomicBool, Ordering};
+  use std::sync::Arc;
+
+  const THREADS: usize = 4;
+
+  let a = Arena::new(ArenaOptions::new().with_capacity(64 * 1024));
+  let stop = Arc::new(AtomicBool::new(false));
+  let handles = (0..THREADS)
+    .map(|_| {
+      let a = a.clone();
+      let stop = stop.clone();
+      std::thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+          let bufs = (0..16)
+            .filter_map(|i| a.alloc_bytes(32 + i).ok())
+            .collect::<std::vec::Vec<_>>();
+          // drop some of the buffers to feed the freelist.
+          for (i, mut b) in bufs.into_iter().enumerate() {
+            if i % 4 != 0 {
+              b.detach();
+            }
+          }
+        }
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  for _ in 0..1000 {
+    let stats = a.stats();
+    assert_eq!(stats.capacity(), a.capacity());
+    assert_eq!(stats.allocated() + stats.remaining(), a.capacity());
+    assert!(stats.allocated() <= a.capacity());
+    assert!(stats.free_list_bytes() <= stats.allocated());
+  }
+
+  stop.store(true, Ordering::Relaxed);
+  for h in handles {
+    h.join().unwrap();
+  }
+
+  // the ARENA is quiescent, so the snapshot is exact.
+  let stats = a.stats();
+  assert_eq!(stats.allocated(), a.allocated());
+  assert_eq!(stats.discarded(), a.discarded() as usize);
+  assert_eq!(
+    stats.free_list_bytes(),
+    a.free_segments()
+      .iter()
+      .map(|(_, size)| *size as usize)
+      .sum::<usize>()
+  );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn direct_io() {
+  let dir = tempfile::tempdir().unwrap();
+
+  // the length of the file must be sector-aligned.
+  let p = dir.path().join("test_direct_io_unaligned");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE + 1))
+    .read(true)
+    .write(true)
+    .direct_io(true);
+  let err = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  )
+  .unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  assert!(!p.exists());
+
+  let p = dir.path().join("test_direct_io");
+  let open_options = OpenOptions::default()
+    .create_new(Some(4096))
+    .read(true)
+    .write(true)
+    .direct_io(true);
+  match Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  ) {
+    Ok(a) => {
+      assert_eq!(a.capacity(), 4096);
+      let mut b = a.alloc_bytes(8).unwrap();
+      b.put_u64_le(8).unwrap();
+      a.flush().unwrap();
+    }
+    // the filesystem of the temporary directory (e.g. tmpfs) may not support the direct I/O.
+    Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+  }
+}
+
+fn zero_range_in(l: Arena) {
+  let mut b = l.alloc_bytes(64).unwrap();
+  b.put_slice(&[0xAB; 64]).unwrap();
+  b.detach();
+
+  l.zero_range(b.offset() + 16, 32).unwrap();
+  let bytes = unsafe { l.get_bytes(b.offset(), 64) };
+  assert_eq!(&bytes[..16], &[0xAB; 16]);
+  assert_eq!(&bytes[16..48], &[0; 32]);
+  assert_eq!(&bytes[48..], &[0xAB; 16]);
+
+  l.zero_range(b.offset(), 0).unwrap();
+  assert!(matches!(
+    l.zero_range(l.capacity() - 8, 16),
+    Err(Error::OutOfBounds { .. })
+  ));
+  assert!(matches!(l.zero_range(0, 8), Err(Error::OutOfBounds { .. })));
+  assert!(matches!(
+    l.zero_range(usize::MAX, 2),
+    Err(Error::OutOfBounds { .. })
+  ));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn zero_range() {
+  run(|| {
+    zero_range_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn zero_range_unify() {
+  run(|| {
+    zero_range_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+fn leak_in(l: Arena) {
+  let mut value = unsafe { l.alloc::<std::vec::Vec<u8>>().unwrap() };
+  value.write(std::vec![1, 2, 3]);
+  let mut leaked = value.leak();
+
+  let mut num = unsafe { l.alloc::<u64>().unwrap() };
+  num.write(42);
+  let num = num.leak();
+
+  // the leaked handles keep the memory alive.
+  assert_eq!(l.refs(), 3);
+  drop(l);
+  assert_eq!(*leaked, [1, 2, 3]);
+  assert_eq!(*num, 42);
+  assert_eq!(
+    unsafe { leaked.arena().get_bytes(num.offset(), 8) },
+    42u64.to_ne_bytes()
+  );
+
+  leaked.push(4);
+  assert_eq!(*leaked, [1, 2, 3, 4]);
+  assert_eq!(leaked.arena().refs(), 2);
+  drop(leaked);
+  assert_eq!(num.arena().refs(), 1);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn leak() {
+  run(|| {
+    leak_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn leak_unify() {
+  run(|| {
+    leak_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+#[should_panic(expected = "the value must be initialized before leaking")]
+fn leak_uninit() {
+  let a = Arena::new(ArenaOptions::new());
+  let value = unsafe { a.alloc::<u64>().unwrap() };
+  let _ = value.leak();
+}
+
+fn small_segment_reclaimable_in(l: Arena) {
+  assert_eq!(l.data_offset() % 8, 0);
+  assert_eq!(l.data().as_ptr() as usize % 8, 0);
+
+  // the smallest segment (the node and the minimum segment size) at the start of the data section.
+  let first = l.alloc_bytes(20).unwrap();
+  let mut b = l.alloc_bytes(8).unwrap();
+  b.detach();
+  drop(first);
+
+  // no padding is needed, the whole buffer is reclaimed as a segment.
+  let segments = l.free_segments();
+  assert_eq!(segments.len(), 1);
+  assert_eq!(segments[0], (l.data_offset() as u32, 20));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn small_segment_reclaimable() {
+  run(|| {
+    small_segment_reclaimable_in(Arena::new(
+      ArenaOptions::new()
+        .with_maximum_alignment(1)
+        .with_data_alignment(1)
+        .with_minimum_segment_size(8),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn small_segment_reclaimable_unify() {
+  run(|| {
+    small_segment_reclaimable_in(Arena::new(
+      ArenaOptions::new()
+        .with_unify(true)
+        .with_maximum_alignment(1)
+        .with_data_alignment(1)
+        .with_minimum_segment_size(8),
+    ));
+  });
+}
+
+#[cfg(feature = "std")]
+fn copy_from_reader_in(l: Arena) {
+  let data = (0..=255u8).cycle().take(1000).collect::<std::vec::Vec<_>>();
+  let mut reader = std::io::Cursor::new(data.clone());
+
+  let mut b = l.copy_from_reader(&mut reader, 600).unwrap();
+  assert_eq!(b.as_ref(), &data[..600]);
+  b.detach();
+  assert_eq!(unsafe { l.get_bytes(b.offset(), 600) }, &data[..600]);
+
+  // short read, the memory is given back.
+  let allocated = l.allocated();
+  let err = l.copy_from_reader(&mut reader, 600).err().unwrap();
+  assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+  assert_eq!(l.allocated(), allocated);
+
+  assert!(l.copy_from_reader(&mut reader, 0).unwrap().is_empty());
+  let err = l
+    .copy_from_reader(&mut reader, l.capacity() as u32)
+    .err()
+    .unwrap();
+  assert_eq!(err.kind(), std::io::ErrorKind::Other);
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn copy_from_reader() {
+  run(|| {
+    copy_from_reader_in(Arena::new(ArenaOptions::new().with_capacity(4096)));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn copy_from_reader_unify() {
+  run(|| {
+    copy_from_reader_in(Arena::new(
+      ArenaOptions::new().with_capacity(4096).with_unify(true),
+    ));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn arena_stack() {
+  use std::{collections::HashSet, sync::Arc};
+
+  const THREADS: usize = 4;
+  const NODES: usize = 64;
+
+  let a = Arena::new(ArenaOptions::new().with_capacity(8 * 1024));
+  let stack = Arc::new(ArenaStack::new(&a));
+  assert!(stack.is_empty());
+
+  let offsets = (0..THREADS * NODES)
+    .map(|_| {
+      let mut node = a.alloc_aligned_bytes::<u64>(8).unwrap();
+      node.detach();
+      node.offset() as u32
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  // every thread pushes its own nodes, and pops the same number of nodes.
+  let handles = offsets
+    .chunks(NODES)
+    .map(|chunk| {
+      let stack = stack.clone();
+      let chunk = chunk.to_vec();
+      std::thread::spawn(move || {
+        let mut popped = std::vec::Vec::new();
+        for offset in chunk {
+          unsafe { stack.push(offset) };
+          if let Some(offset) = stack.pop() {
+            popped.push(offset);
+          }
+        }
+        popped
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  let mut popped = handles
+    .into_iter()
+    .flat_map(|h| h.join().unwrap())
+    .collect::<std::vec::Vec<_>>();
+  while let Some(offset) = stack.pop() {
+    popped.push(offset);
+  }
+  assert!(stack.is_empty());
+
+  // no lost or duplicated offsets.
+  assert_eq!(popped.len(), offsets.len());
+  let popped = popped.into_iter().collect::<HashSet<_>>();
+  assert_eq!(popped, offsets.into_iter().collect::<HashSet<_>>());
+}
+
+#[test]
+#[cfg(feature = "loom")]
+fn arena_stack_loom() {
+  use loom::{sync::Arc, thread};
+
+  loom::model(|| {
+    let a = Arena::new(ArenaOptions::new());
+    let stack = Arc::new(ArenaStack::new(&a));
+    let offsets = (0..4)
+      .map(|_| {
+        let mut node = a.alloc_aligned_bytes::<u64>(8).unwrap();
+        node.detach();
+        node.offset() as u32
+      })
+      .collect::<std::vec::Vec<_>>();
+
+    let handles = offsets
+      .chunks(2)
+      .map(|chunk| {
+        let stack = stack.clone();
+        let chunk = chunk.to_vec();
+        thread::spawn(move || {
+          for offset in chunk {
+            unsafe { stack.push(offset) };
+          }
+          stack.pop()
+        })
+      })
+      .collect::<std::vec::Vec<_>>();
+
+    let mut popped = handles
+      .into_iter()
+      .filter_map(|h| h.join().unwrap())
+      .collect::<std::vec::Vec<_>>();
+    while let Some(offset) = stack.pop() {
+      popped.push(offset);
+    }
+
+    popped.sort_unstable();
+    assert_eq!(popped, offsets);
+  });
+}
+
+fn zero_minimum_segment_size_in(l: Arena) {
+  // the minimum segment size is clamped to the floor.
+  assert_eq!(l.minimum_segment_size(), 12);
+
+  // a tiny buffer is discarded rather than inserted into the freelist.
+  let tiny = l.alloc_bytes(16).unwrap();
+  let mut b = l.alloc_bytes(8).unwrap();
+  b.detach();
+  drop(tiny);
+  assert!(l.free_segments().is_empty());
+  assert_eq!(l.discarded(), 16);
+
+  // a buffer can hold the node and the floor is still reclaimed.
+  let first = l.alloc_bytes(20).unwrap();
+  let mut b = l.alloc_bytes(8).unwrap();
+  b.detach();
+  let offset = first.offset() as u32;
+  drop(first);
+  assert_eq!(l.free_segments(), [(offset, 20)]);
+
+  l.set_minimum_segment_size(0);
+  assert_eq!(l.minimum_segment_size(), 12);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn zero_minimum_segment_size() {
+  run(|| {
+    zero_minimum_segment_size_in(Arena::new(
+      ArenaOptions::new()
+        .with_maximum_alignment(1)
+        .with_minimum_segment_size(0),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn zero_minimum_segment_size_unify() {
+  run(|| {
+    zero_minimum_segment_size_in(Arena::new(
+      ArenaOptions::new()
+        .with_unify(true)
+        .with_maximum_alignment(1)
+        .with_minimum_segment_size(0),
+    ));
+  });
+}
+
+fn validate_free_list_in(l: Arena) {
+  let mut b = l.alloc_aligned_bytes::<u64>(1024).unwrap();
+  b.detach();
+  let base = b.offset() as u32;
+  assert_eq!(l.validate_free_list().unwrap().segments(), 0);
+
+  let set = |offset: u32, size: u32, next: u32| {
+    l.get_segment_node(offset)
+      .unwrap()
+      .store(encode_segment_node(size, next), Ordering::Release);
+  };
+  let head = |next: u32| {
+    l.header().sentinel.store(
+      encode_segment_node(SENTINEL_SEGMENT_NODE_SIZE, next),
+      Ordering::Release,
+    );
+  };
+  let node = SEGMENT_NODE_SIZE as u32;
+  let err = |offset, violation| Err(Error::CorruptedFreeList { offset, violation });
+
+  // segments of 200, 128 and 64 bytes, ordered as the freelist requires.
+  let (a, b, c) = (base, base + 256, base + 512);
+  let mut list = [(a, 200 - node), (b, 128 - node), (c, 64 - node)];
+  if let Freelist::Pessimistic = l.freelist {
+    list.reverse();
+  }
+  let build = |list: &[(u32, u32)]| {
+    head(list[0].0);
+    for (i, &(offset, size)) in list.iter().enumerate() {
+      let next = list
+        .get(i + 1)
+        .map_or(SENTINEL_SEGMENT_NODE_OFFSET, |&(offset, _)| offset);
+      set(offset, size, next);
+    }
+  };
+
+  build(&list);
+  let report = l.validate_free_list().unwrap();
+  assert_eq!(report.segments(), 3);
+  assert_eq!(report.free_bytes(), 392);
+
+  // cycle, a cycle of different sizes is always reported as unordered first.
+  head(a);
+  set(a, 64 - node, a);
+  assert!(matches!(
+    l.validate_free_list(),
+    Err(Error::CorruptedFreeList {
+      violation: FreeListViolation::Cycle,
+      ..
+    })
+  ));
+
+  // unaligned node
+  build(&list);
+  set(list[1].0, list[1].1, list[2].0 + 1);
+  assert_eq!(
+    l.validate_free_list(),
+    err(list[2].0 + 1, FreeListViolation::OutOfBounds)
+  );
+
+  // node out of the bounds
+  build(&list);
+  head(l.capacity() as u32);
+  assert_eq!(
+    l.validate_free_list(),
+    err(l.capacity() as u32, FreeListViolation::OutOfBounds)
+  );
+
+  // segment extends past the allocated memory
+  build(&list);
+  set(c, l.allocated() as u32 - c, SENTINEL_SEGMENT_NODE_OFFSET);
+  head(c);
+  assert_eq!(
+    l.validate_free_list(),
+    err(c, FreeListViolation::Unallocated)
+  );
+
+  // unordered
+  let mut unordered = list;
+  unordered.swap(0, 1);
+  build(&unordered);
+  assert_eq!(
+    l.validate_free_list(),
+    err(unordered[1].0, FreeListViolation::Unordered)
+  );
+
+  // overlapping segments of the same size
+  build(&[(a, 64 - node), (a + 32, 64 - node)]);
+  assert_eq!(
+    l.validate_free_list(),
+    err(a + 32, FreeListViolation::Overlapping)
+  );
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn validate_free_list_optimistic() {
+  run(|| {
+    validate_free_list_in(Arena::new(
+      ArenaOptions::new()
+        .with_capacity(4096)
+        .with_freelist(Freelist::Optimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn validate_free_list_pessimistic() {
+  run(|| {
+    validate_free_list_in(Arena::new(
+      ArenaOptions::new()
+        .with_unify(true)
+        .with_capacity(4096)
+        .with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn map_cow() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_map_cow");
+
+  let offset = {
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let mut b = a.alloc_bytes(5).unwrap();
+    b.put_slice(b"hello").unwrap();
+    b.detach();
+    b.offset()
+  };
+  let on_disk = std::fs::read(&p).unwrap();
+
+  {
+    let open_options = OpenOptions::default().read(true);
+    let a = Arena::map_cow(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(a.backend_kind(), BackendKind::MmapCow);
+    assert!(!a.read_only());
+    assert!(!a.is_persistent());
+    assert_eq!(unsafe { a.get_bytes(offset, 5) }, b"hello");
+
+    unsafe { a.get_bytes_mut(offset, 5) }.copy_from_slice(b"world");
+    let mut b = a.alloc_bytes(5).unwrap();
+    b.put_slice(b"cow!!").unwrap();
+    b.detach();
+    a.flush().unwrap();
+    a.flush_async().unwrap();
+    assert_eq!(unsafe { a.get_bytes(offset, 5) }, b"world");
+  }
+
+  // the file on disk is never touched.
+  assert_eq!(std::fs::read(&p).unwrap(), on_disk);
+
+  // a missing file is not created.
+  let missing = dir.path().join("test_map_cow_missing");
+  let err = Arena::map_cow(
+    &missing,
+    ArenaOptions::new(),
+    OpenOptions::default().read(true),
+    MmapOptions::default(),
+  )
+  .unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+fn alloc_aligned_in(l: Arena) {
+  unsafe {
+    let mut a = l.alloc_aligned::<u32>(64).unwrap();
+    assert_eq!(a.as_mut_ptr().as_ptr() as usize % 64, 0);
+    a.write(10);
+    assert_eq!(*a.as_ref(), 10);
+    a.detach();
+
+    let mut b = l.alloc_aligned::<u64>(128).unwrap();
+    assert_eq!(b.as_mut_ptr().as_ptr() as usize % 128, 0);
+    b.write(20);
+    assert_eq!(*b.as_ref(), 20);
+    drop(b);
+
+    // sub-natural or non-power-of-two alignments are rejected.
+    assert!(matches!(
+      l.alloc_aligned::<u32>(2),
+      Err(Error::InvalidAlignment)
+    ));
+    assert!(matches!(
+      l.alloc_aligned::<u32>(48),
+      Err(Error::InvalidAlignment)
+    ));
+    assert!(matches!(
+      l.alloc_aligned::<u32>(0),
+      Err(Error::InvalidAlignment)
+    ));
+
+    // the main memory is consumed out, allocates from the freelist.
+    let freed = l.alloc_bytes(512).unwrap();
+    let mut guard = l.alloc_bytes(8).unwrap();
+    guard.detach();
+    drop(freed);
+    let mut rest = l.alloc_bytes(l.remaining() as u32).unwrap();
+    rest.detach();
+
+    let mut c = l.alloc_aligned::<u32>(64).unwrap();
+    assert_eq!(c.as_mut_ptr().as_ptr() as usize % 64, 0);
+    c.write(30);
+    assert_eq!(*c.as_ref(), 30);
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_aligned() {
+  run(|| {
+    alloc_aligned_in(Arena::new(ArenaOptions::new().with_capacity(4096)));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_aligned_unify() {
+  run(|| {
+    alloc_aligned_in(Arena::new(
+      ArenaOptions::new().with_unify(true).with_capacity(4096),
+    ));
+  });
+}
+
+fn try_offset_in(l: Arena) {
+  let mut b = l.alloc_bytes(16).unwrap();
+  b.detach();
+  let ptr = b.as_ptr();
+  assert!(l.contains_ptr(ptr));
+  assert_eq!(l.try_offset(ptr), Some(b.offset()));
+  assert_eq!(l.try_offset(ptr), Some(unsafe { l.offset(ptr) }));
+
+  // the bounds of the data section.
+  let range = l.data_ptr_range();
+  assert_eq!(l.try_offset(range.start), Some(l.data_offset()));
+  assert!(l.contains_ptr(unsafe { range.end.sub(1) }));
+  assert!(!l.contains_ptr(range.end));
+  assert_eq!(l.try_offset(range.end), None);
+  assert_eq!(l.try_offset(l.full_ptr_range().start), None);
+
+  // pointers from elsewhere.
+  let other = Arena::new(ArenaOptions::new());
+  let b = other.alloc_bytes(16).unwrap();
+  assert!(!l.contains_ptr(b.as_ptr()));
+  assert_eq!(l.try_offset(b.as_ptr()), None);
+  assert_eq!(l.try_offset(core::ptr::null()), None);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn try_offset() {
+  run(|| {
+    try_offset_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn try_offset_unify() {
+  run(|| {
+    try_offset_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn grow_to_in(mut l: Arena) {
+  let offset = {
+    let mut b = l.alloc_bytes(512).unwrap();
+    b.put_slice(&[1; 512]).unwrap();
+    b.detach();
+    b.offset()
+  };
+  assert!(l.alloc_bytes(1024).is_err());
+
+  // shared or shrinking ARENAs cannot grow.
+  let cloned = l.clone();
+  assert_eq!(
+    l.grow_to(4096).unwrap_err().kind(),
+    std::io::ErrorKind::WouldBlock
+  );
+  drop(cloned);
+  assert_eq!(
+    l.grow_to(512).unwrap_err().kind(),
+    std::io::ErrorKind::InvalidInput
+  );
+
+  let allocated = l.allocated();
+  let data_offset = l.data_offset();
+  l.grow_to(4096).unwrap();
+  assert_eq!(l.capacity(), 4096);
+  assert_eq!(l.allocated(), allocated);
+  assert_eq!(l.data_offset(), data_offset);
+  assert_eq!(unsafe { l.get_bytes(offset, 512) }, [1; 512]);
+
+  // the new tail is zeroed, and can be allocated.
+  let mut b = l.alloc_bytes(1024).unwrap();
+  assert!(b.iter().all(|&x| x == 0));
+  b.put_slice(&[2; 1024]).unwrap();
+  b.detach();
+  assert_eq!(unsafe { l.get_bytes(b.offset(), 1024) }, [2; 1024]);
+  assert_eq!(unsafe { l.get_bytes(offset, 512) }, [1; 512]);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn grow_to() {
+  grow_to_in(Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(1024)).unwrap());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn grow_to_unify() {
+  grow_to_in(
+    Arena::map_anon(
+      ArenaOptions::new().with_unify(true),
+      MmapOptions::new().len(1024),
+    )
+    .unwrap(),
+  );
+}
+
+#[test]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn grow_to_unsupported() {
+  let mut l = Arena::new(ArenaOptions::new());
+  assert_eq!(
+    l.grow_to(4096).unwrap_err().kind(),
+    std::io::ErrorKind::Unsupported
+  );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn ensure_capacity() {
+  let mut l = Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(1024)).unwrap();
+  let offset = {
+    let mut b = l.alloc_bytes(512).unwrap();
+    b.put_slice(&[1; 512]).unwrap();
+    b.detach();
+    b.offset()
+  };
+
+  // enough space, nothing to do.
+  let cap = l.capacity();
+  l.ensure_capacity(l.remaining() as u32).unwrap();
+  assert_eq!(l.capacity(), cap);
+
+  // a shared ARENA cannot grow.
+  let cloned = l.clone();
+  assert!(matches!(l.ensure_capacity(4096), Err(Error::Locked)));
+  drop(cloned);
+
+  l.ensure_capacity(4096).unwrap();
+  assert!(l.remaining() >= 4096);
+  assert_eq!(unsafe { l.get_bytes(offset, 512) }, [1; 512]);
+  let mut b = l.alloc_bytes(4096).unwrap();
+  b.detach();
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn ensure_capacity_unsupported() {
+  let mut l = Arena::new(ArenaOptions::new());
+  l.ensure_capacity(l.remaining() as u32).unwrap();
+  assert!(matches!(
+    l.ensure_capacity(4096),
+    Err(Error::InsufficientSpace { .. })
+  ));
+}
+
+fn alloc_bytes_timeout_in(l: Arena) {
+  let mut b = l.alloc_bytes_timeout(8, 0).unwrap().unwrap();
+  b.put_u64_le(8).unwrap();
+  assert!(l.alloc_bytes_timeout(0, 0).unwrap().unwrap().is_empty());
+
+  // consume the main memory out, then the allocations go to the freelist.
+  let freed = l.alloc_bytes(128).unwrap();
+  let mut guard = l.alloc_bytes(8).unwrap();
+  guard.detach();
+  drop(freed);
+  let mut rest = l.alloc_bytes(l.remaining() as u32).unwrap();
+  rest.detach();
+
+  // genuine lack of space is still an error.
+  assert!(matches!(
+    l.alloc_bytes_timeout(512, 16),
+    Err(Error::InsufficientSpace { .. })
+  ));
+
+  if let Freelist::Pessimistic = l.freelist {
+    assert!(l.alloc_bytes_timeout(32, 16).unwrap().is_some());
+    return;
+  }
+
+  // the head of the freelist is being removed by another thread forever.
+  let head = decode_segment_node(l.header().sentinel.load(Ordering::Acquire)).1;
+  let node = l.get_segment_node(head).unwrap();
+  let val = node.load(Ordering::Acquire);
+  let (_, next) = decode_segment_node(val);
+  node.store(
+    encode_segment_node(REMOVED_SEGMENT_NODE, next),
+    Ordering::Release,
+  );
+  assert!(l.alloc_bytes_timeout(32, 16).unwrap().is_none());
+
+  // the contention is gone.
+  node.store(val, Ordering::Release);
+  assert!(l.alloc_bytes_timeout(32, 16).unwrap().is_some());
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_timeout_optimistic() {
+  run(|| {
+    alloc_bytes_timeout_in(Arena::new(
+      ArenaOptions::new()
+        .with_capacity(4096)
+        .with_freelist(Freelist::Optimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_timeout_pessimistic() {
+  run(|| {
+    alloc_bytes_timeout_in(Arena::new(
+      ArenaOptions::new()
+        .with_capacity(4096)
+        .with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(all(feature = "std", not(feature = "loom")))]
+fn alloc_bytes_timeout_contention() {
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+
+  let a = Arena::new(ArenaOptions::new().with_capacity(16 * 1024));
+  // fill the freelist, and consume the main memory out.
+  let bufs = (0..64)
+    .map(|_| {
+      let b = a.alloc_bytes(64).unwrap();
+      let mut guard = a.alloc_bytes(8).unwrap();
+      guard.detach();
+      b
+    })
+    .collect::<std::vec::Vec<_>>();
+  drop(bufs);
+  let mut rest = a.alloc_bytes(a.remaining() as u32).unwrap();
+  rest.detach();
+
+  let outcomes = Arc::new([
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+  ]);
+  let handles = (0..8)
+    .map(|_| {
+      let a = a.clone();
+      let outcomes = outcomes.clone();
+      std::thread::spawn(move || {
+        for _ in 0..1000 {
+          let outcome = match a.alloc_bytes_timeout(32, 0) {
+            Ok(Some(_)) => 0,
+            // gives up due to the contention.
+            Ok(None) => 1,
+            // all the segments are taken by other threads.
+            Err(Error::InsufficientSpace { .. }) => 2,
+            Err(e) => panic!("unexpected error: {e}"),
+          };
+          outcomes[outcome].fetch_add(1, Ordering::Relaxed);
+        }
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  // never blocks, every thread finishes.
+  for h in handles {
+    h.join().unwrap();
+  }
+  let total = outcomes
+    .iter()
+    .map(|o| o.load(Ordering::Relaxed))
+    .sum::<usize>();
+  assert_eq!(total, 8000);
+  assert!(outcomes[0].load(Ordering::Relaxed) > 0);
+}
+
+#[cfg(all(feature = "std", not(feature = "loom")))]
+fn park_on_contention_in(a: Arena) {
+  // fill the freelist, and consume the main memory out.
+  let bufs = (0..64)
+    .map(|_| {
+      let b = a.alloc_bytes(64).unwrap();
+      let mut guard = a.alloc_bytes(8).unwrap();
+      guard.detach();
+      b
+    })
+    .collect::<std::vec::Vec<_>>();
+  drop(bufs);
+  let mut rest = a.alloc_bytes(a.remaining() as u32).unwrap();
+  rest.detach();
+
+  let handles = (0..8)
+    .map(|_| {
+      let a = a.clone();
+      std::thread::spawn(move || {
+        let mut allocated = 0;
+        for _ in 0..1000 {
+          match a.alloc_bytes(32) {
+            // dropped, so the memory goes back to the freelist.
+            Ok(mut b) => {
+              b.put_u64_le(u64::MAX).unwrap();
+              allocated += 1;
+            }
+            Err(Error::InsufficientSpace { .. }) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+          }
+
+          // the typed allocations go through the same slow path.
+          match unsafe { a.alloc::<[u64; 4]>() } {
+            Ok(mut v) => {
+              v.write([u64::MAX; 4]);
+              allocated += 1;
+            }
+            Err(Error::InsufficientSpace { .. }) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+          }
+        }
+        allocated
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  let allocated = handles
+    .into_iter()
+    .map(|h| h.join().unwrap())
+    .sum::<usize>();
+  assert!(allocated > 0);
+  assert!(a.validate_free_list().is_ok());
+}
+
+#[test]
+#[cfg(all(feature = "std", not(feature = "loom")))]
+fn park_on_contention() {
+  park_on_contention_in(Arena::new(
+    ArenaOptions::new()
+      .with_capacity(16 * 1024)
+      .with_park_on_contention(true),
+  ));
+}
+
+#[test]
+#[cfg(all(feature = "std", not(feature = "loom")))]
+fn park_on_contention_pessimistic() {
+  park_on_contention_in(Arena::new(
+    ArenaOptions::new()
+      .with_capacity(16 * 1024)
+      .with_freelist(Freelist::Pessimistic)
+      .with_park_on_contention(true),
+  ));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn arena_id() {
+  let a = Arena::new(ArenaOptions::new().with_capacity(4096).with_unify(true));
+  let b = Arena::new(ArenaOptions::new());
+  assert_ne!(a.id(), b.id());
+  assert_eq!(a.id(), a.clone().id());
+
+  let mut bytes = a.alloc_bytes(5).unwrap();
+  bytes.put_slice(b"hello").unwrap();
+  assert_eq!(bytes.arena_id(), a.id());
+  assert_eq!(unsafe { a.resolve_bytes(&bytes) }, b"hello");
+  assert_eq!(unsafe { a.clone().resolve_bytes(&bytes) }, b"hello");
+  drop(bytes);
+
+  let value = unsafe { b.alloc::<u64>().unwrap() };
+  assert_eq!(value.arena_id(), b.id());
+  drop(value);
+
+  let parts = a.partition(&[1024, 1024]).unwrap();
+  assert_ne!(parts[0].id(), a.id());
+  assert_ne!(parts[0].id(), parts[1].id());
+}
+
+#[test]
+#[cfg(all(debug_assertions, not(feature = "loom")))]
+#[should_panic(expected = "but resolved by the ARENA")]
+fn resolve_foreign_bytes() {
+  let a = Arena::new(ArenaOptions::new());
+  let b = Arena::new(ArenaOptions::new());
+  let bytes = a.alloc_bytes(5).unwrap();
+  let _ = unsafe { b.resolve_bytes(&bytes) };
+}
+
+fn reserve_segment_in(l: Arena) {
+  // dropped without being taken, the bytes are freed.
+  let reserved = l.reserve_segment(64).unwrap();
+  let mut guard = l.alloc_bytes(8).unwrap();
+  guard.detach();
+  drop(reserved);
+  assert_eq!(l.free_segments().len(), 1);
+
+  let reserved = l.reserve_segment(64).unwrap();
+  let offset = reserved.offset();
+  assert_eq!(reserved.capacity(), 64);
+
+  // fill the rest of the arena.
+  let mut rest = l.alloc_bytes(l.remaining() as u32).unwrap();
+  rest.detach();
+  assert!(l.alloc_bytes(64).is_err());
+
+  let mut b = reserved.take();
+  assert_eq!(b.offset(), offset);
+  assert_eq!(b.capacity(), 64);
+  b.put_slice(&[1; 64]).unwrap();
+  b.detach();
+  assert_eq!(unsafe { l.get_bytes(offset, 64) }, [1; 64]);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reserve_segment() {
+  run(|| {
+    reserve_segment_in(Arena::new(ArenaOptions::new().with_capacity(4096)));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reserve_segment_unify() {
+  run(|| {
+    reserve_segment_in(Arena::new(
+      ArenaOptions::new().with_unify(true).with_capacity(4096),
+    ));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn byte_order_mismatch() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_byte_order_mismatch");
+
+  {
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let a = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let mut b = a.alloc_bytes(8).unwrap();
+    b.detach();
+  }
+
+  let mut file = std::fs::read(&p).unwrap();
+  assert_eq!(file[BYTE_ORDER_OFFSET], NATIVE_ENDIAN);
+
+  // byte-swap the header, as if the file was created on a machine with the other byte order.
+  let header = mem::align_of::<Header>();
+  file[BYTE_ORDER_OFFSET] = if NATIVE_ENDIAN == LITTLE_ENDIAN {
+    BIG_ENDIAN
+  } else {
+    LITTLE_ENDIAN
+  };
+  file[header..header + 16]
+    .chunks_mut(8)
+    .for_each(|field| field.reverse());
+  file[header + 16..header + mem::size_of::<Header>()]
+    .chunks_mut(4)
+    .for_each(|field| field.reverse());
+  std::fs::write(&p, &file).unwrap();
+
+  let check = |err: std::io::Error| {
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("byte order"));
+  };
+  check(
+    Arena::map(
+      &p,
+      OpenOptions::default().read(true),
+      MmapOptions::default(),
+      0,
+    )
+    .unwrap_err(),
+  );
+  check(
+    Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      OpenOptions::default().read(true).write(true),
+      MmapOptions::default(),
+    )
+    .unwrap_err(),
+  );
+  check(Arena::peek_header(&p).unwrap_err());
+
+  // the file is never modified by the failed opens.
+  assert_eq!(std::fs::read(&p).unwrap(), file);
+
+  // an unknown byte order is rejected as well.
+  file[BYTE_ORDER_OFFSET] = 0;
+  std::fs::write(&p, &file).unwrap();
+  check(Arena::peek_header(&p).unwrap_err());
+}
+
+fn live_bytes_in(l: Arena) {
+  assert_eq!(l.live_bytes(), 0);
+
+  let freed = l.alloc_bytes(40).unwrap();
+  let mut b = l.alloc_bytes(60).unwrap();
+  b.detach();
+  let watermark = l.allocated();
+  assert_eq!(watermark, l.data_offset() + 100);
+  assert_eq!(l.live_bytes(), 100);
+
+  drop(freed);
+  assert_eq!(l.free_segments().len(), 1);
+  assert_eq!(l.allocated(), watermark);
+  assert_eq!(l.live_bytes(), 60);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn live_bytes() {
+  run(|| {
+    live_bytes_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn live_bytes_unify() {
+  run(|| {
+    live_bytes_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn spill_arena() {
+  run(|| {
+    let primary = Arena::new(ArenaOptions::new().with_capacity(128));
+    let arena = primary.with_spill(Arena::new(ArenaOptions::new()));
+
+    let mut handles = std::vec::Vec::new();
+    for i in 0..64u64 {
+      let mut b = arena.alloc_bytes(8).unwrap();
+      b.put_u64_le(i).unwrap();
+      b.detach();
+      handles.push((b.tier(), b.offset()));
+    }
+
+    assert!(handles.iter().any(|(t, _)| *t == Tier::Primary));
+    assert!(handles.iter().any(|(t, _)| *t == Tier::Spill));
+    assert_eq!(
+      handles.iter().position(|(t, _)| *t == Tier::Spill),
+      handles
+        .iter()
+        .rposition(|(t, _)| *t == Tier::Primary)
+        .map(|i| i + 1)
+    );
+
+    for (i, (tier, offset)) in handles.into_iter().enumerate() {
+      let buf = unsafe { arena.get_bytes(tier, offset, 8) };
+      assert_eq!(u64::from_le_bytes(buf.try_into().unwrap()), i as u64);
+    }
+
+    let err = Arena::new(ArenaOptions::new())
+      .with_spill(Arena::new(ArenaOptions::new()))
+      .alloc_bytes(1 << 20)
+      .err()
+      .unwrap();
+    assert!(matches!(err, Error::InsufficientSpace { .. }));
+  });
+}
+
+fn to_unified_in(l: Arena) {
+  let mut offsets = std::vec::Vec::new();
+  for i in 0..8u64 {
+    let mut b = l.alloc_bytes(16).unwrap();
+    b.put_u64_le(i).unwrap();
+    b.put_u64_le(i * 2).unwrap();
+    b.detach();
+    offsets.push(b.offset());
+  }
+  let freed = l.alloc_bytes(64).unwrap();
+  let mut tail = l.alloc_bytes(8).unwrap();
+  tail.detach();
+  drop(freed);
+  assert_eq!(l.free_segments().len(), 1);
+
+  let unified = l.to_unified().unwrap();
+  assert!(!unified.header_bytes().is_empty());
+  assert_ne!(unified.id(), l.id());
+  assert_eq!(unified.freelist, l.freelist);
+  assert!(unified.data_offset() >= l.data_offset());
+
+  let shift = unified.data_offset() - l.data_offset();
+  assert_eq!(unified.allocated(), l.allocated() + shift);
+  assert_eq!(unified.live_bytes(), l.live_bytes());
+  for (i, offset) in offsets.into_iter().enumerate() {
+    let buf = unsafe { unified.get_bytes(offset + shift, 16) };
+    assert_eq!(u64::from_le_bytes(buf[..8].try_into().unwrap()), i as u64);
+    assert_eq!(
+      u64::from_le_bytes(buf[8..].try_into().unwrap()),
+      i as u64 * 2
+    );
+  }
+
+  // the freelist links have been moved with the data.
+  let report = unified.validate_free_list().unwrap();
+  assert_eq!(report.segments(), 1);
+  let b = unified.alloc_bytes(32).unwrap();
+  let expected = l.alloc_bytes(32).unwrap();
+  assert_eq!(b.offset(), expected.offset() + shift);
+
+  // unify ARENAs are rejected.
+  assert_eq!(unified.to_unified().err(), Some(Error::IncompatibleArena));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn to_unified() {
+  run(|| {
+    to_unified_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn to_unified_pessimistic() {
+  run(|| {
+    to_unified_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn to_unified_data_alignment() {
+  run(|| {
+    to_unified_in(Arena::new(ArenaOptions::new().with_data_alignment(64)));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn on_event() {
+  use std::sync::{Arc, Mutex};
+
+  let events: Arc<Mutex<std::vec::Vec<ArenaEvent>>> = Arc::new(Mutex::new(std::vec::Vec::new()));
+  let e = events.clone();
+  let a = Arena::new(ArenaOptions::new())
+    .with_on_event(Arc::new(move |event| e.lock().unwrap().push(event)));
+
+  let first = a.alloc_bytes(40).unwrap();
+  let (offset, size) = (first.memory_offset() as u32, first.memory_capacity() as u32);
+  let mut second = a.alloc_bytes(60).unwrap();
+  second.detach();
+  drop(first);
+
+  let events = core::mem::take(&mut *events.lock().unwrap());
+  assert_eq!(events.len(), 4, "{events:?}");
+  assert_eq!(events[0], ArenaEvent::Alloc { offset, size });
+  assert!(matches!(events[1], ArenaEvent::Alloc { size: 60, .. }));
+  assert_eq!(events[2], ArenaEvent::Dealloc { offset, size });
+  // the segment node of the freed region is accounted as discarded.
+  assert_eq!(
+    events[3],
+    ArenaEvent::Discard {
+      size: SEGMENT_NODE_SIZE as u32
+    }
+  );
+  assert_eq!(a.discarded(), SEGMENT_NODE_SIZE as u32);
+}
+
+fn try_exclusive_in(mut l: Arena) {
+  let mut b = l.alloc_bytes(16).unwrap();
+  b.put_u64_le(1).unwrap();
+  b.detach();
+  drop(b);
+
+  // an owned handle shares the ARENA.
+  {
+    let owned = l.alloc_bytes_owned(16).unwrap();
+    assert!(l.try_exclusive().is_none());
+    drop(owned);
+  }
+
+  {
+    let cloned = l.clone();
+    assert!(l.try_exclusive().is_none());
+    drop(cloned);
+  }
+
+  let allocated = l.allocated();
+  assert!(allocated > l.data_offset());
+  {
+    let mut guard = l.try_exclusive().unwrap();
+    guard.clear().unwrap();
+    assert_eq!(guard.arena().allocated(), guard.arena().data_offset());
+    assert_eq!(guard.prune_free_list(64), Ok(0));
+    assert_eq!(guard.consolidate(), Ok(0));
+    assert_eq!(guard.reclaim_tail(), Ok(0));
+
+    // the clones made while the guard is alive break the exclusivity.
+    let cloned = guard.arena().clone();
+    assert_eq!(guard.clear(), Err(Error::Locked));
+    drop(cloned);
+  }
+
+  let b = l.alloc_bytes(16).unwrap();
+  assert_eq!(b.offset(), l.data_offset());
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn try_exclusive() {
+  run(|| {
+    try_exclusive_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn try_exclusive_unify() {
+  run(|| {
+    try_exclusive_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+fn alloc_uninit_slice_in(mut l: Arena) {
+  let mut slice = unsafe { l.alloc_uninit_slice::<u64>(16).unwrap() };
+  assert_eq!(slice.len(), 16);
+  assert!(!slice.is_initialized());
+  assert_eq!(slice.offset() % mem::align_of::<u64>(), 0);
+  for (i, slot) in slice.as_uninit_slice_mut().iter_mut().enumerate() {
+    slot.write(i as u64 * 3);
+  }
+  unsafe { slice.assume_init() };
+  for (i, val) in slice.as_slice().iter().enumerate() {
+    assert_eq!(*val, i as u64 * 3);
+  }
+  slice.as_mut_slice()[0] = 42;
+
+  let offset = slice.offset();
+  let bytes = unsafe { l.get_bytes(offset, 16 * 8) };
+  assert_eq!(u64::from_ne_bytes(bytes[..8].try_into().unwrap()), 42);
+  assert_eq!(u64::from_ne_bytes(bytes[120..].try_into().unwrap()), 45);
+  drop(slice);
+
+  // the elements are dropped once they are initialized.
+  let counter = std::sync::Arc::new(());
+  {
+    let mut slice = unsafe { l.alloc_uninit_slice::<std::sync::Arc<()>>(4).unwrap() };
+    for slot in slice.as_uninit_slice_mut() {
+      slot.write(counter.clone());
+    }
+    unsafe { slice.assume_init() };
+    assert_eq!(std::sync::Arc::strong_count(&counter), 5);
+  }
+  assert_eq!(std::sync::Arc::strong_count(&counter), 1);
+
+  // ZST and empty slices do not allocate.
+  let allocated = l.allocated();
+  let mut zst = unsafe { l.alloc_uninit_slice::<()>(8).unwrap() };
+  assert_eq!(zst.len(), 8);
+  assert_eq!(zst.memory_size(), 0);
+  unsafe { zst.assume_init() };
+  assert_eq!(zst.as_slice().len(), 8);
+  let empty = unsafe { l.alloc_uninit_slice::<u64>(0).unwrap() };
+  assert!(empty.is_empty());
+  assert_eq!(empty.memory_size(), 0);
+  assert_eq!(l.allocated(), allocated);
+  drop((zst, empty));
+
+  assert!(matches!(
+    unsafe { l.alloc_uninit_slice::<u64>(usize::MAX / 4) },
+    Err(Error::InsufficientSpace { .. })
+  ));
+  assert!(l.try_exclusive().is_some());
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_uninit_slice() {
+  run(|| {
+    alloc_uninit_slice_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_uninit_slice_unify() {
+  run(|| {
+    alloc_uninit_slice_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn read_consistent() {
+  use core::sync::atomic::{AtomicBool, Ordering};
+  use std::sync::Arc;
+
+  let a = Arc::new(Arena::new(
+    ArenaOptions::new()
+      .with_capacity(64 * 1024)
+      .with_seqlock(true),
+  ));
+  let done = Arc::new(AtomicBool::new(false));
+
+  let writer = {
+    let a = a.clone();
+    let done = done.clone();
+    std::thread::spawn(move || {
+      for i in 0..2000u32 {
+        let mut pinned = std::vec::Vec::new();
+        for j in 0..8 {
+          let b = a.alloc_bytes(32 + (i + j) % 64).unwrap();
+          if j % 2 == 0 {
+            pinned.push(b);
+          }
+        }
+        drop(pinned);
+        if i % 100 == 0 {
+          unsafe { a.clear().unwrap() };
+        }
+      }
+      done.store(true, Ordering::Release);
+    })
+  };
+
+  let view = || {
+    (
+      a.allocated(),
+      a.free_segments(),
+      a.discarded(),
+      a.free_list_bytes(),
+    )
+  };
+  let mut reads = 0;
+  while !done.load(Ordering::Acquire) || reads == 0 {
+    // nothing is written between the two views, otherwise they would differ.
+    let Some((first, second)) = a.read_consistent(|| {
+      let first = view();
+      for _ in 0..16 {
+        core::hint::spin_loop();
+      }
+      (first, view())
+    }) else {
+      // gave up under the contention.
+      continue;
+    };
+    assert_eq!(first, second);
+    assert!(first.0 >= a.data_offset());
+    reads += 1;
+  }
+
+  writer.join().unwrap();
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn read_consistent_crashed_writer() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_read_consistent_crashed_writer");
+  let open_options = OpenOptions::default()
+    .create(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+  let opts = ArenaOptions::new().with_seqlock(true);
+  let a = Arena::map_mut(&p, opts, open_options.clone(), MmapOptions::default()).unwrap();
+
+  // a write which never finishes, like the process crashed in the middle of it.
+  mem::forget(a.header().begin_write());
+  assert!(a.read_consistent(|| a.allocated()).is_none());
+  drop(a);
+
+  let a = Arena::map_mut(&p, opts, open_options, MmapOptions::default()).unwrap();
+  assert_eq!(a.read_consistent(|| a.allocated()), Some(a.allocated()));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn seqlock_disabled() {
+  let a = Arena::new(ArenaOptions::new());
+  let seq = a.header().seq.load(Ordering::Acquire);
+  let mut b = a.alloc_bytes(8).unwrap();
+  b.detach();
+  assert_eq!(a.header().seq.load(Ordering::Acquire), seq);
+
+  let a = Arena::new(ArenaOptions::new().with_seqlock(true));
+  let seq = a.header().seq.load(Ordering::Acquire);
+  let mut b = a.alloc_bytes(8).unwrap();
+  b.detach();
+  assert_ne!(a.header().seq.load(Ordering::Acquire), seq);
+}
+
+fn data_mut_in(l: Arena) {
+  assert!(unsafe { l.data_mut() }.is_empty());
+
+  for size in [10, 20, 30] {
+    let mut b = l.alloc_bytes(size).unwrap();
+    b.detach();
+  }
+
+  let len = l.allocated() - l.data_offset();
+  {
+    let data = unsafe { l.data_mut() };
+    assert_eq!(data.len(), len);
+    for (i, byte) in data.iter_mut().enumerate() {
+      *byte = i as u8;
+    }
+  }
+
+  let data = l.data();
+  assert_eq!(data.len(), len);
+  assert!(data.iter().enumerate().all(|(i, byte)| *byte == i as u8));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn data_mut() {
+  run(|| {
+    data_mut_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn data_mut_unify() {
+  run(|| {
+    data_mut_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+#[should_panic(expected = "ARENA is read-only")]
+fn data_mut_read_only() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_data_mut_read_only");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+  drop(
+    Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap(),
+  );
+
+  let a = Arena::map(p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+  let _ = unsafe { a.data_mut() };
+}
+
+fn alloc_at_in(l: Arena) {
+  let data_offset = l.data_offset() as u32;
+
+  // reconstruct a layout of two allocations with a gap between them.
+  let (first, second) = (data_offset + 64, data_offset + 256);
+  let mut a = unsafe { l.alloc_at(second, 32).unwrap() };
+  a.put_u64_le(2).unwrap();
+  a.detach();
+  let mut b = unsafe { l.alloc_at(first, 32).unwrap() };
+  b.put_u64_le(1).unwrap();
+  b.detach();
+  assert_eq!(l.allocated(), second as usize + 32);
+  assert_eq!((a.offset(), b.offset()), (second as usize, first as usize));
+  assert!(b.offset() + b.capacity() <= a.offset());
+
+  assert_eq!(
+    unsafe { l.get_bytes(first as usize, 8) },
+    &1u64.to_le_bytes()
+  );
+  assert_eq!(
+    unsafe { l.get_bytes(second as usize, 8) },
+    &2u64.to_le_bytes()
+  );
+  assert!(l.validate_free_list().is_ok());
+
+  // the live regions, and the regions across the live ones are rejected.
+  for (offset, size) in [
+    (first, 8),
+    (first - 8, 16),
+    (second + 16, 32),
+    (first + 16, 256),
+  ] {
+    assert_eq!(
+      unsafe { l.alloc_at(offset, size) }.err(),
+      Some(Error::OverlappingRegions)
+    );
+  }
+
+  assert!(matches!(
+    unsafe { l.alloc_at(data_offset - 1, 8) },
+    Err(Error::OutOfBounds { .. })
+  ));
+  assert!(matches!(
+    unsafe { l.alloc_at(l.capacity() as u32 - 4, 8) },
+    Err(Error::OutOfBounds { .. })
+  ));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_at() {
+  run(|| {
+    alloc_at_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_at_pessimistic() {
+  run(|| {
+    alloc_at_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_at_unify() {
+  run(|| {
+    alloc_at_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+fn arena_view_in(l: Arena) {
+  let offset = {
+    let mut b = l.alloc_bytes(16).unwrap();
+    b.put_slice(b"hello").unwrap();
+    b.detach();
+    b.offset()
+  };
+
+  // make a segment in the freelist
+  {
+    let _segment = l.alloc_bytes(100).unwrap();
+    let mut b = l.alloc_bytes(8).unwrap();
+    b.detach();
+  }
+
+  let buf = l.memory().to_vec();
+  let view = ArenaView::from_bytes(&buf).unwrap();
+  assert_eq!(view.allocated(), l.allocated());
+  assert_eq!(view.discarded(), l.discarded());
+  assert_eq!(view.capacity(), l.capacity());
+  assert_eq!(view.data_offset(), l.data_offset());
+  assert_eq!(view.data(), l.data());
+  assert_eq!(view.freelist(), l.freelist);
+  assert_eq!(view.minimum_segment_size(), l.minimum_segment_size());
+  assert_eq!(view.get_bytes(offset, 5), b"hello");
+  assert_eq!(view.free_segments(), l.free_segments());
+  assert_eq!(view.free_segments().len(), 1);
+
+  assert!(matches!(
+    ArenaView::from_bytes(&buf[..OVERHEAD - 1]),
+    Err(Error::OutOfBounds { .. })
+  ));
+  let mut corrupted = buf.clone();
+  corrupted[MAGIC_TEXT_OFFSET] = 0;
+  assert_eq!(
+    ArenaView::from_bytes(&corrupted).unwrap_err(),
+    Error::IncompatibleArena
+  );
+  let mut corrupted = buf.clone();
+  corrupted[BYTE_ORDER_OFFSET] = 0;
+  assert_eq!(
+    ArenaView::from_bytes(&corrupted).unwrap_err(),
+    Error::IncompatibleArena
+  );
+  assert_eq!(
+    ArenaView::from_bytes(&buf[..l.allocated() - 1]).unwrap_err(),
+    Error::IncompatibleArena
+  );
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn arena_view() {
+  run(|| {
+    arena_view_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn arena_view_pessimistic() {
+  run(|| {
+    arena_view_in(Arena::new(
+      ArenaOptions::new()
+        .with_unify(true)
+        .with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn arena_view_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_arena_view_file");
+  let open_options = OpenOptions::default()
+    .create_new(Some(ARENA_SIZE))
+    .read(true)
+    .write(true);
+  let l = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  )
+  .unwrap();
+  let mut b = l.alloc_bytes(5).unwrap();
+  b.put_slice(b"hello").unwrap();
+  b.detach();
+  l.flush().unwrap();
+
+  let buf = std::fs::read(&p).unwrap();
+  let view = ArenaView::from_bytes(&buf).unwrap();
+  assert_eq!(view.allocated(), l.allocated());
+  assert_eq!(view.get_bytes(b.offset(), 5), b"hello");
+}
+
+fn alloc_buddy_in(l: Arena) {
+  // a parent block of order 8, split into the buddies by the caller.
+  let mut parent = l.alloc_buddy(8).unwrap();
+  parent.detach();
+  let parent = parent.offset() as u32;
+  assert_eq!(parent % 256, 0);
+  let mut tail = l.alloc_bytes(8).unwrap();
+  tail.detach();
+
+  let has_segment = |offset: u32, size: u32| l.free_segments().contains(&(offset, size));
+
+  unsafe {
+    // the buddy is in use, no merge.
+    assert!(l.dealloc_buddy(parent, 6));
+    assert!(has_segment(parent, 64));
+    assert!(l.dealloc_buddy(parent + 128, 6));
+    assert!(has_segment(parent + 128, 64));
+
+    // merge back to order 7, the buddy of order 7 is still in use.
+    assert!(l.dealloc_buddy(parent + 64, 6));
+    assert!(has_segment(parent, 128));
+    assert!(!has_segment(parent, 64));
+
+    // merge twice, back to the parent order.
+    assert!(l.dealloc_buddy(parent + 192, 6));
+    assert!(has_segment(parent, 256));
+    assert!(!has_segment(parent, 128));
+    assert!(!has_segment(parent + 128, 64));
+  }
+  assert!(l.validate_free_list().is_ok());
+
+  // the freed parent can be carved into an aligned block again.
+  let remaining = l.remaining();
+  let mut rest = l.alloc_bytes(remaining as u32).unwrap();
+  rest.detach();
+  let block = l.alloc_buddy(7).unwrap();
+  assert_eq!(block.capacity(), 128);
+  assert_eq!(block.offset() % 128, 0);
+  assert!(block.offset() >= parent as usize && block.offset() + 128 <= parent as usize + 256);
+
+  assert_eq!(l.alloc_buddy(2).err(), Some(Error::InvalidAlignment));
+  assert_eq!(l.alloc_buddy(32).err(), Some(Error::InvalidAlignment));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_buddy() {
+  run(|| {
+    alloc_buddy_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_buddy_pessimistic() {
+  run(|| {
+    alloc_buddy_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_buddy_unify() {
+  run(|| {
+    alloc_buddy_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+struct Record {
+  id: u64,
+  len: u32,
+  flags: u16,
+  kind: u8,
+}
+
+fn write_at_in(l: Arena) {
+  let mut b = l.alloc_bytes(64).unwrap();
+  b.detach();
+  // an unaligned offset is aligned to the alignment of the struct.
+  let offset = b.offset() + 1;
+
+  let record = Record {
+    id: 42,
+    len: 7,
+    flags: 0xBEEF,
+    kind: 3,
+  };
+  unsafe {
+    l.write_at(offset, record).unwrap();
+    assert_eq!(l.read_at::<Record>(offset).unwrap(), record);
+    assert_eq!(l.read_at::<u64>(offset).unwrap(), 42);
+
+    let cap = l.capacity();
+    for offset in [0, l.data_offset() - 1, cap - 4, cap + 1, usize::MAX] {
+      assert!(matches!(
+        l.write_at(offset, record),
+        Err(Error::OutOfBounds { .. })
+      ));
+      assert!(matches!(
+        l.read_at::<Record>(offset),
+        Err(Error::OutOfBounds { .. })
+      ));
+    }
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn write_at() {
+  run(|| {
+    write_at_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn write_at_unify() {
+  run(|| {
+    write_at_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn write_at_read_only() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_write_at_read_only");
+  let offset = {
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let mut b = l.alloc_bytes(16).unwrap();
+    b.detach();
+    unsafe { l.write_at(b.offset(), 7u64).unwrap() };
+    l.flush().unwrap();
+    b.offset()
+  };
+
+  let l = Arena::map(&p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+  unsafe {
+    assert_eq!(l.read_at::<u64>(offset).unwrap(), 7);
+    assert_eq!(l.write_at(offset, 8u64), Err(Error::ReadOnly));
+  }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn advise_dontneed() {
+  const PAGE_SIZE: usize = 4096;
+
+  // the pages of an anonymous ARENA are zeroed.
+  let l = Arena::map_anon(ArenaOptions::new(), MmapOptions::new().len(1 << 16)).unwrap();
+  let mut b = l.alloc_bytes(4 * PAGE_SIZE as u32).unwrap();
+  b.put_slice(&[0xAA; 4 * PAGE_SIZE]).unwrap();
+  b.detach();
+  let offset = b.offset();
+  unsafe {
+    l.advise_dontneed(offset, 4 * PAGE_SIZE).unwrap();
+    let data = l.get_bytes(offset, 4 * PAGE_SIZE);
+    // the partial pages at the both ends are kept.
+    assert_eq!(data[0], 0xAA);
+    assert_eq!(data[4 * PAGE_SIZE - 1], 0xAA);
+    assert!(data.contains(&0));
+    assert!(l.advise_dontneed(l.capacity() - 1, 2).is_err());
+  }
+
+  // the ARENA keeps working.
+  let mut b = l.alloc_bytes(16).unwrap();
+  b.put_slice(&[1; 16]).unwrap();
+  assert_eq!(b.as_ref(), &[1; 16]);
+
+  // the offsets of a partition are relative to the partition.
+  let l = Arena::map_anon(
+    ArenaOptions::new().with_unify(true),
+    MmapOptions::new().len(1 << 16),
+  )
+  .unwrap();
+  let parts = l
+    .partition(&[4 * PAGE_SIZE as u32, 4 * PAGE_SIZE as u32])
+    .unwrap();
+  let mut a = parts[0].alloc_bytes(4 * PAGE_SIZE as u32).unwrap();
+  a.put_slice(&[0xAA; 4 * PAGE_SIZE]).unwrap();
+  let mut b = parts[1].alloc_bytes(4 * PAGE_SIZE as u32).unwrap();
+  b.put_slice(&[0xBB; 4 * PAGE_SIZE]).unwrap();
+  unsafe {
+    parts[1].advise_dontneed(b.offset(), 4 * PAGE_SIZE).unwrap();
+  }
+  assert!(a.as_ref().iter().all(|x| *x == 0xAA));
+  assert!(b.as_ref().contains(&0));
+  assert!(unsafe { parts[1].advise_dontneed(parts[1].capacity() - 1, 2) }.is_err());
+
+  // the pages of a file backed ARENA are read from the file again.
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_advise_dontneed");
+  let open_options = OpenOptions::default()
+    .create_new(Some(1 << 16))
+    .read(true)
+    .write(true);
+  let l = Arena::map_mut(
+    &p,
+    ArenaOptions::new(),
+    open_options,
+    MmapOptions::default(),
+  )
+  .unwrap();
+  let mut b = l.alloc_bytes(4 * PAGE_SIZE as u32).unwrap();
+  b.put_slice(&[0xAA; 4 * PAGE_SIZE]).unwrap();
+  b.detach();
+  unsafe {
+    l.advise_dontneed(b.offset(), 4 * PAGE_SIZE).unwrap();
+    assert_eq!(
+      l.get_bytes(b.offset(), 4 * PAGE_SIZE),
+      &[0xAA; 4 * PAGE_SIZE]
+    );
+  }
+
+  // a no-op for the `Vec` backed ARENAs.
+  let l = Arena::new(ArenaOptions::new());
+  unsafe { l.advise_dontneed(l.data_offset(), 16).unwrap() };
+}
+
+fn checked_accessors_in(l: Arena) {
+  let mut b = l.alloc_aligned_bytes::<u64>(8).unwrap();
+  b.put_u64_le(42).unwrap();
+  b.put_slice(b"hello").unwrap();
+  b.detach();
+  let offset = b.offset();
+  let allocated = l.allocated();
+
+  // checked_pointer
+  let ptr = l.checked_pointer(offset).unwrap();
+  assert_eq!(ptr, unsafe { l.get_pointer(offset) });
+  assert!(l.checked_pointer(allocated - 1).is_some());
+  for offset in [0, l.data_offset() - 1, allocated, l.capacity(), usize::MAX] {
+    assert!(l.checked_pointer(offset).is_none());
+  }
+
+  // checked_aligned_pointer
+  let ptr = l.checked_aligned_pointer::<u64>(offset).unwrap();
+  assert_eq!(ptr, unsafe { l.get_aligned_pointer::<u64>(offset) });
+  assert_eq!(unsafe { ptr.read() }, 42u64.to_le());
+  // the offset is aligned forward, past the live region.
+  assert!(l.checked_aligned_pointer::<u64>(allocated - 7).is_none());
+  assert!(l.checked_aligned_pointer::<u64>(offset + 1).is_some());
+  for offset in [0, allocated, usize::MAX] {
+    assert!(l.checked_aligned_pointer::<u64>(offset).is_none());
+  }
+
+  // try_bytes
+  assert_eq!(l.try_bytes(offset + 8, 5).unwrap(), b"hello");
+  assert_eq!(l.try_bytes(allocated, 0).unwrap(), &[] as &[u8]);
+  for (offset, size) in [
+    (0, 1),
+    (offset, allocated),
+    (allocated, 1),
+    (offset, usize::MAX),
+  ] {
+    assert!(matches!(
+      l.try_bytes(offset, size),
+      Err(Error::OutOfBounds { .. })
+    ));
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn checked_accessors() {
+  run(|| {
+    checked_accessors_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn checked_accessors_unify() {
+  run(|| {
+    checked_accessors_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+fn min_reclaimable_segment_size_in(l: Arena) {
+  assert_eq!(l.data_offset() % SEGMENT_NODE_ALIGNMENT, 0);
+
+  for min_segment_size in [0, 16, 64] {
+    l.set_minimum_segment_size(min_segment_size);
+    let size = l.min_reclaimable_segment_size();
+
+    // reclaimable at every offset.
+    let start = l.data_offset() as u32;
+    for offset in start..start + SEGMENT_NODE_ALIGNMENT as u32 {
+      assert!(l.validate_segment(offset, size));
+    }
+
+    // a byte less is not reclaimable at the worst offset.
+    assert!(!l.validate_segment(start + 1, size - 1));
+    // the aligned offset needs no padding.
+    assert!(l.validate_segment(start, size - (SEGMENT_NODE_ALIGNMENT as u32 - 1)));
+  }
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn min_reclaimable_segment_size() {
+  run(|| {
+    min_reclaimable_segment_size_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn min_reclaimable_segment_size_unify() {
+  run(|| {
+    min_reclaimable_segment_size_in(Arena::new(
+      ArenaOptions::new().with_unify(true).with_data_alignment(64),
+    ));
+  });
+}
+
+fn root_in(l: Arena) {
+  assert_eq!(l.root(), None);
+
+  let mut b = l.alloc_bytes(8).unwrap();
+  b.detach();
+  let offset = b.offset() as u32;
+  drop(b);
+
+  l.set_root(offset);
+  assert_eq!(l.root(), Some(offset));
+
+  l.set_root(0);
+  assert_eq!(l.root(), None);
+
+  l.set_root(offset);
+  unsafe { l.clear().unwrap() };
+  assert_eq!(l.root(), None);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn root() {
+  run(|| {
+    root_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn root_unify() {
+  run(|| {
+    root_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn root_mmap() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_root_mmap");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let offset = {
+      let mut b = l.alloc_bytes(5).unwrap();
+      b.put_slice(b"hello").unwrap();
+      b.detach();
+      b.offset() as u32
+    };
+    l.set_root(offset);
+    l.flush().unwrap();
+    drop(l);
+
+    let l = Arena::map(&p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    let root = l.root().unwrap();
+    assert_eq!(root, offset);
+    assert_eq!(unsafe { l.get_bytes(root as usize, 5) }, b"hello");
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn migrate_region() {
+  run(|| {
+    let l = Arena::new(ArenaOptions::new());
+    let dst = Arena::new(ArenaOptions::new().with_unify(true));
+    let offset = {
+      let mut b = l.alloc_bytes(5).unwrap();
+      b.put_slice(b"hello").unwrap();
+      b.detach();
+      b.offset() as u32
+    };
+
+    let new_offset = l.migrate_region(offset, 5, &dst).unwrap();
+    assert_eq!(unsafe { dst.get_bytes(new_offset as usize, 5) }, b"hello");
+    assert_eq!(l.migrate_region(offset, 0, &dst).unwrap(), 0);
+    assert!(matches!(
+      l.migrate_region(offset, ARENA_SIZE, &dst),
+      Err(Error::OutOfBounds { .. })
+    ));
+
+    // migrate within the same ARENA.
+    let new_offset = l.migrate_region(offset, 5, &l).unwrap();
+    assert_ne!(new_offset, offset);
+    assert_eq!(unsafe { l.get_bytes(new_offset as usize, 5) }, b"hello");
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn migrate_region_to_file() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_migrate_region_to_file");
+    let l = Arena::new(ArenaOptions::new());
+    let offset = {
+      let mut b = l.alloc_bytes(5).unwrap();
+      b.put_slice(b"hello").unwrap();
+      b.detach();
+      b.offset() as u32
+    };
+
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let dst = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let new_offset = l.migrate_region(offset, 5, &dst).unwrap();
+    dst.flush().unwrap();
+    drop(dst);
+
+    let dst = Arena::map(&p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    assert_eq!(unsafe { dst.get_bytes(new_offset as usize, 5) }, b"hello");
+
+    // a read-only destination can not be written.
+    assert!(matches!(
+      l.migrate_region(offset, 5, &dst),
+      Err(Error::ReadOnly)
+    ));
+  });
+}
+
+fn should_compact_in(l: Arena) {
+  assert!(!l.should_compact());
+
+  let threshold = (l.capacity() as f64 * 0.25).ceil() as u32;
+  l.increase_discarded(threshold / 2);
+  assert!(!l.should_compact());
+
+  // free memory which is too small to be a segment is discarded as well.
+  let b = l.alloc_bytes(8).unwrap();
+  let mut guard = l.alloc_bytes(8).unwrap();
+  guard.detach();
+  let discarded = l.discarded();
+  drop(b);
+  assert!(l.discarded() > discarded);
+
+  l.increase_discarded(threshold - 1 - l.discarded());
+  assert!(!l.should_compact());
+  l.increase_discarded(1);
+  assert!(l.should_compact());
+
+  unsafe { l.clear().unwrap() };
+  assert!(!l.should_compact());
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn should_compact() {
+  run(|| {
+    should_compact_in(Arena::new(
+      ArenaOptions::new().with_compaction_threshold(0.25),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn should_compact_unset() {
+  run(|| {
+    let l = Arena::new(ArenaOptions::new());
+    assert_eq!(l.compaction_threshold(), None);
+    l.increase_discarded(l.capacity() as u32);
+    assert!(!l.should_compact());
+  });
+}
+
+#[test]
+#[cfg(all(not(feature = "loom"), feature = "std"))]
+fn rcu_arena() {
+  use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  };
+
+  /// Creates an ARENA whose only allocation is filled with `generation`.
+  fn generation(generation: u8) -> (Arena, u32) {
+    let a = Arena::new(ArenaOptions::new());
+    let mut b = a.alloc_bytes(64).unwrap();
+    b.put_slice(&[generation; 64]).unwrap();
+    b.detach();
+    let offset = b.offset() as u32;
+    drop(b);
+    (a, offset)
+  }
+
+  let (a, offset) = generation(0);
+  let rcu = Arc::new(RcuArena::new(a));
+  let stop = Arc::new(AtomicBool::new(false));
+
+  let readers = (0..4)
+    .map(|_| {
+      let rcu = rcu.clone();
+      let stop = stop.clone();
+      std::thread::spawn(move || {
+        let mut last = 0;
+        while !stop.load(Ordering::Acquire) {
+          let guard = rcu.read();
+          let bytes = unsafe { guard.get_bytes(offset as usize, 64) };
+          // never torn, and the generations only move forward.
+          assert!(bytes.iter().all(|b| *b == bytes[0]));
+          assert!(bytes[0] >= last);
+          last = bytes[0];
+        }
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  for i in 1..=100u8 {
+    let (next, next_offset) = generation(i);
+    assert_eq!(next_offset, offset);
+    let old = rcu.swap(next);
+    // the readers of the old ARENA are drained, then it is safe to reclaim it.
+    let bytes = unsafe { old.get_bytes(offset as usize, 64) };
+    assert!(bytes.iter().all(|b| *b == i - 1));
+    assert_eq!(old.refs(), 1);
+    drop(old);
+  }
+
+  stop.store(true, Ordering::Release);
+  for r in readers {
+    r.join().unwrap();
+  }
+  assert_eq!(
+    unsafe { rcu.read().get_bytes(offset as usize, 64) },
+    &[100; 64]
+  );
+}
+
+fn fill_pattern_in(l: Arena) {
+  let mut b = l.alloc_bytes(300).unwrap();
+  b.detach();
+  let offset = b.offset();
+  drop(b);
+
+  l.fill_pattern(offset, 300, 7).unwrap();
+  assert!(l.verify_pattern(offset, 300, 7));
+  assert!(!l.verify_pattern(offset, 300, 8));
+  // the pattern wraps.
+  assert_eq!(unsafe { l.get_bytes(offset + 248, 2) }, &[255, 0]);
+
+  // a single-byte corruption.
+  unsafe { l.get_bytes_mut(offset + 100, 1)[0] ^= 1 };
+  assert!(!l.verify_pattern(offset, 300, 7));
+  assert!(l.verify_pattern(offset, 100, 7));
+
+  assert!(matches!(
+    l.fill_pattern(l.capacity(), 1, 0),
+    Err(Error::OutOfBounds { .. })
+  ));
+  assert!(!l.verify_pattern(l.capacity(), 1, 0));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn fill_pattern() {
+  run(|| {
+    fill_pattern_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn fill_pattern_unify() {
+  run(|| {
+    fill_pattern_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn fill_pattern_read_only() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_fill_pattern_read_only");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let offset = {
+      let mut b = l.alloc_bytes(16).unwrap();
+      b.detach();
+      b.offset()
+    };
+    l.fill_pattern(offset, 16, 42).unwrap();
+    l.flush().unwrap();
+    drop(l);
+
+    let l = Arena::map(&p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    assert!(l.verify_pattern(offset, 16, 42));
+    assert!(matches!(
+      l.fill_pattern(offset, 16, 42),
+      Err(Error::ReadOnly)
+    ));
+  });
+}
+
+fn generation_in(l: Arena) {
+  let generation = l.generation();
+  let old = l.alloc_bytes(8).unwrap();
+  assert!(old.is_valid());
+
+  unsafe { l.clear().unwrap() };
+  assert_eq!(l.generation(), generation + 1);
+  assert!(!old.is_valid());
+
+  let new = l.alloc_bytes(8).unwrap();
+  assert!(new.is_valid());
+  assert_eq!(new.offset(), old.offset());
+
+  // the stale buffer does not give the memory, which is owned by `new` now, back to the ARENA.
+  let allocated = l.allocated();
+  drop(old);
+  assert_eq!(l.allocated(), allocated);
+  assert!(l.free_segments().is_empty());
+  assert!(new.is_valid());
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn generation() {
+  run(|| {
+    generation_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn generation_unify() {
+  run(|| {
+    generation_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn generation_mmap() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_generation_mmap");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    generation_in(l.clone());
+    let generation = l.generation();
+    l.flush().unwrap();
+    drop(l);
+
+    // the generation is persisted.
+    let l = Arena::map(&p, OpenOptions::new().read(true), MmapOptions::default(), 0).unwrap();
+    assert_eq!(l.generation(), generation);
+  });
+}
+
+fn typed_offset_in(l: Arena) {
+  let mut b = l.alloc_aligned_bytes::<u64>(32).unwrap();
+  for i in 0..4u64 {
+    b.put_u64_le(i).unwrap();
+  }
+  b.detach();
+
+  let base = TypedOffset::<u64>::from_byte_offset(b.byte_offset()).unwrap();
+  assert_eq!(base.byte_offset(), b.byte_offset());
+  for i in 0..4u32 {
+    let offset = TypedOffset::<u64>::new(base.index() + i);
+    assert_eq!(offset.byte_offset().get(), b.offset() as u32 + i * 8);
+    assert_eq!(unsafe { *l.get_ref(offset) }, u64::from_le(i as u64));
+  }
+
+  // a byte offset in the middle of an element.
+  assert!(TypedOffset::<u64>::from_byte_offset(ByteOffset::new(b.offset() as u32 + 4)).is_none());
+  assert!(TypedOffset::<()>::from_byte_offset(b.byte_offset()).is_none());
+  assert_eq!(
+    unsafe { l.get_bytes_at(b.byte_offset(), 8) },
+    0u64.to_le_bytes()
+  );
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn typed_offset() {
+  run(|| {
+    typed_offset_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn typed_offset_unify() {
+  run(|| {
+    typed_offset_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn snapshot_cow() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_snapshot_cow");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE * 16))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let offset = {
+      let mut b = l.alloc_bytes(8192).unwrap();
+      b.put_slice(&[1; 8192]).unwrap();
+      b.detach();
+      b.offset()
+    };
+    l.set_root(offset as u32);
+
+    let snapshot = l.snapshot_cow().unwrap();
+    assert_eq!(snapshot.backend_kind(), BackendKind::MmapCow);
+    assert_eq!(snapshot.allocated(), l.allocated());
+    assert_eq!(snapshot.root(), Some(offset as u32));
+
+    // the writes to the original, both the data and the header, are not visible in the snapshot.
+    l.fill_pattern(offset, 8192, 0).unwrap();
+    let mut b = l.alloc_bytes(16).unwrap();
+    b.detach();
+    l.flush().unwrap();
+    assert_ne!(snapshot.allocated(), l.allocated());
+    assert_eq!(unsafe { snapshot.get_bytes(offset, 8192) }, [1; 8192]);
+
+    // the writes to the snapshot never reach the file.
+    snapshot.fill_pattern(offset, 8192, 1).unwrap();
+    assert!(l.verify_pattern(offset, 8192, 0));
+
+    let l = Arena::new(ArenaOptions::new());
+    assert_eq!(
+      l.snapshot_cow().unwrap_err().kind(),
+      std::io::ErrorKind::Unsupported
+    );
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn capacity_for() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    for (i, data_alignment) in [1, 64].into_iter().enumerate() {
+      let opts = ArenaOptions::new().with_data_alignment(data_alignment);
+      let payload = 4096;
+      let cap = opts.capacity_for(payload, mem::align_of::<u64>());
+
+      let p = dir.path().join(std::format!("test_capacity_for_{i}"));
+      let open_options = OpenOptions::default()
+        .create_new(Some(cap))
+        .read(true)
+        .write(true);
+      let l = Arena::map_mut(&p, opts, open_options, MmapOptions::default()).unwrap();
+      assert_eq!(l.capacity(), cap as usize);
+
+      let b = l
+        .alloc_aligned_bytes::<u64>(payload - mem::size_of::<u64>() as u32)
+        .unwrap();
+      assert_eq!(b.capacity(), payload as usize);
+      assert_eq!(b.offset() % mem::align_of::<u64>(), 0);
+    }
+
+    assert_eq!(ArenaOptions::new().capacity_for(u32::MAX, 8), u32::MAX);
+  });
+}
+
+fn occupancy_bitmap_in(l: Arena) {
+  assert!(l.occupancy_bitmap(64).is_empty());
+
+  let blocks = (0..4)
+    .map(|_| l.alloc_bytes(64).unwrap())
+    .collect::<std::vec::Vec<_>>();
+  assert_eq!(l.allocated() - l.data_offset(), 256);
+  assert_eq!(l.occupancy_bitmap(64), [true; 4]);
+
+  let mut blocks = blocks.into_iter();
+  let first = blocks.next().unwrap();
+  let second = blocks.next().unwrap();
+  let third = blocks.next().unwrap();
+  let _fourth = blocks.next().unwrap();
+  drop(first);
+  drop(third);
+  assert_eq!(l.occupancy_bitmap(64), [false, true, false, true]);
+  assert_eq!(
+    l.occupancy_bitmap(32),
+    [false, false, true, true, false, false, true, true]
+  );
+  assert_eq!(l.occupancy_bitmap(128), [true, true]);
+  // the last bucket is shorter than the granularity.
+  assert_eq!(l.occupancy_bitmap(96), [true, true, true]);
+
+  // the adjacent free segments cover a whole bucket together.
+  drop(second);
+  assert_eq!(l.occupancy_bitmap(128), [false, true]);
+  assert_eq!(l.occupancy_bitmap(192), [false, true]);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn occupancy_bitmap() {
+  run(|| {
+    occupancy_bitmap_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn occupancy_bitmap_unify() {
+  run(|| {
+    occupancy_bitmap_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
+
+fn reclaim_tail_in(l: Arena) {
+  let mut head = l.alloc_bytes(8).unwrap();
+  head.detach();
+  let a = l.alloc_bytes(256).unwrap();
+  let b = l.alloc_bytes(256).unwrap();
+  let c = l.alloc_bytes(256).unwrap();
+  let discarded = l.discarded();
+
+  drop(a);
+  drop(b);
+  // the last allocation goes back to the main memory, the segments of `a` and `b` are stranded before it.
+  drop(c);
+  assert_eq!(l.free_segments().len(), 2);
+  let allocated = l.allocated();
+  let remaining = l.remaining();
+  assert!(l.alloc_bytes((remaining + 512) as u32).is_err());
+
+  let reclaimed = unsafe { l.reclaim_tail().unwrap() };
+  assert_eq!(reclaimed, 512);
+  assert_eq!(l.allocated(), allocated - 512);
+  assert_eq!(l.remaining(), remaining + 512);
+  assert!(l.free_segments().is_empty());
+  assert_eq!(l.discarded(), discarded);
+  assert!(l.validate_free_list().is_ok());
+
+  // nothing to reclaim.
+  assert_eq!(unsafe { l.reclaim_tail().unwrap() }, 0);
+
+  // the reclaimed tail serves a large contiguous request.
+  let big = l.alloc_bytes((remaining + 512) as u32).unwrap();
+  assert_eq!(big.offset(), head.offset() + 8);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reclaim_tail_optimistic() {
+  run(|| {
+    reclaim_tail_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reclaim_tail_pessimistic() {
+  run(|| {
+    reclaim_tail_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn reclaim_tail_read_only() {
+  run(|| {
+    let l = Arena::new(ArenaOptions::new()).freeze();
+    assert_eq!(unsafe { l.reclaim_tail() }, Err(Error::ReadOnly));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn hugetlbfs() {
+  run(|| {
+    // best-effort, skipped if no hugetlbfs is mounted, or no huge pages are available.
+    let Some(mount) = std::fs::read_to_string("/proc/mounts")
+      .unwrap_or_default()
+      .lines()
+      .map(|line| line.split_whitespace().collect::<std::vec::Vec<_>>())
+      .find(|fields| fields.get(2) == Some(&"hugetlbfs"))
+      .map(|fields| std::path::PathBuf::from(fields[1]))
+    else {
+      return;
+    };
+
+    let p = mount.join(std::format!("rarena_test_hugetlbfs_{}", std::process::id()));
+    let _ = std::fs::remove_file(&p);
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true)
+      .hugetlbfs(true);
+    let res = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    );
+    let l = match res {
+      Ok(l) => l,
+      Err(_) => {
+        let _ = std::fs::remove_file(&p);
+        return;
+      }
+    };
+    l.remove_on_drop(true);
+
+    // the length of the file is rounded up to the huge page size.
+    assert!(l.capacity() > ARENA_SIZE as usize);
+    assert_eq!(l.capacity() % ARENA_SIZE as usize, 0);
+    let mut b = l.alloc_bytes(ARENA_SIZE).unwrap();
+    b.put_slice(&[1; ARENA_SIZE as usize]).unwrap();
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", target_os = "linux", not(feature = "loom")))]
+fn hugetlbfs_not_mounted() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_hugetlbfs_not_mounted");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true)
+      .hugetlbfs(true);
+    let err = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // a file which is not on hugetlbfs is not rounded up without the option.
+    let p = dir.path().join("test_hugetlbfs_not_mounted_plain");
+    let open_options = OpenOptions::default()
+      .create(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let l = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(l.capacity(), ARENA_SIZE as usize);
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn deterministic() {
+  run(|| {
+    fn layout() -> (std::vec::Vec<usize>, std::vec::Vec<u8>) {
+      let l = Arena::new(
+        ArenaOptions::new()
+          .with_unify(true)
+          .with_capacity(4096)
+          .with_deterministic(true),
+      );
+      assert!(l.serial.is_some());
+
+      let mut offsets = std::vec::Vec::new();
+      let mut live = std::vec::Vec::new();
+      for i in 0..16u8 {
+        let mut b = l.alloc_bytes(16 + i as u32 * 8).unwrap();
+        b.put_slice(&std::vec![i; b.capacity()]).unwrap();
+        offsets.push(b.offset());
+        live.push(b);
+      }
+
+      // free every third buffer, then allocate from the freelist.
+      let mut i = 0;
+      live.retain(|_| {
+        i += 1;
+        i % 3 != 0
+      });
+      for i in 0..8u8 {
+        let mut b = l.alloc_bytes(24 + i as u32 * 4).unwrap();
+        b.put_slice(&std::vec![0xF0 | i; b.capacity()]).unwrap();
+        b.detach();
+        offsets.push(b.offset());
+      }
+      live.into_iter().for_each(|mut b| b.detach());
+
+      (offsets, l.data().to_vec())
+    }
+
+    let (offsets, data) = layout();
+    assert_eq!(layout(), (offsets, data));
+  });
+}
+
+#[test]
+#[cfg(all(feature = "std", not(feature = "loom")))]
+fn deterministic_concurrent() {
+  let l = Arena::new(
+    ArenaOptions::new()
+      .with_capacity(64 * 1024)
+      .with_deterministic(true),
+  );
+
+  let handles = (0..4)
+    .map(|_| {
+      let l = l.clone();
+      std::thread::spawn(move || {
+        let mut offsets = std::vec::Vec::new();
+        for _ in 0..100 {
+          let b = l.alloc_bytes(64).unwrap();
+          let mut c = l.alloc_bytes(32).unwrap();
+          c.detach();
+          offsets.push(c.offset());
+          drop(b);
+        }
+        offsets
+      })
+    })
+    .collect::<std::vec::Vec<_>>();
+
+  let mut offsets = handles
+    .into_iter()
+    .flat_map(|h| h.join().unwrap())
+    .collect::<std::vec::Vec<_>>();
+  offsets.sort_unstable();
+  offsets.dedup();
+  assert_eq!(offsets.len(), 400);
+  assert!(l.validate_free_list().is_ok());
+}
+
+fn alloc_bytes_up_to_in(l: Arena) {
+  // less than the available space.
+  let b = l.alloc_bytes_up_to(16).unwrap();
+  assert_eq!(b.capacity(), 16);
+
+  // a free segment in the middle, which is smaller than the main memory.
+  let segment = l.alloc_bytes(128).unwrap();
+  let mut guard = l.alloc_bytes(8).unwrap();
+  guard.detach();
+  drop(segment);
+
+  let remaining = l.remaining();
+  let mut rest = l.alloc_bytes_up_to(remaining as u32 + 100).unwrap();
+  assert_eq!(rest.capacity(), remaining);
+  rest.put_slice(&std::vec![1; remaining]).unwrap();
+  rest.detach();
+  assert_eq!(l.remaining(), 0);
+
+  // the main memory is full, the largest free segment is used.
+  let mut b = l.alloc_bytes_up_to(1024).unwrap();
+  assert_eq!(b.capacity(), 128 - SEGMENT_NODE_SIZE);
+  b.detach();
+
+  assert!(matches!(
+    l.alloc_bytes_up_to(1024),
+    Err(Error::InsufficientSpace { .. })
+  ));
+  assert_eq!(l.alloc_bytes_up_to(0).unwrap().capacity(), 0);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_up_to() {
+  run(|| {
+    alloc_bytes_up_to_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_up_to_pessimistic() {
+  run(|| {
+    alloc_bytes_up_to_in(Arena::new(
+      ArenaOptions::new().with_freelist(Freelist::Pessimistic),
+    ));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn alloc_bytes_up_to_read_only() {
+  run(|| {
+    let l = Arena::new(ArenaOptions::new()).freeze();
+    assert!(matches!(l.alloc_bytes_up_to(8), Err(Error::ReadOnly)));
+  });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn auto_flush_interval() {
+  run(|| {
+    let dir = tempfile::tempdir().unwrap();
+    let p = dir.path().join("test_auto_flush_interval");
+    let open_options = OpenOptions::default()
+      .create_new(Some(ARENA_SIZE))
+      .read(true)
+      .write(true);
+    let opts = ArenaOptions::new().with_auto_flush_interval(Some(8));
+    let l = Arena::map_mut(&p, opts, open_options, MmapOptions::default()).unwrap();
+    let alloc = |l: &Arena, n: usize| {
+      for _ in 0..n {
+        let mut b = l.alloc_bytes(8).unwrap();
+        b.detach();
+      }
+    };
+
+    // a flush touches the header.
+    alloc(&l, 7);
+    assert!(l.last_modified().is_none());
+    alloc(&l, 1);
+    let first = l.last_modified().unwrap();
+
+    // the clones share the counter.
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    let cloned = l.clone();
+    alloc(&cloned, 7);
+    assert_eq!(l.last_modified(), Some(first));
+    alloc(&cloned, 1);
+    let second = l.last_modified().unwrap();
+    assert!(second > first);
+
+    // the failed allocations are not counted.
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    assert!(l.alloc_bytes(ARENA_SIZE * 2).is_err());
+    alloc(&l, 7);
+    assert_eq!(l.last_modified(), Some(second));
+    alloc(&l, 1);
+    assert!(l.last_modified().unwrap() > second);
+
+    // a no-op for the other backends.
+    let l = Arena::new(opts);
+    alloc(&l, 20);
+    assert!(l.last_modified().is_none());
+
+    assert_eq!(
+      ArenaOptions::new()
+        .with_auto_flush_interval(Some(0))
+        .auto_flush_interval(),
+      None
+    );
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn inline_bump_arena() {
+  let mut arena = InlineBumpArena::<1024>::new();
+  assert_eq!(arena.capacity(), 1024);
+
+  let a = arena.alloc_bytes(1000).unwrap();
+  assert_eq!(a.as_ptr() as usize % 8, 0);
+  assert_eq!(a, [0; 1000]);
+  a.fill(1);
+  let b = arena.alloc_bytes(16).unwrap();
+  b.fill(2);
+  assert_eq!(arena.remaining(), 8);
+
+  assert_eq!(
+    arena.alloc_bytes(9).unwrap_err(),
+    Error::InsufficientSpace {
+      requested: 9,
+      available: 8,
+    }
+  );
+  assert_eq!(arena.alloc_bytes(8).unwrap().len(), 8);
+  assert!(arena.alloc_bytes(1).is_err());
+
+  arena.reset(true);
+  assert_eq!(arena.allocated(), 0);
+  assert_eq!(arena.alloc_bytes(1024).unwrap(), [0; 1024]);
+}
+
+fn memory_unused_tail_in(l: Arena) {
+  let header = l.data_offset();
+  assert_eq!(l.memory().len(), l.capacity());
+  assert!(l.memory()[header..].iter().all(|b| *b == 0));
+
+  let mut b = l.alloc_bytes(256).unwrap();
+  b.put_slice(&[0xAB; 256]).unwrap();
+  b.detach();
+  assert_eq!(l.allocated_memory().len(), l.allocated());
+  assert!(l.memory()[l.allocated()..].iter().all(|b| *b == 0));
+
+  // the bytes reclaimed by `clear` are stale until they are allocated again.
+  unsafe { l.clear().unwrap() };
+  assert_eq!(l.allocated(), header);
+  assert!(l.memory()[header..].contains(&0xAB));
+
+  let mut rest = l.alloc_bytes(l.remaining() as u32).unwrap();
+  rest.detach();
+  assert!(l.memory()[header..].iter().all(|b| *b == 0));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn memory_unused_tail() {
+  run(|| {
+    memory_unused_tail_in(Arena::new(ArenaOptions::new()));
+  });
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn memory_unused_tail_unify() {
+  run(|| {
+    memory_unused_tail_in(Arena::new(ArenaOptions::new().with_unify(true)));
+  });
+}
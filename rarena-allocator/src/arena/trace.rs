@@ -0,0 +1,84 @@
+use std::{collections::VecDeque, sync::Mutex, vec::Vec};
+
+/// The kind of an event recorded in the allocation trace.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TraceOp {
+  /// Memory is allocated.
+  Alloc,
+  /// Memory is deallocated.
+  Dealloc,
+  /// Memory is discarded.
+  Discard,
+}
+
+/// An event recorded in the allocation trace, see [`Arena::drain_trace`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TraceEvent {
+  op: TraceOp,
+  offset: u32,
+  size: u32,
+}
+
+impl TraceEvent {
+  /// Creates a new trace event.
+  #[inline]
+  pub const fn new(op: TraceOp, offset: u32, size: u32) -> Self {
+    Self { op, offset, size }
+  }
+
+  /// Returns the kind of the event.
+  #[inline]
+  pub const fn op(&self) -> TraceOp {
+    self.op
+  }
+
+  /// Returns the offset of the memory, `0` if the offset is unknown,
+  /// e.g. discarded by [`Arena::increase_discarded`].
+  #[inline]
+  pub const fn offset(&self) -> u32 {
+    self.offset
+  }
+
+  /// Returns the size of the memory.
+  #[inline]
+  pub const fn size(&self) -> u32 {
+    self.size
+  }
+}
+
+/// A bounded ring buffer of trace events, the oldest events are dropped when it is full.
+#[derive(Debug)]
+pub(crate) struct Tracer {
+  cap: usize,
+  events: Mutex<VecDeque<TraceEvent>>,
+}
+
+impl Tracer {
+  #[inline]
+  pub(crate) fn new(cap: usize) -> Self {
+    Self {
+      cap,
+      events: Mutex::new(VecDeque::with_capacity(cap)),
+    }
+  }
+
+  #[inline]
+  pub(crate) const fn capacity(&self) -> usize {
+    self.cap
+  }
+
+  #[inline]
+  pub(crate) fn record(&self, op: TraceOp, offset: u32, size: u32) {
+    let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+    if events.len() == self.cap {
+      events.pop_front();
+    }
+    events.push_back(TraceEvent::new(op, offset, size));
+  }
+
+  #[inline]
+  pub(crate) fn drain(&self) -> Vec<TraceEvent> {
+    let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+    events.drain(..).collect()
+  }
+}
@@ -0,0 +1,191 @@
+use super::*;
+
+/// A read-only view of the raw bytes of a unify or file backed ARENA, which parses the header
+/// from the front of the buffer, without memory mapping, locking or any other machinery of [`Arena`].
+///
+/// The buffer must start at the start of the ARENA memory, e.g. the bytes returned by [`Arena::memory`]
+/// of a unify ARENA, or the contents of an ARENA file. The offsets of the allocations are valid in the view.
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions, ArenaView};
+///
+/// let arena = Arena::new(ArenaOptions::new().with_unify(true));
+/// let mut bytes = arena.alloc_bytes(5).unwrap();
+/// bytes.put_slice(b"hello").unwrap();
+/// bytes.detach();
+///
+/// let view = ArenaView::from_bytes(arena.memory()).unwrap();
+/// assert_eq!(view.allocated(), arena.allocated());
+/// assert_eq!(view.get_bytes(bytes.offset(), 5), b"hello");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ArenaView<'a> {
+  buf: &'a [u8],
+  freelist: Freelist,
+  magic_version: u16,
+  data_offset: u32,
+  allocated: u32,
+  discarded: u32,
+  min_segment_size: u32,
+  root: u64,
+  sentinel: u64,
+}
+
+impl<'a> ArenaView<'a> {
+  /// Parses the sanity bytes and the header at the front of the buffer.
+  ///
+  /// Returns [`Error::OutOfBounds`] if the buffer is too small to hold the header, or [`Error::IncompatibleArena`]
+  /// if the buffer is not an ARENA of the current version, or the header is not consistent with the buffer.
+  pub fn from_bytes(buf: &'a [u8]) -> Result<Self, Error> {
+    // the memory of an ARENA is aligned to the header, so the header is always right after the sanity bytes.
+    const HEADER_OFFSET: usize = mem::align_of::<Header>();
+
+    let len = buf.len();
+    if len < OVERHEAD {
+      return Err(Error::OutOfBounds {
+        offset: 0,
+        size: OVERHEAD as u32,
+        capacity: len.min(u32::MAX as usize) as u32,
+      });
+    }
+
+    let magic_version = u16::from_le_bytes(
+      buf[MAGIC_VERISON_OFFSET..MAGIC_VERISON_OFFSET + MAGIC_VERISON_SIZE]
+        .try_into()
+        .unwrap(),
+    );
+    let freelist =
+      Memory::check_sanity_bytes(None, magic_version, buf).map_err(|_| Error::IncompatibleArena)?;
+
+    // Safety: the buffer contains a whole header, and the header is plain old data.
+    let header = unsafe { ptr::read_unaligned(buf.as_ptr().add(HEADER_OFFSET).cast::<Header>()) };
+    let data_offset = header.data_offset;
+    let allocated = header.allocated.load(Ordering::Relaxed);
+    if (data_offset as usize) < OVERHEAD || data_offset > allocated || allocated as usize > len {
+      return Err(Error::IncompatibleArena);
+    }
+
+    Ok(Self {
+      buf,
+      freelist,
+      magic_version,
+      data_offset,
+      allocated,
+      discarded: header.discarded.load(Ordering::Relaxed),
+      min_segment_size: header.min_segment_size.load(Ordering::Relaxed),
+      root: header.root.load(Ordering::Relaxed),
+      sentinel: header.sentinel.load(Ordering::Relaxed),
+    })
+  }
+
+  /// Returns the number of bytes allocated by the ARENA, including the overhead.
+  #[inline]
+  pub const fn allocated(&self) -> usize {
+    self.allocated as usize
+  }
+
+  /// Returns the number of bytes discarded by the ARENA.
+  #[inline]
+  pub const fn discarded(&self) -> u32 {
+    self.discarded
+  }
+
+  /// Returns the capacity of the ARENA, which is the length of the buffer.
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    self.buf.len()
+  }
+
+  /// Returns the offset of the data section.
+  #[inline]
+  pub const fn data_offset(&self) -> usize {
+    self.data_offset as usize
+  }
+
+  /// Returns the data section of the ARENA, header is not included.
+  #[inline]
+  pub fn data(&self) -> &'a [u8] {
+    &self.buf[self.data_offset as usize..self.allocated as usize]
+  }
+
+  /// Returns the freelist kind stored in the sanity bytes.
+  #[inline]
+  pub const fn freelist(&self) -> Freelist {
+    self.freelist
+  }
+
+  /// Returns the magic version stored in the sanity bytes.
+  #[inline]
+  pub const fn magic_version(&self) -> u16 {
+    self.magic_version
+  }
+
+  /// Returns the minimum segment size stored in the header.
+  #[inline]
+  pub const fn minimum_segment_size(&self) -> u32 {
+    self.min_segment_size
+  }
+
+  /// Returns the root offset stored in the header, the same as [`Arena::root`].
+  #[inline]
+  pub const fn root(&self) -> Option<u32> {
+    match self.root {
+      0 => None,
+      offset => Some(offset as u32),
+    }
+  }
+
+  /// Returns `size` bytes at `offset` of the buffer.
+  ///
+  /// # Panics
+  /// - If `offset..offset + size` is out of the bounds of the buffer.
+  #[inline]
+  pub fn get_bytes(&self, offset: usize, size: usize) -> &'a [u8] {
+    if size == 0 {
+      return &[];
+    }
+
+    &self.buf[offset..offset + size]
+  }
+
+  /// Returns the segments in the freelist as `(offset, size)` pairs, in the order of the freelist,
+  /// the same as [`Arena::free_segments`].
+  ///
+  /// The walk stops at the first segment node which is out of the allocated memory.
+  pub fn free_segments(&self) -> std::vec::Vec<(u32, u32)> {
+    let mut segments = std::vec::Vec::new();
+    let (_, mut next_node_offset) = decode_segment_node(self.sentinel);
+    // bounded by the number of the segment nodes that fit in the buffer, in case of a cycle.
+    let mut remaining = self.buf.len() / SEGMENT_NODE_SIZE;
+    while next_node_offset != SENTINEL_SEGMENT_NODE_OFFSET && remaining > 0 {
+      let Some(node) = self.segment_node(next_node_offset) else {
+        break;
+      };
+      let (node_size, next) = decode_segment_node(node);
+      if node_size != REMOVED_SEGMENT_NODE {
+        segments.push((next_node_offset, node_size + SEGMENT_NODE_SIZE as u32));
+      }
+      next_node_offset = next;
+      remaining -= 1;
+    }
+    segments
+  }
+
+  #[inline]
+  fn segment_node(&self, offset: u32) -> Option<u64> {
+    let start = offset as usize;
+    if offset < self.data_offset
+      || start % SEGMENT_NODE_ALIGNMENT != 0
+      || start + SEGMENT_NODE_SIZE > self.allocated as usize
+    {
+      return None;
+    }
+
+    self.buf[start..start + SEGMENT_NODE_SIZE]
+      .try_into()
+      .ok()
+      .map(u64::from_ne_bytes)
+  }
+}
@@ -0,0 +1,148 @@
+//! Recoverable collections whose nodes live in the ARENA.
+//!
+//! The links between the nodes are stored as `u32` offsets instead of pointers,
+//! so a collection in a file backed ARENA can be recovered after reopening the file.
+
+use core::marker::PhantomData;
+
+use crate::{Arena, Error};
+
+/// The node of [`ArenaLinkedList`], `next` is the offset of the next node, `0` means the end of the list.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct Node<T> {
+  next: u32,
+  value: T,
+}
+
+/// A singly linked list whose nodes are allocated in the ARENA.
+///
+/// The list only stores the offset of its head, which can be persisted somewhere else and used to
+/// recover the list by [`ArenaLinkedList::from_head`].
+///
+/// # Example
+///
+/// ```rust
+/// use rarena_allocator::{Arena, ArenaOptions, collections::ArenaLinkedList};
+///
+/// let mut list = ArenaLinkedList::<u64>::new(Arena::new(ArenaOptions::new()));
+/// list.push_front(1).unwrap();
+/// list.push_front(2).unwrap();
+///
+/// assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 1]);
+/// ```
+#[derive(Debug)]
+pub struct ArenaLinkedList<T> {
+  arena: Arena,
+  head: u32,
+  _marker: PhantomData<T>,
+}
+
+impl<T: Copy> ArenaLinkedList<T> {
+  /// Creates an empty list in the given ARENA.
+  #[inline]
+  pub const fn new(arena: Arena) -> Self {
+    Self {
+      arena,
+      head: 0,
+      _marker: PhantomData,
+    }
+  }
+
+  /// Recovers a list from the offset of its head, which is returned by [`ArenaLinkedList::head`].
+  ///
+  /// # Safety
+  /// - `head` must be `0` or the offset of the head of a list of `T` in the ARENA.
+  #[inline]
+  pub const unsafe fn from_head(arena: Arena, head: u32) -> Self {
+    Self {
+      arena,
+      head,
+      _marker: PhantomData,
+    }
+  }
+
+  /// Returns the offset of the head of the list, `0` if the list is empty.
+  #[inline]
+  pub const fn head(&self) -> u32 {
+    self.head
+  }
+
+  /// Returns `true` if the list is empty.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.head == 0
+  }
+
+  /// Returns the ARENA of the list.
+  #[inline]
+  pub const fn arena(&self) -> &Arena {
+    &self.arena
+  }
+
+  /// Pushes a value to the front of the list, returns the offset of the new node.
+  pub fn push_front(&mut self, value: T) -> Result<u32, Error> {
+    // Safety: `Node<T>` is `Copy`, so it is inlined in the ARENA and never needs to be dropped.
+    let mut node = unsafe { self.arena.alloc::<Node<T>>()? };
+    node.write(Node {
+      next: self.head,
+      value,
+    });
+
+    // Safety: the node is written, and it is linked into the list, which owns the memory from now on.
+    unsafe { node.detach() };
+    self.head = node.offset() as u32;
+    Ok(self.head)
+  }
+
+  /// Returns an iterator over the values of the list, from the front to the back.
+  #[inline]
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter {
+      arena: &self.arena,
+      next: self.head,
+      _marker: PhantomData,
+    }
+  }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a ArenaLinkedList<T> {
+  type Item = &'a T;
+  type IntoIter = Iter<'a, T>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+/// An iterator over the values of [`ArenaLinkedList`].
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+  arena: &'a Arena,
+  next: u32,
+  _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T: Copy> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.next == 0 {
+      return None;
+    }
+
+    // Safety: the offset is either the head of the list or the link stored in a node of the list.
+    let node = unsafe {
+      &*self
+        .arena
+        .get_aligned_pointer::<Node<T>>(self.next as usize)
+    };
+    self.next = node.next;
+    Some(&node.value)
+  }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,66 @@
+use super::*;
+use crate::ArenaOptions;
+
+fn list_in(arena: Arena) -> u32 {
+  let mut list = ArenaLinkedList::<u64>::new(arena);
+  assert!(list.is_empty());
+  for i in 0..1000 {
+    list.push_front(i).unwrap();
+  }
+
+  assert!(list.iter().copied().eq((0..1000).rev()));
+  list.head()
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn linked_list() {
+  list_in(Arena::new(ArenaOptions::new().with_capacity(64 * 1024)));
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn linked_list_unify() {
+  list_in(Arena::new(
+    ArenaOptions::new()
+      .with_capacity(64 * 1024)
+      .with_unify(true),
+  ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm"), not(feature = "loom")))]
+fn linked_list_reopen() {
+  use crate::{MmapOptions, OpenOptions};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_linked_list_reopen");
+  let open_options = OpenOptions::default()
+    .create_new(Some(64 * 1024))
+    .read(true)
+    .write(true);
+
+  let head = {
+    let arena = Arena::map_mut(
+      &p,
+      ArenaOptions::new(),
+      open_options,
+      MmapOptions::default(),
+    )
+    .unwrap();
+    let head = list_in(arena.clone());
+    arena.flush().unwrap();
+    head
+  };
+
+  let arena = Arena::map(
+    &p,
+    OpenOptions::default().read(true),
+    MmapOptions::default(),
+    0,
+  )
+  .unwrap();
+  let list = unsafe { ArenaLinkedList::<u64>::from_head(arena, head) };
+  assert!(list.iter().copied().eq((0..1000).rev()));
+}
@@ -1,11 +1,11 @@
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg(feature = "memmap")]
 #[derive(Debug)]
 pub(crate) struct TooSmall {
   cap: usize,
   min_cap: usize,
 }
 
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg(feature = "memmap")]
 impl TooSmall {
   #[inline]
   pub(crate) const fn new(cap: usize, min_cap: usize) -> Self {
@@ -13,7 +13,7 @@ impl TooSmall {
   }
 }
 
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg(feature = "memmap")]
 impl core::fmt::Display for TooSmall {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     write!(
@@ -24,7 +24,7 @@ impl core::fmt::Display for TooSmall {
   }
 }
 
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg(feature = "memmap")]
 impl std::error::Error for TooSmall {}
 
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
@@ -91,6 +91,35 @@ impl core::fmt::Display for VersionMismatch {
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 impl std::error::Error for VersionMismatch {}
 
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[derive(Debug)]
+pub(crate) struct MinimumSegmentSizeMismatch {
+  expected: u32,
+  found: u32,
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl MinimumSegmentSizeMismatch {
+  #[inline]
+  pub(crate) const fn new(expected: u32, found: u32) -> Self {
+    Self { expected, found }
+  }
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl core::fmt::Display for MinimumSegmentSizeMismatch {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "minimum segment size mismatch: expected {}, but found {}.",
+      self.expected, self.found
+    )
+  }
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl std::error::Error for MinimumSegmentSizeMismatch {}
+
 /// Error indicating that the buffer does not have enough space to write bytes into.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct BufferTooSmall {
@@ -163,6 +192,33 @@ impl core::fmt::Display for NotEnoughBytes {
 #[cfg(feature = "std")]
 impl std::error::Error for NotEnoughBytes {}
 
+/// The violation found by [`Arena::validate_free_list`](crate::Arena::validate_free_list).
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
+pub enum FreeListViolation {
+  /// The freelist does not terminate.
+  Cycle,
+  /// The segment node is not aligned, or out of the bounds of the arena.
+  OutOfBounds,
+  /// The segment extends past the allocated memory of the arena.
+  Unallocated,
+  /// The segment is not ordered by size as the freelist kind requires.
+  Unordered,
+  /// The segment overlaps with another segment.
+  Overlapping,
+}
+
+impl core::fmt::Display for FreeListViolation {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Cycle => write!(f, "cycle detected"),
+      Self::OutOfBounds => write!(f, "node out of bounds"),
+      Self::Unallocated => write!(f, "segment extends past the allocated memory"),
+      Self::Unordered => write!(f, "segment out of order"),
+      Self::Overlapping => write!(f, "segment overlaps with another segment"),
+    }
+  }
+}
+
 /// An error indicating that the arena is full
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Error {
@@ -175,6 +231,41 @@ pub enum Error {
   },
   /// The arena is read-only
   ReadOnly,
+  /// The destination arena is not compatible with the source arena,
+  /// e.g. the data offsets or the freelist kinds are different, or the destination is not empty.
+  IncompatibleArena,
+  /// The segment cannot be inserted into the freelist, e.g. it is out of the allocated memory,
+  /// overlaps with another segment, or is too small to be a segment.
+  InvalidSegment {
+    /// The offset of the segment
+    offset: u32,
+    /// The size of the segment
+    size: u32,
+  },
+  /// The region is out of the bounds of the arena
+  OutOfBounds {
+    /// The offset of the region
+    offset: u32,
+    /// The size of the region
+    size: u32,
+    /// The capacity of the arena
+    capacity: u32,
+  },
+  /// The regions overlap with each other
+  OverlappingRegions,
+  /// The alignment is not a power of two, or less than the natural alignment of the type
+  InvalidAlignment,
+  /// The [`ExclusiveGuard`](crate::ExclusiveGuard) is no longer exclusive because the ARENA
+  /// has been cloned, or the ARENA can not grow because it is shared,
+  /// see [`Arena::ensure_capacity`](crate::Arena::ensure_capacity)
+  Locked,
+  /// The freelist is corrupted
+  CorruptedFreeList {
+    /// The offset of the first segment node violating the invariants
+    offset: u32,
+    /// The violated invariant
+    violation: FreeListViolation,
+  },
 }
 
 impl core::fmt::Display for Error {
@@ -189,6 +280,30 @@ impl core::fmt::Display for Error {
         requested, available
       ),
       Error::ReadOnly => write!(f, "Arena is read-only"),
+      Error::IncompatibleArena => write!(f, "Arena is not compatible with the source arena"),
+      Error::InvalidSegment { offset, size } => write!(
+        f,
+        "Invalid freelist segment: {} bytes at offset {}",
+        size, offset
+      ),
+      Error::OutOfBounds {
+        offset,
+        size,
+        capacity,
+      } => write!(
+        f,
+        "Region of {} bytes at offset {} is out of the bounds of the arena (capacity: {})",
+        size, offset, capacity
+      ),
+      Error::OverlappingRegions => write!(f, "Regions overlap with each other"),
+      Error::InvalidAlignment => write!(
+        f,
+        "Alignment must be a power of two and not less than the natural alignment"
+      ),
+      Error::Locked => write!(f, "Arena is locked or shared"),
+      Error::CorruptedFreeList { offset, violation } => {
+        write!(f, "Corrupted freelist: {} at offset {}", violation, offset)
+      }
     }
   }
 }
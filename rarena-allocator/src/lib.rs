@@ -2,6 +2,7 @@
 #![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![cfg_attr(docsrs, allow(unused_attributes))]
+#![cfg_attr(feature = "allocator-api", feature(allocator_api))]
 #![deny(missing_docs)]
 
 #[cfg(not(any(feature = "std", feature = "alloc")))]
@@ -22,6 +23,10 @@ pub use error::*;
 mod options;
 pub use options::*;
 
+#[cfg(feature = "collections")]
+#[cfg_attr(docsrs, doc(cfg(feature = "collections")))]
+pub mod collections;
+
 mod common {
   #[cfg(not(feature = "loom"))]
   pub(crate) use std::alloc::{alloc_zeroed, dealloc, Layout};
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 mod open_options;
 
@@ -5,6 +7,13 @@ mod open_options;
 #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
 pub use open_options::*;
 
+#[cfg(all(feature = "memmap", target_family = "wasm"))]
+mod wasm_mmap_options;
+
+#[cfg(all(feature = "memmap", target_family = "wasm"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", target_family = "wasm"))))]
+pub use wasm_mmap_options::*;
+
 /// Unknown freelist error.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 #[repr(transparent)]
@@ -48,16 +57,99 @@ impl TryFrom<u8> for Freelist {
   }
 }
 
+/// The action to take when the ARENA does not have enough space for an allocation,
+/// returned by the hook set by [`Arena::with_on_oom`](crate::Arena::with_on_oom).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OomAction {
+  /// Retry the allocation once more, e.g. the hook has freed some space out-of-band.
+  Retry,
+  /// Fail the allocation with [`Error::InsufficientSpace`](crate::Error::InsufficientSpace).
+  Fail,
+}
+
+/// The hook invoked when the ARENA does not have enough space for an allocation.
+#[derive(Clone)]
+pub(crate) struct OomHook(pub(crate) Arc<dyn Fn(u32) -> OomAction + Send + Sync>);
+
+impl core::fmt::Debug for OomHook {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.write_str("OomHook")
+  }
+}
+
+/// The hook invoked once when the used ratio of the ARENA first crosses the threshold.
+#[derive(Clone)]
+pub(crate) struct HighWaterHook {
+  pub(crate) ratio: f64,
+  pub(crate) f: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl core::fmt::Debug for HighWaterHook {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("HighWaterHook")
+      .field("ratio", &self.ratio)
+      .finish()
+  }
+}
+
+/// The event observed by the hook set by [`Arena::with_on_event`](crate::Arena::with_on_event).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArenaEvent {
+  /// A region is allocated.
+  Alloc {
+    /// The offset of the region
+    offset: u32,
+    /// The size of the region
+    size: u32,
+  },
+  /// A region is deallocated.
+  Dealloc {
+    /// The offset of the region
+    offset: u32,
+    /// The size of the region
+    size: u32,
+  },
+  /// Some bytes are discarded, e.g. too small to be a segment of the freelist.
+  Discard {
+    /// The number of the discarded bytes
+    size: u32,
+  },
+}
+
+/// The hook invoked on every allocation, deallocation and discard of the ARENA.
+#[derive(Clone)]
+pub(crate) struct EventHook(pub(crate) Arc<dyn Fn(ArenaEvent) + Send + Sync>);
+
+impl core::fmt::Debug for EventHook {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.write_str("EventHook")
+  }
+}
+
 /// Options for creating an ARENA
 #[derive(Debug, Clone, Copy)]
 pub struct ArenaOptions {
   maximum_alignment: usize,
+  data_alignment: usize,
   capacity: u32,
   minimum_segment_size: u32,
   maximum_retries: u8,
   magic_version: u16,
   unify: bool,
+  zero_on_free: bool,
+  strict_reopen: bool,
+  deterministic: bool,
+  seqlock: bool,
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  auto_flush_interval: Option<u32>,
+  compaction_threshold: Option<f64>,
   freelist: Freelist,
+  #[cfg(feature = "std")]
+  park_on_contention: bool,
+  #[cfg(feature = "trace")]
+  trace_capacity: usize,
+  #[cfg(feature = "numa")]
+  numa_node: Option<u32>,
 }
 
 impl Default for ArenaOptions {
@@ -73,12 +165,26 @@ impl ArenaOptions {
   pub const fn new() -> Self {
     Self {
       maximum_alignment: 8,
+      data_alignment: 1,
       capacity: 1024,
       minimum_segment_size: 20,
       maximum_retries: 5,
       unify: false,
       magic_version: 0,
+      zero_on_free: true,
+      strict_reopen: false,
+      deterministic: false,
+      seqlock: false,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      auto_flush_interval: None,
+      compaction_threshold: None,
       freelist: Freelist::Optimistic,
+      #[cfg(feature = "std")]
+      park_on_contention: false,
+      #[cfg(feature = "trace")]
+      trace_capacity: 0,
+      #[cfg(feature = "numa")]
+      numa_node: None,
     }
   }
 
@@ -107,6 +213,36 @@ impl ArenaOptions {
     self
   }
 
+  /// Set the alignment of the start of the data region of the ARENA.
+  ///
+  /// The data offset is padded up, so that [`Arena::data`](crate::Arena::data) and the allocations
+  /// at the start of the data region are aligned to this value, e.g. for SIMD or DMA.
+  /// For a file backed ARENA, the effective data offset is persisted in the header,
+  /// and this value is ignored when reopening.
+  ///
+  /// The data offset is always aligned to at least `8` bytes, so that the segment nodes of the freelist
+  /// at the start of the data region never need padding.
+  ///
+  /// The alignment must be a power of 2.
+  /// The default data alignment is `1`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_data_alignment(64);
+  /// ```
+  #[inline]
+  pub const fn with_data_alignment(mut self, alignment: usize) -> Self {
+    assert!(
+      alignment.is_power_of_two(),
+      "alignment must be a power of 2"
+    );
+    self.data_alignment = alignment;
+    self
+  }
+
   /// Set the capacity of the ARENA. This configuration will be ignored if the ARENA is backed by a memory map,
   /// see [`Arena::map_mut`](crate::alloc::Arena::map_mut), [`Arena::map`](crate::alloc::Arena::map) and [`Arena::map_anon`](crate::alloc::Arena::map_anon) for more details.
   ///
@@ -133,6 +269,9 @@ impl ArenaOptions {
   ///
   /// The default minimum segment size is `48 bytes`.
   ///
+  /// The value is clamped to at least `12` bytes (the size of a segment node plus a `u32`) when the ARENA
+  /// is created, so that the freelist is never bloated by the tiny segments which can never be used.
+  ///
   /// # Example
   ///
   /// ```
@@ -148,7 +287,8 @@ impl ArenaOptions {
 
   /// Set the maximum retries of the ARENA.
   ///
-  /// This value controls how many times the ARENA will retry to allocate from slow path.
+  /// This value controls how many times the ARENA will try to allocate from slow path.
+  /// `0` means the slow path is tried only once, without any retry, which is the same as `1`.
   ///
   /// The default maximum retries is `5`.
   ///
@@ -224,6 +364,217 @@ impl ArenaOptions {
     self
   }
 
+  /// Set if the ARENA zeroes the memory which was freed before handing it out again.
+  ///
+  /// If you always overwrite the whole allocation, zeroing is wasted work, set this value to `false`
+  /// to skip it. Then the memory reused from the freelist (or reclaimed by
+  /// [`Arena::clear`](crate::Arena::clear)) may contain stale bytes, and
+  /// [`Arena::alloc_bytes`](crate::Arena::alloc_bytes) no longer guarantees the returned memory is zeroed.
+  ///
+  /// The default value is `true`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_zero_on_free(false);
+  /// ```
+  #[inline]
+  pub const fn with_zero_on_free(mut self, zero_on_free: bool) -> Self {
+    self.zero_on_free = zero_on_free;
+    self
+  }
+
+  /// Set if the threads contending on the freelist are parked, instead of busy-waiting,
+  /// after exhausting the spins.
+  ///
+  /// A parked thread is unparked when the freelist is changed, or after a short timeout, so this
+  /// reduces the CPU wasted under sustained contention, at the cost of the latency of the wakeup.
+  ///
+  /// The default value is `false`, which means the threads always busy-wait.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_park_on_contention(true);
+  /// ```
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  #[inline]
+  pub const fn with_park_on_contention(mut self, park: bool) -> Self {
+    self.park_on_contention = park;
+    self
+  }
+
+  /// Set if all the allocations and the deallocations of the ARENA (and its clones) go through
+  /// a single serialized path, so that the offsets, and the layout of the memory, are reproducible
+  /// for a fixed sequence of calls, e.g. for the golden-file tests of the on-disk layout.
+  ///
+  /// **This is intended for testing only.** The ARENA is no longer lock-free, the concurrent
+  /// allocations spin on a lock. The determinism only covers the order of the calls, the concurrent
+  /// callers still race for the lock, so the sequence of calls must be fixed by the caller.
+  ///
+  /// The hooks of the ARENA, e.g. [`Arena::with_on_event`](crate::Arena::with_on_event) and
+  /// [`Arena::with_on_high_water`](crate::Arena::with_on_high_water), may be invoked while the lock is held,
+  /// so they must not allocate or deallocate in the ARENA (or its clones), otherwise they deadlock.
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_deterministic(true);
+  /// ```
+  #[inline]
+  pub const fn with_deterministic(mut self, deterministic: bool) -> Self {
+    self.deterministic = deterministic;
+    self
+  }
+
+  /// Set if the writers bump the seqlock in the header around the writes of the watermark and the freelist,
+  /// so that [`Arena::read_consistent`](crate::Arena::read_consistent) can observe a consistent view,
+  /// even from another process sharing the same file backed ARENA.
+  ///
+  /// This costs two extra atomic operations on the header for every allocation and deallocation.
+  /// The writes in progress left by a crashed process are reset when the file is opened by
+  /// [`Arena::map_mut`](crate::Arena::map_mut).
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_seqlock(true);
+  /// ```
+  #[inline]
+  pub const fn with_seqlock(mut self, seqlock: bool) -> Self {
+    self.seqlock = seqlock;
+    self
+  }
+
+  /// Set the number of the successful allocations after which the file backed ARENA is flushed by
+  /// [`Arena::flush_async`](crate::Arena::flush_async) automatically, which bounds the data lost on a crash.
+  ///
+  /// Only the ARENAs created by [`Arena::map_mut`](crate::Arena::map_mut) are flushed, this is a no-op for
+  /// the other backends. The errors of the automatic flushes are ignored, the next interval tries again.
+  ///
+  /// The default value is `None`, which means the ARENA is never flushed automatically, `Some(0)` is the same as `None`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_auto_flush_interval(Some(64));
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub const fn with_auto_flush_interval(mut self, interval: Option<u32>) -> Self {
+    self.auto_flush_interval = match interval {
+      Some(0) => None,
+      interval => interval,
+    };
+    self
+  }
+
+  /// Set if reopening a file backed ARENA with a different minimum segment size is an error.
+  ///
+  /// The minimum segment size is persisted in the header of a file backed ARENA. When reopening,
+  /// if the persisted value differs from [`ArenaOptions::minimum_segment_size`], then:
+  ///
+  /// - if `strict_reopen` is `false`, the persisted value is honored and the option is ignored.
+  /// - if `strict_reopen` is `true`, [`Arena::map_mut`](crate::Arena::map_mut) returns an error.
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_strict_reopen(true);
+  /// ```
+  #[inline]
+  pub const fn with_strict_reopen(mut self, strict_reopen: bool) -> Self {
+    self.strict_reopen = strict_reopen;
+    self
+  }
+
+  /// Set the ratio of the discarded bytes to the capacity of the ARENA, at which the ARENA
+  /// should be compacted, see [`Arena::should_compact`](crate::Arena::should_compact).
+  ///
+  /// The ARENA never compacts itself, the threshold only standardizes the decision.
+  ///
+  /// The default value is `None`, which means the ARENA never suggests compaction.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_compaction_threshold(0.25);
+  /// ```
+  #[inline]
+  pub const fn with_compaction_threshold(mut self, threshold: f64) -> Self {
+    self.compaction_threshold = Some(threshold);
+    self
+  }
+
+  /// Set the capacity of the allocation trace of the ARENA.
+  ///
+  /// If the capacity is greater than `0`, the ARENA records the allocations, deallocations and discards
+  /// into a bounded ring buffer, which can be drained by [`Arena::drain_trace`](crate::Arena::drain_trace).
+  /// When the buffer is full, the oldest events are dropped.
+  ///
+  /// The default value is `0`, which means no trace is recorded.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_trace_capacity(1024);
+  /// ```
+  #[cfg(feature = "trace")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+  #[inline]
+  pub const fn with_trace_capacity(mut self, trace_capacity: usize) -> Self {
+    self.trace_capacity = trace_capacity;
+    self
+  }
+
+  /// Set the NUMA node which the memory of the ARENA is bound to.
+  ///
+  /// This only works for the ARENAs created by [`Arena::new`](crate::Arena::new) and
+  /// `Arena::map_anon`, and is only supported on Linux (via `mbind`) for now.
+  /// If the binding fails, or the platform is not supported, the memory is allocated
+  /// with the default policy of the system.
+  ///
+  /// The default value is `None`, which means the memory is not bound to any NUMA node.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_numa_node(Some(0));
+  /// ```
+  #[cfg(feature = "numa")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "numa")))]
+  #[inline]
+  pub const fn with_numa_node(mut self, node: Option<u32>) -> Self {
+    self.numa_node = node;
+    self
+  }
+
   /// Get the maximum alignment of the ARENA.
   ///
   /// # Example
@@ -240,6 +591,22 @@ impl ArenaOptions {
     self.maximum_alignment
   }
 
+  /// Get the alignment of the start of the data region of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_data_alignment(64);
+  ///
+  /// assert_eq!(opts.data_alignment(), 64);
+  /// ```
+  #[inline]
+  pub const fn data_alignment(&self) -> usize {
+    self.data_alignment
+  }
+
   /// Get the capacity of the ARENA.
   ///
   /// # Example
@@ -256,6 +623,60 @@ impl ArenaOptions {
     self.capacity
   }
 
+  /// Returns the capacity of an ARENA in which `payload` bytes, aligned to `max_align`, can always be allocated,
+  /// accounting for the header overhead, the padding of the data offset to [`data_alignment`](Self::data_alignment)
+  /// and the worst-case padding of the allocation to `max_align`.
+  ///
+  /// This is the length to pass when the length of the memory is the whole capacity of the ARENA, e.g. the length of
+  /// a new file without [`OpenOptions::reserve_overhead`](crate::OpenOptions::reserve_overhead), or the length of an
+  /// anonymous memory map. A `Vec` backed ARENA reserves the overhead by itself, so [`with_capacity`](Self::with_capacity)
+  /// takes the payload directly.
+  ///
+  /// The result is saturated to `u32::MAX`.
+  ///
+  /// # Panics
+  /// - If `max_align` is not a power of 2.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new();
+  /// let cap = opts.capacity_for(1000, 8);
+  /// assert!(cap > 1000);
+  ///
+  /// # #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  /// # {
+  /// use rarena_allocator::{Arena, MmapOptions};
+  ///
+  /// let arena = Arena::map_anon(opts, MmapOptions::new().len(cap)).unwrap();
+  /// assert!(arena.alloc_aligned_bytes::<u64>(1000 - 8).is_ok());
+  /// # }
+  /// ```
+  #[inline]
+  pub const fn capacity_for(&self, payload: u32, max_align: usize) -> u32 {
+    use crate::arena::{OVERHEAD, SEGMENT_NODE_ALIGNMENT};
+
+    assert!(
+      max_align.is_power_of_two(),
+      "alignment must be a power of 2"
+    );
+
+    let data_alignment = if self.data_alignment > SEGMENT_NODE_ALIGNMENT {
+      self.data_alignment
+    } else {
+      SEGMENT_NODE_ALIGNMENT
+    };
+    let padding = (data_alignment - 1).saturating_add(max_align - 1);
+    let overhead = OVERHEAD.saturating_add(padding);
+    if overhead > u32::MAX as usize {
+      return u32::MAX;
+    }
+
+    payload.saturating_add(overhead as u32)
+  }
+
   /// Get the minimum segment size of the ARENA.
   ///
   /// # Example
@@ -273,7 +694,8 @@ impl ArenaOptions {
   }
 
   /// Get the maximum retries of the ARENA.
-  /// This value controls how many times the ARENA will retry to allocate from slow path.
+  /// This value controls how many times the ARENA will try to allocate from slow path.
+  /// `0` means the slow path is tried only once, without any retry, which is the same as `1`.
   ///
   /// The default maximum retries is `5`.
   ///
@@ -351,4 +773,170 @@ impl ArenaOptions {
   pub const fn freelist(&self) -> Freelist {
     self.freelist
   }
+
+  /// Get if the ARENA zeroes the memory which was freed before handing it out again.
+  ///
+  /// The default value is `true`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_zero_on_free(false);
+  ///
+  /// assert_eq!(opts.zero_on_free(), false);
+  /// ```
+  #[inline]
+  pub const fn zero_on_free(&self) -> bool {
+    self.zero_on_free
+  }
+
+  /// Get the ratio of the discarded bytes to the capacity of the ARENA, at which the ARENA should be compacted.
+  ///
+  /// The default value is `None`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_compaction_threshold(0.25);
+  ///
+  /// assert_eq!(opts.compaction_threshold(), Some(0.25));
+  /// ```
+  #[inline]
+  pub const fn compaction_threshold(&self) -> Option<f64> {
+    self.compaction_threshold
+  }
+
+  /// Get if the threads contending on the freelist are parked after exhausting the spins.
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_park_on_contention(true);
+  ///
+  /// assert_eq!(opts.park_on_contention(), true);
+  /// ```
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  #[inline]
+  pub const fn park_on_contention(&self) -> bool {
+    self.park_on_contention
+  }
+
+  /// Get if the allocations and the deallocations go through a single serialized path.
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_deterministic(true);
+  ///
+  /// assert_eq!(opts.deterministic(), true);
+  /// ```
+  #[inline]
+  pub const fn deterministic(&self) -> bool {
+    self.deterministic
+  }
+
+  /// Get if the writers bump the seqlock in the header.
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_seqlock(true);
+  ///
+  /// assert_eq!(opts.seqlock(), true);
+  /// ```
+  #[inline]
+  pub const fn seqlock(&self) -> bool {
+    self.seqlock
+  }
+
+  /// Get the number of the successful allocations after which the file backed ARENA is flushed automatically.
+  ///
+  /// The default value is `None`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_auto_flush_interval(Some(64));
+  ///
+  /// assert_eq!(opts.auto_flush_interval(), Some(64));
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  pub const fn auto_flush_interval(&self) -> Option<u32> {
+    self.auto_flush_interval
+  }
+
+  /// Get if reopening a file backed ARENA with a different minimum segment size is an error.
+  ///
+  /// The default value is `false`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_strict_reopen(true);
+  ///
+  /// assert_eq!(opts.strict_reopen(), true);
+  /// ```
+  #[inline]
+  pub const fn strict_reopen(&self) -> bool {
+    self.strict_reopen
+  }
+
+  /// Get the capacity of the allocation trace of the ARENA.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_trace_capacity(1024);
+  ///
+  /// assert_eq!(opts.trace_capacity(), 1024);
+  /// ```
+  #[cfg(feature = "trace")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+  #[inline]
+  pub const fn trace_capacity(&self) -> usize {
+    self.trace_capacity
+  }
+
+  /// Get the NUMA node which the memory of the ARENA is bound to.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::ArenaOptions;
+  ///
+  /// let opts = ArenaOptions::new().with_numa_node(Some(0));
+  ///
+  /// assert_eq!(opts.numa_node(), Some(0));
+  /// ```
+  #[cfg(feature = "numa")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "numa")))]
+  #[inline]
+  pub const fn numa_node(&self) -> Option<u32> {
+    self.numa_node
+  }
 }
@@ -11,6 +11,12 @@ pub struct OpenOptions {
   opts: StdOpenOptions,
   create: Option<u32>,
   create_new: Option<u32>,
+  size_hint: Option<u32>,
+  truncate: bool,
+  try_lock: bool,
+  direct_io: bool,
+  reserve_overhead: bool,
+  hugetlbfs: bool,
 }
 
 impl From<StdOpenOptions> for OpenOptions {
@@ -19,6 +25,12 @@ impl From<StdOpenOptions> for OpenOptions {
       opts,
       create_new: None,
       create: None,
+      size_hint: None,
+      truncate: false,
+      try_lock: false,
+      direct_io: false,
+      reserve_overhead: false,
+      hugetlbfs: false,
     }
   }
 }
@@ -58,6 +70,12 @@ impl OpenOptions {
       opts: StdOpenOptions::new(),
       create: None,
       create_new: None,
+      size_hint: None,
+      truncate: false,
+      try_lock: false,
+      direct_io: false,
+      reserve_overhead: false,
+      hugetlbfs: false,
     }
   }
 
@@ -150,6 +168,9 @@ impl OpenOptions {
   /// If a file is successfully opened with this option set it will truncate
   /// the file to opts length if it already exists.
   ///
+  /// A truncated file is regarded as a new file, its length is set to the size given by
+  /// [`OpenOptions::create`], [`OpenOptions::create_new`] or [`OpenOptions::size_hint`].
+  ///
   /// The file must be opened with write access for truncate to work.
   ///
   /// # Examples
@@ -162,6 +183,7 @@ impl OpenOptions {
   #[inline]
   pub fn truncate(mut self, truncate: bool) -> Self {
     self.opts.truncate(truncate);
+    self.truncate = truncate;
     self
   }
 
@@ -252,33 +274,286 @@ impl OpenOptions {
     self
   }
 
+  /// Sets the length of the file when it is created or truncated, if the size is not given by
+  /// [`OpenOptions::create`] or [`OpenOptions::create_new`].
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use rarena_allocator::OpenOptions;
+  ///
+  /// let opts = OpenOptions::new().read(true).write(true).truncate(true).size_hint(1000);
+  /// ```
+  #[inline]
+  pub fn size_hint(mut self, size: u32) -> Self {
+    self.size_hint = Some(size);
+    self
+  }
+
+  /// Sets the option to try to acquire an exclusive lock on the file when opening it.
+  ///
+  /// If the file is already locked by another handle (or another process),
+  /// opening the file fails immediately with [`io::ErrorKind::WouldBlock`]
+  /// instead of blocking. The lock is held until the ARENA is dropped.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use rarena_allocator::OpenOptions;
+  ///
+  /// let opts = OpenOptions::new().read(true).write(true).try_lock(true);
+  /// ```
+  #[inline]
+  pub fn try_lock(mut self, try_lock: bool) -> Self {
+    self.try_lock = try_lock;
+    self
+  }
+
+  /// Sets the option to bypass the page cache of the OS when opening the file, by `O_DIRECT` on Linux
+  /// and `FILE_FLAG_NO_BUFFERING` on Windows. The other platforms are not supported, opening the file
+  /// fails with [`io::ErrorKind::Unsupported`].
+  ///
+  /// The direct I/O requires the length of the file to be sector-aligned, so the size given by
+  /// [`OpenOptions::create`], [`OpenOptions::create_new`] or [`OpenOptions::size_hint`] must be
+  /// a multiple of `4096` bytes, otherwise opening the file fails with [`io::ErrorKind::InvalidInput`].
+  /// Some filesystems (e.g. tmpfs) do not support the direct I/O at all, opening the file fails with
+  /// [`io::ErrorKind::InvalidInput`] as well.
+  ///
+  /// **Note:** the flag only affects the I/O through the file handle, the memory map of the file
+  /// still goes through the page cache, so mixing them is unusual, this is mainly useful for
+  /// the writer which manages its own cache.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use rarena_allocator::OpenOptions;
+  ///
+  /// let opts = OpenOptions::new().read(true).write(true).create(Some(4096)).direct_io(true);
+  /// ```
+  #[inline]
+  pub fn direct_io(mut self, direct_io: bool) -> Self {
+    self.direct_io = direct_io;
+    self
+  }
+
+  /// Sets the option to regard the size given by [`OpenOptions::create`], [`OpenOptions::create_new`]
+  /// or [`OpenOptions::size_hint`] as the capacity hint of a new ARENA, instead of the length of the file.
+  ///
+  /// When a new file is created (or truncated) by [`Arena::map_mut`](crate::Arena::map_mut), it is sized to the
+  /// header overhead plus the hint (and the padding for the data alignment), so that the hint bytes can be allocated,
+  /// even if the hint is smaller than the header.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use rarena_allocator::OpenOptions;
+  ///
+  /// let opts = OpenOptions::new().read(true).write(true).create_new(Some(16)).reserve_overhead(true);
+  /// ```
+  #[inline]
+  pub fn reserve_overhead(mut self, reserve_overhead: bool) -> Self {
+    self.reserve_overhead = reserve_overhead;
+    self
+  }
+
+  /// Sets the option to expect the file on a hugetlbfs mount, so that the ARENA is backed by the explicit huge pages.
+  ///
+  /// The length of a file on hugetlbfs must be a multiple of the huge page size of the mount (e.g. `2MiB`),
+  /// so the size given by [`OpenOptions::create`], [`OpenOptions::create_new`] or [`OpenOptions::size_hint`] is rounded up
+  /// to the huge page size when the file is created (or truncated), and opening an existing file whose length is not
+  /// a multiple of the huge page size fails with [`io::ErrorKind::InvalidInput`]. A file on hugetlbfs is detected
+  /// on Linux even if this option is not set, this option makes opening a file which is not on hugetlbfs fail with
+  /// [`io::ErrorKind::InvalidInput`] as well. The other platforms are not supported, opening the file fails with
+  /// [`io::ErrorKind::Unsupported`].
+  ///
+  /// **Note:** the length given by [`MmapOptions::len`] must be a multiple of the huge page size as well,
+  /// and a file on hugetlbfs can not be shrunk by [`Arena::shrink_on_drop`](crate::Arena::shrink_on_drop).
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use rarena_allocator::OpenOptions;
+  ///
+  /// let opts = OpenOptions::new().read(true).write(true).create(Some(4096)).hugetlbfs(true);
+  /// ```
+  #[inline]
+  pub fn hugetlbfs(mut self, hugetlbfs: bool) -> Self {
+    self.hugetlbfs = hugetlbfs;
+    self
+  }
+
+  #[inline]
+  pub(crate) const fn reserves_overhead(&self) -> bool {
+    self.reserve_overhead
+  }
+
+  /// Sets the length of the file, which is rounded up to the huge page size if the file is on hugetlbfs.
+  pub(crate) fn set_len(&self, file: &File, len: u64) -> io::Result<()> {
+    match self.huge_page_size(file)? {
+      Some(page_size) => file.set_len((len + page_size - 1) / page_size * page_size),
+      None => file.set_len(len),
+    }
+  }
+
+  /// Returns the huge page size of the mount if the file is on hugetlbfs, or `None` if not.
+  ///
+  /// Returns [`io::ErrorKind::InvalidInput`] if the file is expected on hugetlbfs, but it is not.
+  #[cfg(target_os = "linux")]
+  fn huge_page_size(&self, file: &File) -> io::Result<Option<u64>> {
+    use std::os::unix::io::AsRawFd;
+
+    const HUGETLBFS_MAGIC: u32 = 0x958458f6;
+
+    let mut stat = core::mem::MaybeUninit::<libc::statfs>::uninit();
+    // Safety: the fd is valid, and the buffer is large enough.
+    if unsafe { libc::fstatfs(file.as_raw_fd(), stat.as_mut_ptr()) } != 0 {
+      return Err(io::Error::last_os_error());
+    }
+    // Safety: the buffer is initialized by `fstatfs`.
+    let stat = unsafe { stat.assume_init() };
+
+    // the type of `f_type` differs between the targets, only the low 32 bits are meaningful.
+    #[allow(clippy::unnecessary_cast)]
+    if stat.f_type as u32 == HUGETLBFS_MAGIC {
+      // the block size of hugetlbfs is the huge page size of the mount.
+      return Ok(Some(stat.f_bsize as u64));
+    }
+
+    if self.hugetlbfs {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "the file is not on a hugetlbfs mount",
+      ));
+    }
+    Ok(None)
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn huge_page_size(&self, _file: &File) -> io::Result<Option<u64>> {
+    if self.hugetlbfs {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "hugetlbfs is not supported on this platform",
+      ));
+    }
+    Ok(None)
+  }
+
   pub(crate) fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<(bool, File)> {
+    let (create_new, file) = self.open_in(path)?;
+    if let Some(page_size) = self.huge_page_size(&file)? {
+      let len = file.metadata()?.len();
+      if len % page_size != 0 {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidInput,
+          std::format!(
+            "the length of a file on hugetlbfs must be a multiple of the huge page size {page_size} bytes, but got {len}"
+          ),
+        ));
+      }
+    }
+    if self.try_lock {
+      fs4::FileExt::try_lock_exclusive(&file).map_err(|e| {
+        if e.raw_os_error() == fs4::lock_contended_error().raw_os_error() {
+          io::Error::new(io::ErrorKind::WouldBlock, e)
+        } else {
+          e
+        }
+      })?;
+    }
+    Ok((create_new, file))
+  }
+
+  fn open_in<P: AsRef<Path>>(&self, path: P) -> io::Result<(bool, File)> {
+    let opts = self.std_open_options()?;
+
     if let Some(size) = self.create_new {
-      return self
-        .opts
+      return opts
         .open(path)
-        .and_then(|f| f.set_len(size as u64).map(|_| (true, f)));
+        .map_err(|e| self.direct_io_error(e))
+        .and_then(|f| self.set_len(&f, size as u64).map(|_| (true, f)));
+    }
+
+    // the file is regarded as new if it is created or truncated, then the ARENA
+    // must initialize the header instead of recovering from the file.
+    let exists = path.as_ref().exists();
+    let size = self.create.or(self.size_hint);
+    opts
+      .open(path)
+      .map_err(|e| self.direct_io_error(e))
+      .and_then(|f| match size {
+        Some(size) if !exists || self.truncate => self.set_len(&f, size as u64).map(|_| (true, f)),
+        _ => Ok((exists && self.truncate, f)),
+      })
+  }
+
+  /// Returns the std open options with the platform specific flags applied.
+  fn std_open_options(&self) -> io::Result<StdOpenOptions> {
+    const DIRECT_IO_ALIGNMENT: u32 = 4096;
+
+    let mut opts = self.opts.clone();
+    if !self.direct_io {
+      return Ok(opts);
+    }
+
+    for size in [self.create_new, self.create, self.size_hint]
+      .into_iter()
+      .flatten()
+    {
+      if size % DIRECT_IO_ALIGNMENT != 0 {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidInput,
+          std::format!(
+            "the length of the file must be a multiple of {DIRECT_IO_ALIGNMENT} bytes for direct I/O, but got {size}"
+          ),
+        ));
+      }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+      use std::os::unix::fs::OpenOptionsExt;
+      opts.custom_flags(libc::O_DIRECT);
+      Ok(opts)
     }
 
-    if let Some(size) = self.create {
-      return if path.as_ref().exists() {
-        self.opts.open(path).map(|f| (false, f))
-      } else {
-        self
-          .opts
-          .open(path)
-          .and_then(|f| f.set_len(size as u64).map(|_| (true, f)))
-      };
+    #[cfg(windows)]
+    {
+      use std::os::windows::fs::OpenOptionsExt;
+      const FILE_FLAG_NO_BUFFERING: u32 = 0x20000000;
+      opts.custom_flags(FILE_FLAG_NO_BUFFERING);
+      Ok(opts)
     }
 
-    self.opts.open(path).map(|f| (false, f))
+    #[cfg(not(any(target_os = "linux", windows)))]
+    Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "direct I/O is not supported on this platform",
+    ))
+  }
+
+  /// Makes the error of opening a file on a filesystem which does not support the direct I/O clear.
+  fn direct_io_error(&self, e: io::Error) -> io::Error {
+    #[cfg(target_os = "linux")]
+    if self.direct_io && e.raw_os_error() == Some(libc::EINVAL) {
+      return io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "the filesystem does not support direct I/O",
+      );
+    }
+
+    e
   }
 }
 
 /// A memory map options for file backed [`SkipMap`](super::SkipMap),
 /// providing advanced options and flags for specifying memory map behavior.
 #[derive(Clone, Debug)]
-pub struct MmapOptions(Mmap2Options);
+pub struct MmapOptions {
+  opts: Mmap2Options,
+  /// The length set by [`MmapOptions::len`], `None` if not set or unknown.
+  len: Option<u32>,
+}
 
 impl Default for MmapOptions {
   fn default() -> Self {
@@ -288,7 +563,7 @@ impl Default for MmapOptions {
 
 impl From<Mmap2Options> for MmapOptions {
   fn from(opts: Mmap2Options) -> Self {
-    Self(opts)
+    Self { opts, len: None }
   }
 }
 
@@ -305,12 +580,16 @@ impl MmapOptions {
   /// ```
   #[inline]
   pub fn new() -> Self {
-    Self(Mmap2Options::new())
+    Self {
+      opts: Mmap2Options::new(),
+      len: None,
+    }
   }
 
   /// Configures the created memory mapped buffer to be `len` bytes long.
   ///
-  /// This option is mandatory for anonymous memory maps.
+  /// This option is mandatory for anonymous memory maps, the length is the capacity
+  /// of an ARENA created by `Arena::map_anon`.
   ///
   /// For file-backed memory maps, the length will default to the file length.
   ///
@@ -323,7 +602,8 @@ impl MmapOptions {
   /// ```
   #[inline]
   pub fn len(mut self, len: u32) -> Self {
-    self.0.len(len as usize);
+    self.opts.len(len as usize);
+    self.len = Some(len);
     self
   }
 
@@ -342,7 +622,7 @@ impl MmapOptions {
   /// ```
   #[inline]
   pub fn offset(mut self, offset: u32) -> Self {
-    self.0.offset(offset as u64);
+    self.opts.offset(offset as u64);
     self
   }
 
@@ -361,7 +641,7 @@ impl MmapOptions {
   /// ```
   #[inline]
   pub fn stack(mut self) -> Self {
-    self.0.stack();
+    self.opts.stack();
     self
   }
 
@@ -384,7 +664,7 @@ impl MmapOptions {
   /// ```
   #[inline]
   pub fn huge(mut self, page_bits: Option<u8>) -> Self {
-    self.0.huge(page_bits);
+    self.opts.huge(page_bits);
     self
   }
 
@@ -404,23 +684,33 @@ impl MmapOptions {
   /// ```
   #[inline]
   pub fn populate(mut self) -> Self {
-    self.0.populate();
+    self.opts.populate();
     self
   }
 
+  #[inline]
+  pub(crate) const fn get_len(&self) -> Option<u32> {
+    self.len
+  }
+
   #[inline]
   pub(crate) unsafe fn map(&self, file: &File) -> io::Result<memmap2::Mmap> {
-    self.0.map(file)
+    self.opts.map(file)
   }
 
   #[inline]
   pub(crate) unsafe fn map_mut(&self, file: &File) -> io::Result<memmap2::MmapMut> {
-    self.0.map_mut(file)
+    self.opts.map_mut(file)
+  }
+
+  #[inline]
+  pub(crate) unsafe fn map_copy(&self, file: &File) -> io::Result<memmap2::MmapMut> {
+    self.opts.map_copy(file)
   }
 
   #[inline]
   pub(crate) fn map_anon(&self) -> io::Result<memmap2::MmapMut> {
-    self.0.map_anon()
+    self.opts.map_anon()
   }
 }
 
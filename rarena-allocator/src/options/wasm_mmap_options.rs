@@ -0,0 +1,73 @@
+/// A memory map options for [`Arena::map_anon`](crate::Arena::map_anon) on `wasm` targets.
+///
+/// Memory maps are not available on `wasm`, so this type only records the length of the map,
+/// and [`Arena::map_anon`](crate::Arena::map_anon) falls back to a memory-only `Vec` backed ARENA
+/// of that size. The other configurations are accepted so that cross-platform code compiles,
+/// but they have no effect.
+#[derive(Clone, Debug, Default)]
+pub struct MmapOptions {
+  len: Option<u32>,
+}
+
+impl MmapOptions {
+  /// Creates a new set of options for configuring and creating a memory map.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::MmapOptions;
+  ///
+  /// // Create a new memory map options.
+  /// let mut mmap_options = MmapOptions::new();
+  /// ```
+  #[inline]
+  pub const fn new() -> Self {
+    Self { len: None }
+  }
+
+  /// Configures the created buffer to be `len` bytes long.
+  ///
+  /// This option is mandatory for anonymous memory maps.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use rarena_allocator::MmapOptions;
+  ///
+  /// let opts = MmapOptions::new().len(9);
+  /// ```
+  #[inline]
+  pub const fn len(mut self, len: u32) -> Self {
+    self.len = Some(len);
+    self
+  }
+
+  /// This option has no effect on `wasm`.
+  #[inline]
+  pub const fn offset(self, _offset: u32) -> Self {
+    self
+  }
+
+  /// This option has no effect on `wasm`.
+  #[inline]
+  pub const fn stack(self) -> Self {
+    self
+  }
+
+  /// This option has no effect on `wasm`.
+  #[inline]
+  pub const fn huge(self, _page_bits: Option<u8>) -> Self {
+    self
+  }
+
+  /// This option has no effect on `wasm`.
+  #[inline]
+  pub const fn populate(self) -> Self {
+    self
+  }
+
+  #[inline]
+  pub(crate) const fn get_len(&self) -> Option<u32> {
+    self.len
+  }
+}